@@ -12,7 +12,9 @@ mod source;
 
 pub use package_id::PackageId;
 pub use report::{
-    Count, CounterBlock, DependencyKind, PackageInfo, QuickReportEntry,
-    QuickSafetyReport, ReportEntry, SafetyReport, UnsafeInfo,
+    Count, CounterBlock, DependencyCondition, DependencyKind, ModuleEntry,
+    PackageInfo, PhaseDuration, QuickReportEntry, QuickSafetyReport,
+    ReportEntry, SafetyReport, ScanError, ScanErrorKind, ScanStats,
+    TargetEntry, UnsafeInfo,
 };
 pub use source::Source;
@@ -16,6 +16,10 @@ pub struct PackageInfo {
     pub dev_dependencies: HashSet<PackageId>,
     #[serde(serialize_with = "set_serde::serialize")]
     pub build_dependencies: HashSet<PackageId>,
+    /// Targets and features the dependencies are conditional on, only
+    /// present for conditional dependencies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependency_conditions: Vec<DependencyCondition>,
 }
 
 impl PackageInfo {
@@ -25,6 +29,7 @@ impl PackageInfo {
             dependencies: Default::default(),
             dev_dependencies: Default::default(),
             build_dependencies: Default::default(),
+            dependency_conditions: Default::default(),
         }
     }
 
@@ -37,6 +42,18 @@ impl PackageInfo {
     }
 }
 
+/// Conditions a dependency of a package is subject to
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DependencyCondition {
+    pub dependency: PackageId,
+    pub kind: DependencyKind,
+    /// Platform the dependency is limited to, a `cfg` expression or a target
+    /// triple
+    pub target: Option<String>,
+    /// Features of the package activating the optional dependency
+    pub features: Vec<String>,
+}
+
 /// Entry of the report generated from scanning for packages that forbid the use of `unsafe`
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct QuickReportEntry {
@@ -54,6 +71,12 @@ pub struct QuickSafetyReport {
     /// Packages that were not scanned successfully
     #[serde(serialize_with = "set_serde::serialize")]
     pub packages_without_metrics: HashSet<PackageId>,
+    /// Failures that make the report incomplete
+    #[serde(default)]
+    pub scan_errors: Vec<ScanError>,
+    /// Cost of the scan, only present when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ScanStats>,
 }
 
 /// Entry of the report generated from scanning for the use of `unsafe`
@@ -62,6 +85,39 @@ pub struct ReportEntry {
     pub package: PackageInfo,
     /// Unsafety scan results
     pub unsafety: UnsafeInfo,
+    /// Per-module unsafety scan results, only present when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modules: Option<Vec<ModuleEntry>>,
+    /// Per-target unsafety scan results, only present when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub targets: Option<Vec<TargetEntry>>,
+}
+
+/// Unsafety usage in a single module of a package
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ModuleEntry {
+    /// Entry point of the crate the module belongs to, relative to the
+    /// package root. `None` for files outside of any crate, e.g. tests
+    pub crate_root: Option<PathBuf>,
+    /// Module path, e.g. `crate::foo::bar`
+    pub name: String,
+    /// Source file of the module
+    pub path: PathBuf,
+    /// Unsafety scan results for the source file of the module
+    pub unsafety: UnsafeInfo,
+}
+
+/// Unsafety usage in the files read by a single compiled target of a package
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TargetEntry {
+    /// Name of the target, e.g. the name of a bin or an integration test
+    pub name: String,
+    /// Kind of the target, e.g. `lib`, `bin` or `build-script`
+    pub kind: String,
+    /// Whether the target was compiled with `cfg(test)`
+    pub test: bool,
+    /// Unsafety scan results, counting the files read by the target as used
+    pub unsafety: UnsafeInfo,
 }
 
 /// Report generated from scanning for the use of `unsafe`
@@ -73,6 +129,61 @@ pub struct SafetyReport {
     pub packages_without_metrics: HashSet<PackageId>,
     #[serde(serialize_with = "set_serde::serialize")]
     pub used_but_not_scanned_files: HashSet<PathBuf>,
+    /// Failures that make the report incomplete
+    #[serde(default)]
+    pub scan_errors: Vec<ScanError>,
+    /// Cost of the scan, only present when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ScanStats>,
+}
+
+/// Time spent in each phase of a scan, and the amount of code scanned
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScanStats {
+    /// Phases in the order they ran
+    pub phases: Vec<PhaseDuration>,
+    /// Number of packages with at least one scanned file
+    pub packages_scanned: u64,
+    /// Number of source files scanned
+    pub files_scanned: u64,
+    /// Total size of the scanned source files
+    pub bytes_scanned: u64,
+}
+
+/// Wall clock time spent in a phase of a scan
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PhaseDuration {
+    /// Name of the phase, e.g. `metadata`, `clean`, `build` or `scan`
+    pub phase: String,
+    pub duration_ms: u64,
+}
+
+/// A failure during a scan, the results for the affected file or package
+/// are missing from the report
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScanError {
+    /// File or package directory the failure occurred for, if known
+    pub path: Option<PathBuf>,
+    /// Package the failure occurred for, if known
+    pub package: Option<PackageId>,
+    pub kind: ScanErrorKind,
+    /// Description of the failure
+    pub message: String,
+}
+
+/// Kind of failure during a scan
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum ScanErrorKind {
+    /// A file could not be read
+    Io,
+    /// A file is not valid UTF-8
+    Utf8,
+    /// A file could not be parsed as Rust source code
+    Parse,
+    /// The build used to determine the source files in use failed
+    Build,
+    /// A package manifest could not be read
+    Package,
 }
 
 /// Unsafety usage in a package
@@ -152,6 +263,15 @@ impl CounterBlock {
             || self.item_traits.unsafe_ > 0
             || self.methods.unsafe_ > 0
     }
+
+    /// Total number of unsafe items of all kinds
+    pub fn unsafe_count(&self) -> u64 {
+        self.functions.unsafe_
+            + self.exprs.unsafe_
+            + self.item_impls.unsafe_
+            + self.item_traits.unsafe_
+            + self.methods.unsafe_
+    }
 }
 
 impl Add for CounterBlock {
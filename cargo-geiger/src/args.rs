@@ -1,10 +1,14 @@
 use crate::args::Verbosity::{Normal, Quiet, Verbose};
 use crate::format::print_config::OutputFormat;
+use crate::format::{EdgeAnnotation, SortBy};
+use crate::output::OutputSpec;
+use crate::temporary_project::CrateSpec;
 
 use cargo::core::shell::ColorChoice;
 use cargo::{CliResult, Config};
 use pico_args::Arguments;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 /// Constant `&str` containing help text
 pub const HELP: &str =
@@ -12,16 +16,84 @@ pub const HELP: &str =
 
 USAGE:
     cargo geiger [OPTIONS]
+    cargo geiger why <SPEC> [OPTIONS]
+
+SUBCOMMANDS:
+    why <SPEC>                    Print every path from the root package to the
+                                  packages matching SPEC instead of the tree,
+                                  with the counters of each package on the way
+                                  and the kind, enabling features and target of
+                                  each dependency.
 
 OPTIONS:
     -p, --package <SPEC>          Package to be used as the root of the tree.
+        --by-module               Print a tree of the modules of the root
+                                  package instead of its dependencies, with
+                                  per-module counters and forbid status.
+        --by-target               Add the counters of each compiled target of
+                                  each package, e.g. its lib, bins, tests and
+                                  build script, to the Json report.
+        --duplicates              Print the packages present in more than one
+                                  version or from more than one source instead
+                                  of the tree, with the counters, forbid status
+                                  and dependency paths of each copy.
+        --blast-radius            Follow each package in the tree with the used
+                                  and total unsafe expressions of its whole
+                                  subtree, and of the dependencies that would
+                                  disappear along with it, each package
+                                  counted once.
+        --rank-direct-deps        Print the direct dependencies of the root
+                                  package instead of the tree, ordered by the
+                                  unsafe code that would disappear along with
+                                  each of them.
+        --flat                    Print each package once instead of the tree,
+                                  ranked by its unsafe code, most first.
+        --sort-by <METRIC>        Metric of the --flat ranking: functions,
+                                  exprs, impls, traits, methods, total or
+                                  ratio [default: total].
+        --top <N>                 Print only the first N packages of the
+                                  --flat ranking.
         --features <FEATURES>     Space-separated list of features to activate.
         --all-features            Activate all available features.
+        --feature-matrix          Scan once per feature set of the root
+                                  package: no default features, default
+                                  features, each feature alone and all
+                                  features. Prints which feature sets pull in
+                                  additional unsafe crates to stdout.
+        --feature-set <FEATURES>  Feature set to use in the feature matrix
+                                  instead of each feature alone, can be
+                                  repeated.
         --no-default-features     Do not activate the `default` feature.
-        --target <TARGET>         Set the target triple.
+        --target <TARGET>         Set the target triple. Can be repeated to
+                                  scan several targets and compare them on
+                                  stdout.
         --all-targets             Return dependencies for all targets. By
                                   default only the host target is matched.
+        --lib                     Only build the package's library.
+        --bin <NAME>              Only build the specified binary, can be
+                                  repeated.
+        --bins                    Only build all binaries.
+        --example <NAME>          Only build the specified example, can be
+                                  repeated.
+        --examples                Only build all examples.
+        --test <NAME>             Only build the specified test target, can
+                                  be repeated.
+        --tests                   Only build all test targets.
+        --bench <NAME>            Only build the specified bench target, can
+                                  be repeated.
+        --benches                 Only build all bench targets.
+        --release                 Build artifacts in release mode.
         --manifest-path <PATH>    Path to Cargo.toml.
+        --crate <NAME@VERSION>    Scan a published crate without a local
+                                  project, e.g. `--crate regex@1.7.3`. The
+                                  feature flags apply to this crate, which
+                                  is the root of the scan.
+        --binary <PATH>           Scan the dependencies embedded by `cargo
+                                  auditable` in a binary and print a Json
+                                  or CycloneDx report, without a manifest or
+                                  a rebuild.
+        --vendor-dir <PATH>       Resolve crates.io dependencies from a
+                                  `cargo vendor` directory.
     -i, --invert                  Invert the tree direction.
         --no-indent               Display the dependencies as a list (rather
                                   than a tree).
@@ -29,18 +101,65 @@ OPTIONS:
                                   than a tree), but prefixed with the depth.
     -a, --all                     Don't truncate dependencies that have already
                                   been displayed.
+    -e, --edges <CONDITIONS>      Comma-separated conditions of the dependencies
+                                  to show in the tree: `features` of the
+                                  dependent package enabling an optional
+                                  dependency, `targets` the dependency is
+                                  limited to.
+        --depth <N>               Only display dependencies up to depth N, the
+                                  totals still include deeper dependencies.
+        --prune <SPEC>            Remove a package and the dependencies only it
+                                  pulls in from the tree and the totals, can be
+                                  repeated.
+        --exclude <SPEC>          Remove the packages matching SPEC from the
+                                  tree and the totals, their dependencies are
+                                  kept. Can be repeated.
+        --no-deps                 Only scan the root package, without building
+                                  or scanning its dependencies. The files of
+                                  its selected targets are counted as used.
     --format <FORMAT>             Format string used for printing dependencies
-                                  [default: {p}].
-    --output-format               Output format for the report: Ascii, GitHubMarkdown,
-                                  Json, Utf8, Ratio [default: Utf8]
-    --update-readme               Writes output to ./README.md. Looks for a Safety
-                                  Report section, replaces if found, adds if not.
-                                  Throws an error if no README.md exists.
+                                  [default: {p}]. Placeholders: {p} name and
+                                  version, {l} license, {r} repository,
+                                  {name}, {version}, {source}, {source_url},
+                                  {description}, {authors}, {edition},
+                                  {depth}, {forbids} and the used/total unsafe
+                                  {u.functions}, {u.exprs}, {u.impls},
+                                  {u.traits}, {u.methods} and {u.total}.
+                                  {x:<N} and {x:>N} pad to N characters,
+                                  {?x:text} shows text only when x has a
+                                  value. With {forbids} or {u.*} the format
+                                  lays out the whole line of each package.
+    --output-format               Output format for the report: Ascii, Csv,
+                                  CycloneDx, Dot, GitHubMarkdown, Html, Json,
+                                  JUnit, Mermaid, Tsv, Utf8, Ratio
+                                  [default: Utf8]. Dot and Mermaid render the
+                                  dependency graph, Html a standalone page with
+                                  the tree and per file counters, Csv and Tsv
+                                  one row per package, JUnit one test case per
+                                  package and CycloneDx a JSON bill of materials
+                                  with the counters of each component. They are
+                                  not available with --forbid-only.
+        --per-file                Write one row per scanned .rs file instead of
+                                  one per package in Csv and Tsv outputs.
+        --max-unsafe <N>          Used unsafe items a package may have before
+                                  its test case fails in JUnit outputs, unless
+                                  it forbids unsafe code [default: 0].
+        --collapse-safe           Show the packages whose dependencies are all
+                                  free of unsafe code as a single node in Dot
+                                  and Mermaid outputs.
+    --output <FORMAT[=PATH]>      Additional output of the same scan, written to
+                                  PATH or stdout if PATH is missing or `-`. Can be
+                                  repeated, e.g. `--output json=report.json`.
+    --update-readme               Writes a GitHubMarkdown report to ./README.md,
+                                  in addition to any other output. Looks for a
+                                  Safety Report section, replaces if found, adds
+                                  if not. Throws an error if no README.md exists.
         --readme-path <PATH>      Path of README.md file to be written to.
         --section-name <NAME>     The section name in the README.md to be written
                                   to.
     -v, --verbose                 Use verbose output (-vv very verbose/build.rs
-                                  output).
+                                  output). Also prints the time spent in each
+                                  scan phase and adds it to the JSON report.
     -q, --quiet                   No output printed to stdout other than the
                                   tree.
         --color <WHEN>            Coloring: auto, always, never.
@@ -50,7 +169,8 @@ OPTIONS:
     -Z \"<FLAG>...\"                Unstable (nightly-only) flags to Cargo.
         --include-tests           Count unsafe usage in tests.
         --build-dependencies      Also analyze build dependencies.
-        --dev-dependencies        Also analyze dev dependencies.
+        --dev-dependencies        Also analyze dev dependencies, test targets
+                                  are built to measure their usage.
         --all-dependencies        Analyze all dependencies, including build and
                                   dev.
         --forbid-only             Don't build or clean anything, only scan
@@ -59,15 +179,31 @@ OPTIONS:
                                   significantly faster than the default
                                   scanning mode. TODO: Add ability to combine
                                   this with a whitelist for use in CI.
+        --strict                  Fail instead of reporting partial results
+                                  when a file can't be scanned or the build
+                                  fails.
     -h, --help                    Prints help information.
     -V, --version                 Prints version information.
 ";
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Args {
     pub all: bool,
+    pub binary: Option<PathBuf>,
+    pub blast_radius: bool,
+    pub by_module: bool,
+    pub by_target: bool,
+    pub collapse_safe: bool,
     pub color: Option<String>,
+    pub crate_spec: Option<CrateSpec>,
     pub deps_args: DepsArgs,
+    pub depth: Option<usize>,
+    pub duplicates: bool,
+    pub edges: Vec<EdgeAnnotation>,
+    pub exclude: Vec<String>,
+    pub feature_matrix: bool,
+    pub feature_sets: Vec<Vec<String>>,
+    pub flat: bool,
     pub features_args: FeaturesArgs,
     pub forbid_only: bool,
     pub format: String,
@@ -77,17 +213,32 @@ pub struct Args {
     pub invert: bool,
     pub locked: bool,
     pub manifest_path: Option<PathBuf>,
+    pub max_unsafe: Option<u64>,
+    pub no_deps: bool,
     pub no_indent: bool,
     pub offline: bool,
+    /// Format of the first output, or `GitHubMarkdown` if the scan only
+    /// updates the README.
     pub output_format: OutputFormat,
+    pub outputs: Vec<OutputSpec>,
     pub package: Option<String>,
+    pub per_file: bool,
     pub prefix_depth: bool,
+    pub prune: Vec<String>,
     pub quiet: bool,
+    pub rank_direct_deps: bool,
     pub readme_args: ReadmeArgs,
+    pub release: bool,
+    pub sort_by: Option<SortBy>,
+    pub strict: bool,
     pub target_args: TargetArgs,
+    pub target_selection_args: TargetSelectionArgs,
+    pub top: Option<usize>,
     pub unstable_flags: Vec<String>,
+    pub vendor_dir: Option<PathBuf>,
     pub verbosity: Verbosity,
     pub version: bool,
+    pub why: Option<String>,
 }
 
 impl Args {
@@ -101,14 +252,35 @@ impl Args {
     pub fn parse_args(
         mut raw_args: Arguments,
     ) -> Result<Args, Box<dyn std::error::Error>> {
+        let output_format: Option<OutputFormat> =
+            raw_args.opt_value_from_str("--output-format")?;
         let mut args = Args {
             all: raw_args.contains(["-a", "--all"]),
+            binary: raw_args.opt_value_from_str("--binary")?,
+            blast_radius: raw_args.contains("--blast-radius"),
+            by_module: raw_args.contains("--by-module"),
+            by_target: raw_args.contains("--by-target"),
+            collapse_safe: raw_args.contains("--collapse-safe"),
             color: raw_args.opt_value_from_str("--color")?,
+            crate_spec: raw_args.opt_value_from_str("--crate")?,
             deps_args: DepsArgs {
                 all_deps: raw_args.contains("--all-dependencies"),
                 build_deps: raw_args.contains("--build-dependencies"),
                 dev_deps: raw_args.contains("--dev-dependencies"),
             },
+            depth: raw_args.opt_value_from_str("--depth")?,
+            duplicates: raw_args.contains("--duplicates"),
+            edges: raw_args
+                .opt_value_from_fn(["-e", "--edges"], parse_edge_annotations)?
+                .unwrap_or_default(),
+            exclude: raw_args.values_from_str("--exclude")?,
+            feature_matrix: raw_args.contains("--feature-matrix"),
+            feature_sets: raw_args
+                .values_from_str("--feature-set")?
+                .into_iter()
+                .map(|raw_features: String| parse_features(Some(raw_features)))
+                .collect(),
+            flat: raw_args.contains("--flat"),
             features_args: FeaturesArgs {
                 all_features: raw_args.contains("--all-features"),
                 features: parse_features(
@@ -126,25 +298,45 @@ impl Args {
             invert: raw_args.contains(["-i", "--invert"]),
             locked: raw_args.contains("--locked"),
             manifest_path: raw_args.opt_value_from_str("--manifest-path")?,
+            max_unsafe: raw_args.opt_value_from_str("--max-unsafe")?,
+            no_deps: raw_args.contains("--no-deps"),
             no_indent: raw_args.contains("--no-indent"),
             offline: raw_args.contains("--offline"),
             package: raw_args.opt_value_from_str(["-p", "--package"])?,
+            per_file: raw_args.contains("--per-file"),
             prefix_depth: raw_args.contains("--prefix-depth"),
+            prune: raw_args.values_from_str("--prune")?,
             quiet: raw_args.contains(["-q", "--quiet"]),
+            rank_direct_deps: raw_args.contains("--rank-direct-deps"),
             readme_args: ReadmeArgs {
                 readme_path: raw_args.opt_value_from_str("--readme-path")?,
                 section_name: raw_args.opt_value_from_str("--section-name")?,
                 update_readme: raw_args.contains("--update-readme"),
             },
+            release: raw_args.contains("--release"),
+            sort_by: raw_args.opt_value_from_fn("--sort-by", parse_sort_by)?,
+            strict: raw_args.contains("--strict"),
             target_args: TargetArgs {
                 all_targets: raw_args.contains("--all-targets"),
-                target: raw_args.opt_value_from_str("--target")?,
+                targets: raw_args.values_from_str("--target")?,
+            },
+            target_selection_args: TargetSelectionArgs {
+                all_benches: raw_args.contains("--benches"),
+                all_bins: raw_args.contains("--bins"),
+                all_examples: raw_args.contains("--examples"),
+                all_tests: raw_args.contains("--tests"),
+                benches: raw_args.values_from_str("--bench")?,
+                bins: raw_args.values_from_str("--bin")?,
+                examples: raw_args.values_from_str("--example")?,
+                lib: raw_args.contains("--lib"),
+                tests: raw_args.values_from_str("--test")?,
             },
+            top: raw_args.opt_value_from_str("--top")?,
             unstable_flags: raw_args
                 .opt_value_from_str("-Z")?
                 .map(|s: String| s.split(' ').map(|s| s.to_owned()).collect())
                 .unwrap_or_else(Vec::new),
-
+            vendor_dir: raw_args.opt_value_from_str("--vendor-dir")?,
             version: raw_args.contains(["-V", "--version"]),
             verbosity: match (
                 raw_args.contains("-vv"),
@@ -154,20 +346,100 @@ impl Args {
                 (false, true) => Normal,
                 (true, _) => Verbose,
             },
-            output_format: raw_args
-                .opt_value_from_str("--output-format")?
-                .unwrap_or(OutputFormat::Utf8),
+            output_format: OutputFormat::GitHubMarkdown,
+            outputs: raw_args.values_from_str("--output")?,
+            why: None,
         };
 
-        if args.readme_args.update_readme
-            && args.output_format != OutputFormat::GitHubMarkdown
+        // Cargo passes the name of the subcommand, `geiger`, first.
+        let free_args = raw_args.finish();
+        if let Some(position) = free_args.iter().position(|arg| *arg == "why") {
+            args.why = Some(
+                free_args
+                    .get(position + 1)
+                    .ok_or("`why` requires a package specification")?
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+
+        // `--crate` scans a temporary project with the crate as its root.
+        if args.crate_spec.is_some()
+            && (args.manifest_path.is_some() || args.package.is_some())
+        {
+            return Err(
+                "`--manifest-path` and `--package` are not available with `--crate`"
+                    .into(),
+            );
+        }
+
+        // The comparison of a feature matrix, or of several targets, is
+        // printed to stdout, it has no output formats.
+        let has_explicit_outputs = output_format.is_some()
+            || !args.outputs.is_empty()
+            || args.readme_args.update_readme;
+        if args.feature_matrix && has_explicit_outputs {
+            return Err(
+                "`--output`, `--output-format` and `--update-readme` are not available with `--feature-matrix`"
+                    .into(),
+            );
+        }
+        if args.target_args.targets.len() > 1 && has_explicit_outputs {
+            return Err(
+                "`--output`, `--output-format` and `--update-readme` are not available with several `--target`"
+                    .into(),
+            );
+        }
+
+        // `--output-format` is the output to stdout, and the default unless
+        // the scan only updates the README.
+        // Scans of binaries only produce a report, Json unless asked for.
+        let default_output_format = match args.binary {
+            Some(_) => OutputFormat::Json,
+            None => OutputFormat::Utf8,
+        };
+        if let Some(output_format) = output_format {
+            args.outputs.insert(0, OutputSpec::stdout(output_format));
+        } else if args.outputs.is_empty() && !args.readme_args.update_readme {
+            args.outputs.push(OutputSpec::stdout(default_output_format));
+        }
+        if let Some(output) = args.outputs.first() {
+            args.output_format = output.format;
+        }
+        if !args.flat && (args.sort_by.is_some() || args.top.is_some()) {
+            return Err("`--sort-by` and `--top` require `--flat`".into());
+        }
+        if args.binary.is_some()
+            && args.outputs.iter().any(|output| {
+                !matches!(
+                    output.format,
+                    OutputFormat::CycloneDx | OutputFormat::Json
+                )
+            })
+        {
+            return Err(
+                "Only the CycloneDx and Json outputs are available with `--binary`"
+                    .into(),
+            );
+        }
+        if args.forbid_only
+            && args.outputs.iter().any(|output| {
+                matches!(
+                    output.format,
+                    OutputFormat::Csv
+                        | OutputFormat::CycloneDx
+                        | OutputFormat::Dot
+                        | OutputFormat::Html
+                        | OutputFormat::JUnit
+                        | OutputFormat::Mermaid
+                        | OutputFormat::Tsv
+                )
+            })
         {
-            eprintln!(
-                "OutputFormat has been specified as {:?}, but the `--update-readme` flag has also been provided. \
-                To ensure the report written to the README.md is correct, a reduced charset will be used.",
-                args.output_format
+            return Err(
+                "The Csv, CycloneDx, Dot, Html, JUnit, Mermaid and Tsv outputs are not available with `--forbid-only`"
+                    .into(),
             );
-            args.output_format = OutputFormat::GitHubMarkdown
         }
 
         Ok(args)
@@ -201,7 +473,7 @@ impl Args {
             self.offline,
             &target_dir,
             &self.unstable_flags,
-            &[], // Some cargo API change, TODO: Look closer at this later.
+            &self.cargo_config_overrides(),
         )?;
 
         match config.shell().color_choice() {
@@ -212,36 +484,110 @@ impl Args {
 
         Ok(())
     }
+
+    /// The formats to render a scan in, one per `--output`, followed by the
+    /// `GitHubMarkdown` README section when `--update-readme` is given
+    pub fn output_formats(&self) -> Vec<OutputFormat> {
+        let mut output_formats = self
+            .outputs
+            .iter()
+            .map(|output| output.format)
+            .collect::<Vec<OutputFormat>>();
+        if self.readme_args.update_readme {
+            output_formats.push(OutputFormat::GitHubMarkdown);
+        }
+        output_formats
+    }
+
+    /// Cargo `--config` overrides derived from `Args`, to be passed to both
+    /// the `cargo::util::Config` and the `cargo metadata` invocation
+    pub fn cargo_config_overrides(&self) -> Vec<String> {
+        match &self.vendor_dir {
+            Some(vendor_dir) => {
+                let vendor_dir = vendor_dir
+                    .canonicalize()
+                    .unwrap_or_else(|_| vendor_dir.clone());
+                vec![
+                    String::from(
+                        "source.crates-io.replace-with=\"vendored-sources\"",
+                    ),
+                    format!(
+                        "source.vendored-sources.directory={:?}",
+                        vendor_dir.display().to_string()
+                    ),
+                ]
+            }
+            None => vec![],
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct DepsArgs {
     pub all_deps: bool,
     pub build_deps: bool,
     pub dev_deps: bool,
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct FeaturesArgs {
     pub all_features: bool,
     pub features: Vec<String>,
     pub no_default_features: bool,
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct TargetArgs {
     pub all_targets: bool,
-    pub target: Option<String>,
+    pub targets: Vec<String>,
+}
+
+impl TargetArgs {
+    /// The target used for the dependency graph, only a single target can be
+    /// evaluated at a time.
+    pub fn target(&self) -> Option<String> {
+        self.targets.first().cloned()
+    }
+}
+
+/// The cargo target selection flags, limiting the build to some of the
+/// targets of the package
+#[derive(Clone, Debug, Default)]
+pub struct TargetSelectionArgs {
+    pub all_benches: bool,
+    pub all_bins: bool,
+    pub all_examples: bool,
+    pub all_tests: bool,
+    pub benches: Vec<String>,
+    pub bins: Vec<String>,
+    pub examples: Vec<String>,
+    pub lib: bool,
+    pub tests: Vec<String>,
 }
 
-#[derive(Debug, Default)]
+impl TargetSelectionArgs {
+    /// Whether any target was selected, rather than the default targets
+    pub fn is_specified(&self) -> bool {
+        self.all_benches
+            || self.all_bins
+            || self.all_examples
+            || self.all_tests
+            || !self.benches.is_empty()
+            || !self.bins.is_empty()
+            || !self.examples.is_empty()
+            || self.lib
+            || !self.tests.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct ReadmeArgs {
     pub readme_path: Option<PathBuf>,
     pub section_name: Option<String>,
     pub update_readme: bool,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Verbosity {
     Verbose,
     Normal,
@@ -254,6 +600,32 @@ impl Default for Verbosity {
     }
 }
 
+fn parse_edge_annotations(
+    raw_edge_annotations: &str,
+) -> Result<Vec<EdgeAnnotation>, String> {
+    raw_edge_annotations
+        .split(',')
+        .filter(|edge_annotation| !edge_annotation.is_empty())
+        .map(|edge_annotation| {
+            EdgeAnnotation::from_str(edge_annotation).map_err(|_| {
+                format!(
+                    "Invalid edge condition `{}`, expected `features` or `targets`",
+                    edge_annotation
+                )
+            })
+        })
+        .collect()
+}
+
+fn parse_sort_by(raw_sort_by: &str) -> Result<SortBy, String> {
+    SortBy::from_str(raw_sort_by).map_err(|_| {
+        format!(
+            "Invalid sort metric `{}`, expected `functions`, `exprs`, `impls`, `traits`, `methods`, `total` or `ratio`",
+            raw_sort_by
+        )
+    })
+}
+
 fn parse_features(raw_features: Option<String>) -> Vec<String> {
     raw_features
         .as_ref()
@@ -269,6 +641,8 @@ fn parse_features(raw_features: Option<String>) -> Vec<String> {
 pub mod args_tests {
     use super::*;
 
+    use crate::output::OutputDestination;
+
     use cargo::core::shell::ColorChoice;
     use cargo::core::Verbosity as CargoCoreVerbosity;
     use rstest::*;
@@ -318,7 +692,7 @@ pub mod args_tests {
         case(
             vec![OsString::from("--update-readme"), OsString::from("--output-format"), OsString::from("Ascii")],
             false,
-            OutputFormat::GitHubMarkdown,
+            OutputFormat::Ascii,
             Quiet
         )
     )]
@@ -339,6 +713,418 @@ pub mod args_tests {
         assert_eq!(args.verbosity, expected_verbosity)
     }
 
+    #[rstest(
+        input_argument_vector,
+        expected_outputs,
+        case(vec![], vec![OutputSpec::stdout(OutputFormat::Utf8)]),
+        case(vec![OsString::from("--update-readme")], vec![]),
+        case(
+            vec![
+                OsString::from("--output"),
+                OsString::from("json=report.json"),
+                OsString::from("--output"),
+                OsString::from("utf8=-"),
+                OsString::from("--update-readme"),
+            ],
+            vec![
+                OutputSpec {
+                    destination: OutputDestination::File(PathBuf::from(
+                        "report.json"
+                    )),
+                    format: OutputFormat::Json
+                },
+                OutputSpec::stdout(OutputFormat::Utf8),
+            ]
+        ),
+        case(
+            vec![
+                OsString::from("--output-format"),
+                OsString::from("Ascii"),
+                OsString::from("--output"),
+                OsString::from("json=report.json"),
+            ],
+            vec![
+                OutputSpec::stdout(OutputFormat::Ascii),
+                OutputSpec {
+                    destination: OutputDestination::File(PathBuf::from(
+                        "report.json"
+                    )),
+                    format: OutputFormat::Json
+                },
+            ]
+        )
+    )]
+    fn parse_args_test_outputs(
+        input_argument_vector: Vec<OsString>,
+        expected_outputs: Vec<OutputSpec>,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(input_argument_vector))
+            .unwrap();
+
+        assert_eq!(args.outputs, expected_outputs);
+        assert_eq!(
+            args.output_format,
+            expected_outputs
+                .first()
+                .map(|output| output.format)
+                .unwrap_or(OutputFormat::GitHubMarkdown)
+        );
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_outputs,
+        case(
+            vec!["--binary", "app"],
+            Ok(vec![OutputSpec::stdout(OutputFormat::Json)])
+        ),
+        case(
+            vec!["--binary", "app", "--output", "cyclonedx=bom.json"],
+            Ok(vec![OutputSpec {
+                destination: OutputDestination::File(PathBuf::from(
+                    "bom.json"
+                )),
+                format: OutputFormat::CycloneDx
+            }])
+        ),
+        case(vec!["--binary", "app", "--output-format", "Utf8"], Err(()))
+    )]
+    fn parse_args_test_binary_outputs(
+        input_argument_vector: Vec<&str>,
+        expected_outputs: Result<Vec<OutputSpec>, ()>,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert_eq!(
+            args_result.map(|args| args.outputs).map_err(|_| ()),
+            expected_outputs
+        );
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_is_ok,
+        case(vec!["--crate", "regex@1.7.3"], true),
+        case(
+            vec!["--crate", "regex@1.7.3", "--manifest-path", "Cargo.toml"],
+            false
+        ),
+        case(vec!["--crate", "regex@1.7.3", "-p", "regex"], false),
+        case(vec!["--crate", "regex@1.7.3", "--package", "regex"], false)
+    )]
+    fn parse_args_test_crate(
+        input_argument_vector: Vec<&str>,
+        expected_is_ok: bool,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert_eq!(args_result.is_ok(), expected_is_ok);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_is_ok,
+        case(vec!["--feature-matrix"], true),
+        case(vec!["--feature-matrix", "--output-format", "Json"], false),
+        case(vec!["--feature-matrix", "--output", "json=report.json"], false),
+        case(vec!["--feature-matrix", "--update-readme"], false),
+        case(vec!["--target", "x86_64-unknown-linux-gnu"], true),
+        case(
+            vec![
+                "--target",
+                "x86_64-unknown-linux-gnu",
+                "--output-format",
+                "Json"
+            ],
+            true
+        ),
+        case(
+            vec![
+                "--target",
+                "x86_64-unknown-linux-gnu",
+                "--target",
+                "x86_64-pc-windows-msvc"
+            ],
+            true
+        ),
+        case(
+            vec![
+                "--target",
+                "x86_64-unknown-linux-gnu",
+                "--target",
+                "x86_64-pc-windows-msvc",
+                "--output",
+                "json=report.json"
+            ],
+            false
+        )
+    )]
+    fn parse_args_test_comparison_outputs(
+        input_argument_vector: Vec<&str>,
+        expected_is_ok: bool,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert_eq!(args_result.is_ok(), expected_is_ok);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_output_formats,
+        case(vec![], vec![OutputFormat::Utf8]),
+        case(
+            vec![OsString::from("--update-readme")],
+            vec![OutputFormat::GitHubMarkdown]
+        ),
+        case(
+            vec![
+                OsString::from("--output"),
+                OsString::from("json=report.json"),
+                OsString::from("--update-readme"),
+            ],
+            vec![OutputFormat::Json, OutputFormat::GitHubMarkdown]
+        )
+    )]
+    fn output_formats_test(
+        input_argument_vector: Vec<OsString>,
+        expected_output_formats: Vec<OutputFormat>,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(input_argument_vector))
+            .unwrap();
+
+        assert_eq!(args.output_formats(), expected_output_formats);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_output_format_and_collapse_safe,
+        case(vec!["--output-format", "dot"], Ok((OutputFormat::Dot, false))),
+        case(
+            vec!["--output-format", "Mermaid", "--collapse-safe"],
+            Ok((OutputFormat::Mermaid, true))
+        ),
+        case(vec!["--output", "dot=deps.dot", "--forbid-only"], Err(())),
+        case(vec!["--output-format", "html"], Ok((OutputFormat::Html, false))),
+        case(vec!["--output", "Html=report.html", "--forbid-only"], Err(())),
+        case(vec!["--output-format", "csv"], Ok((OutputFormat::Csv, false))),
+        case(vec!["--output-format", "Tsv"], Ok((OutputFormat::Tsv, false))),
+        case(vec!["--output", "csv=report.csv", "--forbid-only"], Err(())),
+        case(vec!["--output-format", "junit"], Ok((OutputFormat::JUnit, false))),
+        case(
+            vec!["--output-format", "cyclonedx"],
+            Ok((OutputFormat::CycloneDx, false))
+        ),
+        case(vec!["--output", "CycloneDx=bom.json", "--forbid-only"], Err(()))
+    )]
+    fn parse_args_test_graph_output_formats(
+        input_argument_vector: Vec<&str>,
+        expected_output_format_and_collapse_safe: Result<
+            (OutputFormat, bool),
+            (),
+        >,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert_eq!(
+            args_result
+                .map(|args| (args.output_format, args.collapse_safe))
+                .map_err(|_| ()),
+            expected_output_format_and_collapse_safe
+        );
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_why,
+        case(vec!["geiger"], Ok(None)),
+        case(vec!["geiger", "why", "syn"], Ok(Some(String::from("syn")))),
+        case(
+            vec!["why", "--all", "syn@1.0.109"],
+            Ok(Some(String::from("syn@1.0.109")))
+        ),
+        case(vec!["geiger", "why"], Err(()))
+    )]
+    fn parse_args_test_why(
+        input_argument_vector: Vec<&str>,
+        expected_why: Result<Option<String>, ()>,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert_eq!(
+            args_result.map(|args| args.why).map_err(|_| ()),
+            expected_why
+        );
+    }
+
+    #[rstest]
+    fn parse_args_test_depth_prune_exclude_no_deps() {
+        let args = Args::parse_args(Arguments::from_vec(vec![
+            OsString::from("--depth"),
+            OsString::from("2"),
+            OsString::from("--prune"),
+            OsString::from("syn"),
+            OsString::from("--prune"),
+            OsString::from("quote@1.0.23"),
+            OsString::from("--exclude"),
+            OsString::from("libc"),
+            OsString::from("--no-deps"),
+        ]))
+        .unwrap();
+
+        assert_eq!(args.depth, Some(2));
+        assert_eq!(
+            args.prune,
+            vec![String::from("syn"), String::from("quote@1.0.23")]
+        );
+        assert_eq!(args.exclude, vec![String::from("libc")]);
+        assert!(args.no_deps);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_edges,
+        case(vec![], Ok(vec![])),
+        case(vec!["-e", "features"], Ok(vec![EdgeAnnotation::Features])),
+        case(
+            vec!["--edges", "targets,features"],
+            Ok(vec![EdgeAnnotation::Targets, EdgeAnnotation::Features])
+        ),
+        case(vec!["--edges", "normal"], Err(()))
+    )]
+    fn parse_args_test_edges(
+        input_argument_vector: Vec<&str>,
+        expected_edges: Result<Vec<EdgeAnnotation>, ()>,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert_eq!(
+            args_result.map(|args| args.edges).map_err(|_| ()),
+            expected_edges
+        );
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_blast_radius,
+        expected_rank_direct_deps,
+        case(vec![], false, false),
+        case(vec!["--blast-radius"], true, false),
+        case(vec!["--rank-direct-deps"], false, true)
+    )]
+    fn parse_args_test_blast_radius(
+        input_argument_vector: Vec<&str>,
+        expected_blast_radius: bool,
+        expected_rank_direct_deps: bool,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ))
+        .unwrap();
+
+        assert_eq!(args.blast_radius, expected_blast_radius);
+        assert_eq!(args.rank_direct_deps, expected_rank_direct_deps);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_flat_args,
+        case(vec![], Ok((false, None, None))),
+        case(vec!["--flat"], Ok((true, None, None))),
+        case(
+            vec!["--flat", "--sort-by", "ratio", "--top", "10"],
+            Ok((true, Some(SortBy::Ratio), Some(10)))
+        ),
+        case(vec!["--flat", "--sort-by", "lines"], Err(())),
+        case(vec!["--top", "10"], Err(()))
+    )]
+    fn parse_args_test_flat(
+        input_argument_vector: Vec<&str>,
+        expected_flat_args: Result<(bool, Option<SortBy>, Option<usize>), ()>,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert_eq!(
+            args_result
+                .map(|args| (args.flat, args.sort_by, args.top))
+                .map_err(|_| ()),
+            expected_flat_args
+        );
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_bins,
+        expected_all_tests,
+        expected_is_specified,
+        case(vec![], vec![], false, false),
+        case(
+            vec![
+                OsString::from("--bin"),
+                OsString::from("a"),
+                OsString::from("--bin"),
+                OsString::from("b")
+            ],
+            vec![String::from("a"), String::from("b")],
+            false,
+            true
+        ),
+        case(vec![OsString::from("--tests")], vec![], true, true)
+    )]
+    fn parse_args_test_target_selection(
+        input_argument_vector: Vec<OsString>,
+        expected_bins: Vec<String>,
+        expected_all_tests: bool,
+        expected_is_specified: bool,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(input_argument_vector))
+            .unwrap();
+
+        assert_eq!(args.target_selection_args.bins, expected_bins);
+        assert_eq!(args.target_selection_args.all_tests, expected_all_tests);
+        assert_eq!(
+            args.target_selection_args.is_specified(),
+            expected_is_specified
+        );
+    }
+
     #[rstest(
         input_raw_features,
         expected_features,
@@ -7,13 +7,14 @@
 // using rustc? Is it implementing a compiler plugin?
 
 use crate::args::Args;
+use crate::mapping::QueryResolve;
 
 // TODO: Consider making this a lib.rs (again) and expose a full API, excluding
 // only the terminal output..? That API would be dependent on cargo.
 use cargo::core::Workspace;
 use cargo::util::{important_paths, CargoResult};
-use cargo::Config;
-use cargo_metadata::{CargoOpt, Metadata, MetadataCommand};
+use cargo::{CliError, Config};
+use cargo_metadata::{CargoOpt, Metadata, MetadataCommand, PackageId};
 use cargo_platform::Cfg;
 use krates::Builder as KratesBuilder;
 use krates::Krates;
@@ -31,6 +32,7 @@ pub fn get_cargo_metadata(
 
     let mut metadata_command = MetadataCommand::new();
     metadata_command.manifest_path(root_manifest_path);
+    metadata_command.other_options(metadata_command_options(args));
 
     if let Some(metadata_command_features) = match &args.features_args {
         features_args if features_args.all_features => {
@@ -50,6 +52,24 @@ pub fn get_cargo_metadata(
     Ok(metadata_command.exec()?)
 }
 
+fn metadata_command_options(args: &Args) -> Vec<String> {
+    let mut options = vec![];
+    for (enabled, option) in [
+        (args.frozen, "--frozen"),
+        (args.locked, "--locked"),
+        (args.offline, "--offline"),
+    ] {
+        if enabled {
+            options.push(String::from(option));
+        }
+    }
+    for cargo_config_override in args.cargo_config_overrides() {
+        options.push(String::from("--config"));
+        options.push(cargo_config_override);
+    }
+    options
+}
+
 /// TODO: Write proper documentation for this.
 /// This function seems to be looking up the active flags for conditional
 /// compilation (`cargo_platform::Cfg` instances).
@@ -80,6 +100,44 @@ pub fn get_krates(cargo_metadata: &Metadata) -> CargoResult<Krates> {
         .build_with_metadata(cargo_metadata.clone(), |_| ())?)
 }
 
+/// The root package of the manifest, which has to be an actual package rather
+/// than a virtual manifest.
+pub fn get_root_package_id(
+    args: &Args,
+    cargo_metadata: &Metadata,
+    config: &Config,
+) -> Result<PackageId, CliError> {
+    if let Some(cargo_metadata_root_package) = cargo_metadata.root_package() {
+        Ok(cargo_metadata_root_package.id.clone())
+    } else {
+        eprintln!(
+            "manifest path `{}` is a virtual manifest, but this command requires running against an actual package in this workspace",
+            match args.manifest_path.clone() {
+                Some(path) => path,
+                None => important_paths::find_root_manifest_for_wd(config.cwd())?,
+            }.as_os_str().to_str().unwrap()
+        );
+
+        Err(CliError::code(1))
+    }
+}
+
+/// The package selected with `--package`, falling back to `root_package_id`
+/// if none was selected or it could not be found.
+pub fn resolve_package_query(
+    args: &Args,
+    krates: &Krates,
+    root_package_id: PackageId,
+) -> PackageId {
+    args.package
+        .as_ref()
+        .map_or(root_package_id.clone(), |package_query| {
+            krates
+                .query_resolve(package_query)
+                .map_or(root_package_id, |package_id| package_id)
+        })
+}
+
 pub fn get_workspace(
     config: &Config,
     manifest_path: Option<PathBuf>,
@@ -108,6 +166,46 @@ mod cli_tests {
         assert!(cargo_metadata_result.is_ok());
     }
 
+    #[rstest(
+        input_frozen,
+        input_offline,
+        input_vendor_dir,
+        expected_options,
+        case(false, false, None, vec![]),
+        case(
+            true,
+            true,
+            None,
+            vec![String::from("--frozen"), String::from("--offline")]
+        ),
+        case(
+            false,
+            false,
+            Some(PathBuf::from("/does/not/exist")),
+            vec![
+                String::from("--config"),
+                String::from("source.crates-io.replace-with=\"vendored-sources\""),
+                String::from("--config"),
+                String::from("source.vendored-sources.directory=\"/does/not/exist\""),
+            ]
+        )
+    )]
+    fn metadata_command_options_test(
+        input_frozen: bool,
+        input_offline: bool,
+        input_vendor_dir: Option<PathBuf>,
+        expected_options: Vec<String>,
+    ) {
+        let args = Args {
+            frozen: input_frozen,
+            offline: input_offline,
+            vendor_dir: input_vendor_dir,
+            ..Default::default()
+        };
+
+        assert_eq!(metadata_command_options(&args), expected_options);
+    }
+
     #[rstest]
     fn get_cfgs_test() {
         let config = Config::default().unwrap();
@@ -0,0 +1,421 @@
+use crate::args::{Args, FeaturesArgs};
+use crate::cli::{
+    get_cargo_metadata, get_krates, get_root_package_id, get_workspace,
+    resolve_package_query,
+};
+use crate::format::print_config::PrintConfig;
+use crate::graph::build_graph;
+use crate::mapping::CargoMetadataParameters;
+use crate::scan::{
+    scan_to_safety_report, ScanParameters, ScanResult, ScanStatsCollector,
+};
+
+use cargo::{CliError, Config};
+use cargo_geiger_serde::{PackageId, SafetyReport};
+use cargo_metadata::{Metadata, PackageId as CargoMetadataPackageId};
+use std::collections::BTreeMap;
+
+/// A single configuration to scan, and the label it is reported under.
+pub struct ComparisonRun {
+    pub label: String,
+    pub args: Args,
+}
+
+/// Runs the full scan once per run and compares the results against the
+/// first run, which serves as the baseline.
+pub fn compare_runs(
+    config: &Config,
+    runs: &[ComparisonRun],
+    title: &str,
+) -> Result<ScanResult, CliError> {
+    let mut labelled_reports = Vec::<(&str, SafetyReport)>::new();
+    for run in runs {
+        config
+            .shell()
+            .status("Comparing", format!("{}: {}", title, run.label))?;
+        labelled_reports
+            .push((run.label.as_str(), scan_to_report(&run.args, config)?));
+    }
+
+    Ok(ScanResult {
+        scan_output_lines: construct_comparison_lines(&labelled_reports, title),
+        warning_count: 0,
+    })
+}
+
+/// The runs of a feature matrix: no default features, default features,
+/// either each feature of the root package, the one selected with
+/// `--package` if any, alone or the feature sets given with `--feature-set`,
+/// and all features.
+pub fn feature_matrix_runs(
+    args: &Args,
+    config: &Config,
+) -> Result<Vec<ComparisonRun>, CliError> {
+    let feature_sets = if args.feature_sets.is_empty() {
+        let cargo_metadata = get_cargo_metadata(args, config)?;
+        let krates = get_krates(&cargo_metadata)?;
+        let root_package_id = resolve_package_query(
+            args,
+            &krates,
+            get_root_package_id(args, &cargo_metadata, config)?,
+        );
+        single_feature_sets(&cargo_metadata, &root_package_id)
+    } else {
+        args.feature_sets.clone()
+    };
+
+    let mut runs = vec![
+        feature_run(
+            args,
+            "no-default",
+            FeaturesArgs {
+                no_default_features: true,
+                ..Default::default()
+            },
+        ),
+        feature_run(args, "default", FeaturesArgs::default()),
+    ];
+    for features in feature_sets {
+        let label = format!("features `{}`", features.join(" "));
+        runs.push(feature_run(
+            args,
+            &label,
+            FeaturesArgs {
+                all_features: false,
+                features,
+                no_default_features: true,
+            },
+        ));
+    }
+    runs.push(feature_run(
+        args,
+        "all",
+        FeaturesArgs {
+            all_features: true,
+            ..Default::default()
+        },
+    ));
+    Ok(runs)
+}
+
+/// Each feature of the package with `package_id` alone, apart from `default`,
+/// in alphabetical order
+fn single_feature_sets(
+    cargo_metadata: &Metadata,
+    package_id: &CargoMetadataPackageId,
+) -> Vec<Vec<String>> {
+    let mut features = cargo_metadata[package_id]
+        .features
+        .keys()
+        .filter(|feature| feature.as_str() != "default")
+        .cloned()
+        .collect::<Vec<String>>();
+    features.sort();
+    features.into_iter().map(|feature| vec![feature]).collect()
+}
+
+/// One run per `--target`, each scanning only that target.
+pub fn target_runs(args: &Args) -> Vec<ComparisonRun> {
+    args.target_args
+        .targets
+        .iter()
+        .map(|target| {
+            let mut run_args = args.clone();
+            run_args.target_args.targets = vec![target.clone()];
+            ComparisonRun {
+                label: target.clone(),
+                args: run_args,
+            }
+        })
+        .collect()
+}
+
+fn feature_run(
+    args: &Args,
+    label: &str,
+    features_args: FeaturesArgs,
+) -> ComparisonRun {
+    let mut run_args = args.clone();
+    run_args.features_args = features_args;
+    ComparisonRun {
+        label: String::from(label),
+        args: run_args,
+    }
+}
+
+fn scan_to_report(
+    args: &Args,
+    config: &Config,
+) -> Result<SafetyReport, CliError> {
+    let cargo_metadata = get_cargo_metadata(args, config)?;
+    let krates = get_krates(&cargo_metadata)?;
+    let cargo_metadata_parameters = CargoMetadataParameters {
+        metadata: &cargo_metadata,
+        krates: &krates,
+    };
+    let workspace = get_workspace(config, args.manifest_path.clone())?;
+    let root_package_id = resolve_package_query(
+        args,
+        &krates,
+        get_root_package_id(args, &cargo_metadata, config)?,
+    );
+    let global_rustc = config.load_global_rustc(Some(&workspace))?;
+    let graph = build_graph(
+        args,
+        &cargo_metadata_parameters,
+        &global_rustc.host,
+        &global_rustc.path,
+        root_package_id.clone(),
+    )?;
+    let print_config = PrintConfig::new(args)?;
+    let stats = ScanStatsCollector::default();
+    let scan_parameters = ScanParameters {
+        args,
+        config,
+        print_config: &print_config,
+        stats: &stats,
+    };
+
+    scan_to_safety_report(
+        &cargo_metadata_parameters,
+        &graph,
+        root_package_id,
+        &scan_parameters,
+        &workspace,
+    )
+}
+
+/// Packages with used unsafe code, and the amount of it
+fn unsafe_packages(report: &SafetyReport) -> BTreeMap<&PackageId, u64> {
+    report
+        .packages
+        .values()
+        .filter(|entry| entry.unsafety.used.has_unsafe())
+        .map(|entry| (&entry.package.id, entry.unsafety.used.unsafe_count()))
+        .collect()
+}
+
+fn construct_comparison_lines(
+    labelled_reports: &[(&str, SafetyReport)],
+    title: &str,
+) -> Vec<String> {
+    let (baseline_label, baseline_report) = match labelled_reports.first() {
+        Some(labelled_report) => labelled_report,
+        None => return vec![],
+    };
+    let baseline_packages = unsafe_packages(baseline_report);
+    let baseline_total = baseline_packages.values().sum::<u64>();
+
+    let mut lines = vec![
+        String::new(),
+        format!("{}, compared to `{}`:", title, baseline_label),
+        String::new(),
+    ];
+    for (label, report) in labelled_reports {
+        let packages = unsafe_packages(report);
+        let total = packages.values().sum::<u64>();
+        lines.push(format!(
+            "{}: {} unsafe used ({:+}) in {} crates ({:+})",
+            label,
+            total,
+            total as i64 - baseline_total as i64,
+            packages.len(),
+            packages.len() as i64 - baseline_packages.len() as i64
+        ));
+        for (package_id, unsafe_count) in &packages {
+            if !baseline_packages.contains_key(package_id) {
+                lines.push(format!(
+                    "    + {} {}: {} unsafe used",
+                    package_id.name, package_id.version, unsafe_count
+                ));
+            }
+        }
+        for (package_id, unsafe_count) in &baseline_packages {
+            if !packages.contains_key(package_id) {
+                lines.push(format!(
+                    "    - {} {}: {} unsafe used",
+                    package_id.name, package_id.version, unsafe_count
+                ));
+            }
+        }
+    }
+    lines.push(String::new());
+    lines
+}
+
+#[cfg(test)]
+mod comparison_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{
+        Count, CounterBlock, PackageInfo, ReportEntry, Source, UnsafeInfo,
+    };
+    use rstest::*;
+    use url::Url;
+
+    #[rstest]
+    fn construct_comparison_lines_test() {
+        let baseline_report = create_report(vec![("a", 2), ("b", 0)]);
+        let default_report = create_report(vec![("a", 3), ("b", 0), ("c", 4)]);
+        let minimal_report = create_report(vec![("b", 0)]);
+
+        let lines = construct_comparison_lines(
+            &[
+                ("no-default", baseline_report),
+                ("default", default_report),
+                ("minimal", minimal_report),
+            ],
+            "Feature matrix",
+        );
+
+        assert_eq!(
+            lines,
+            vec![
+                String::new(),
+                String::from("Feature matrix, compared to `no-default`:"),
+                String::new(),
+                String::from("no-default: 2 unsafe used (+0) in 1 crates (+0)"),
+                String::from("default: 7 unsafe used (+5) in 2 crates (+1)"),
+                String::from("    + c 1.0.0: 4 unsafe used"),
+                String::from("minimal: 0 unsafe used (-2) in 0 crates (-1)"),
+                String::from("    - a 1.0.0: 2 unsafe used"),
+                String::new(),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn feature_matrix_runs_test_feature_sets() {
+        let args = Args {
+            feature_sets: vec![
+                vec![String::from("a")],
+                vec![String::from("b"), String::from("c")],
+            ],
+            ..Default::default()
+        };
+        let config = Config::default().unwrap();
+
+        let runs = feature_matrix_runs(&args, &config).unwrap();
+
+        let summary = runs
+            .iter()
+            .map(|run| {
+                (
+                    run.label.as_str(),
+                    run.args.features_args.all_features,
+                    run.args.features_args.no_default_features,
+                    run.args.features_args.features.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("no-default", false, true, vec![]),
+                ("default", false, false, vec![]),
+                ("features `a`", false, true, vec![String::from("a")]),
+                (
+                    "features `b c`",
+                    false,
+                    true,
+                    vec![String::from("b"), String::from("c")]
+                ),
+                ("all", true, false, vec![]),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn single_feature_sets_test() {
+        let package = |name: &str, features: serde_json::Value| {
+            serde_json::json!({
+                "name": name,
+                "version": "0.1.0",
+                "id": format!("{} 0.1.0 (path+file:///{})", name, name),
+                "source": null,
+                "dependencies": [],
+                "targets": [],
+                "features": features,
+                "manifest_path": format!("/{}/Cargo.toml", name),
+            })
+        };
+        let metadata: Metadata = serde_json::from_value(serde_json::json!({
+            "packages": [
+                package("root", serde_json::json!({ "r": [] })),
+                package(
+                    "member",
+                    serde_json::json!({ "default": ["a"], "a": [], "b": [] })
+                ),
+            ],
+            "workspace_members": ["root 0.1.0 (path+file:///root)"],
+            "resolve": null,
+            "workspace_root": "/",
+            "target_directory": "/target",
+            "version": 1,
+        }))
+        .unwrap();
+
+        assert_eq!(
+            single_feature_sets(&metadata, &metadata.packages[1].id),
+            vec![vec![String::from("a")], vec![String::from("b")]]
+        );
+    }
+
+    #[rstest]
+    fn target_runs_test() {
+        let mut args = Args::default();
+        args.target_args.targets = vec![
+            String::from("x86_64-unknown-linux-gnu"),
+            String::from("wasm32-unknown-unknown"),
+        ];
+
+        let runs = target_runs(&args);
+
+        let summary = runs
+            .iter()
+            .map(|run| {
+                (run.label.as_str(), run.args.target_args.targets.clone())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "x86_64-unknown-linux-gnu",
+                    vec![String::from("x86_64-unknown-linux-gnu")]
+                ),
+                (
+                    "wasm32-unknown-unknown",
+                    vec![String::from("wasm32-unknown-unknown")]
+                ),
+            ]
+        );
+    }
+
+    fn create_report(packages: Vec<(&str, u64)>) -> SafetyReport {
+        let mut report = SafetyReport::default();
+        for (name, unsafe_) in packages {
+            let package_id = PackageId {
+                name: String::from(name),
+                version: cargo_metadata::semver::Version::new(1, 0, 0),
+                source: Source::Path(Url::parse("file:///path").unwrap()),
+            };
+            report.packages.insert(
+                package_id.clone(),
+                ReportEntry {
+                    package: PackageInfo::new(package_id),
+                    unsafety: UnsafeInfo {
+                        used: CounterBlock {
+                            exprs: Count { safe: 1, unsafe_ },
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    modules: None,
+                    targets: None,
+                },
+            );
+        }
+        report
+    }
+}
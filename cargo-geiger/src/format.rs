@@ -1,4 +1,9 @@
+pub mod csv;
+pub mod cyclonedx;
 pub mod emoji_symbols;
+pub mod graph_export;
+pub mod html;
+pub mod junit;
 pub mod pattern;
 pub mod print_config;
 pub mod table;
@@ -6,10 +11,12 @@ pub mod table;
 mod display;
 mod parse;
 
+use crate::graph::DependencyEdge;
+
 use cargo_metadata::DependencyKind;
 use std::fmt;
 use std::str::{self, FromStr};
-use strum_macros::EnumIter;
+use strum_macros::{EnumIter, EnumString};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Charset {
@@ -24,12 +31,36 @@ impl Default for Charset {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// Side of the padding of a placeholder, `<` pads on the right and `>` on
+/// the left
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Alignment {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Chunk {
+    Authors,
+    /// Chunks rendered only when the placeholder has a value, e.g.
+    /// `{?l: ({l})}`
+    Conditional(Box<Chunk>, Vec<Chunk>),
+    /// The used and total unsafe items of a counter, e.g. `{u.exprs}`
+    Counter(UnsafeCounter),
+    Depth,
+    Description,
+    Edition,
+    ForbidsUnsafe,
     License,
+    Name,
     Package,
+    /// A placeholder padded to a width, e.g. `{p:<20}`
+    Padded(Box<Chunk>, Alignment, usize),
     Raw(String),
     Repository,
+    SourceKind,
+    SourceUrl,
+    Version,
 }
 
 impl FromStr for Charset {
@@ -53,13 +84,53 @@ pub enum CrateDetectionStatus {
     UnsafeDetected,
 }
 
+/// Conditions of the dependencies that can be shown in the tree
+#[derive(Clone, Copy, Debug, EnumIter, EnumString, Eq, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum EdgeAnnotation {
+    Features,
+    Targets,
+}
+
+/// Metrics the packages of the flat list can be ranked by
+#[derive(Clone, Copy, Debug, EnumString, Eq, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum SortBy {
+    Functions,
+    Exprs,
+    Impls,
+    Traits,
+    Methods,
+    /// All used unsafe items
+    Total,
+    /// Share of unsafe items among all items, used or not
+    Ratio,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum RawChunk<'a> {
     Argument(&'a str),
+    /// A placeholder and the text rendered when it has a value
+    Conditional(&'a str, &'a str),
+    /// A placeholder and its padding
+    FormattedArgument(&'a str, &'a str),
     Error(&'static str),
     Text(&'a str),
 }
 
+/// Counters of unsafe items that can be shown with the `u.` placeholders
+#[derive(Clone, Copy, Debug, EnumString, Eq, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum UnsafeCounter {
+    Functions,
+    Exprs,
+    Impls,
+    Traits,
+    Methods,
+    /// All unsafe items
+    Total,
+}
+
 #[derive(Clone, Copy)]
 pub enum SymbolKind {
     Lock = 0,
@@ -91,6 +162,35 @@ pub fn get_kind_group_name(dep_kind: DependencyKind) -> Option<&'static str> {
     }
 }
 
+/// The conditions of `dependency_edge` selected by `edge_annotations`, e.g.
+/// `feature "std"` or `cfg(unix)`
+pub fn get_edge_conditions(
+    dependency_edge: &DependencyEdge,
+    edge_annotations: &[EdgeAnnotation],
+) -> Vec<String> {
+    let mut edge_conditions = Vec::new();
+    if edge_annotations.contains(&EdgeAnnotation::Features) {
+        edge_conditions.extend(
+            dependency_edge
+                .features
+                .iter()
+                .map(|feature| format!("feature \"{}\"", feature)),
+        );
+    }
+    if edge_annotations.contains(&EdgeAnnotation::Targets) {
+        edge_conditions.extend(dependency_edge.target.clone());
+    }
+    edge_conditions
+}
+
+/// `edge_conditions` following a package name, e.g. ` (feature "std")`
+pub fn format_edge_conditions(edge_conditions: &[String]) -> String {
+    match edge_conditions {
+        [] => String::new(),
+        _ => format!(" ({})", edge_conditions.join(", ")),
+    }
+}
+
 #[cfg(test)]
 mod format_tests {
     use super::*;
@@ -113,6 +213,33 @@ mod format_tests {
         assert_eq!(Charset::from_str(input_string), expected_enum_result);
     }
 
+    #[rstest(
+        input_edge_annotations,
+        expected_edge_conditions,
+        case(vec![], vec![]),
+        case(vec![EdgeAnnotation::Features], vec!["feature \"std\""]),
+        case(vec![EdgeAnnotation::Targets], vec!["cfg(unix)"]),
+        case(
+            vec![EdgeAnnotation::Targets, EdgeAnnotation::Features],
+            vec!["feature \"std\"", "cfg(unix)"]
+        )
+    )]
+    fn get_edge_conditions_test(
+        input_edge_annotations: Vec<EdgeAnnotation>,
+        expected_edge_conditions: Vec<&str>,
+    ) {
+        let dependency_edge = DependencyEdge {
+            kind: DependencyKind::Normal,
+            target: Some(String::from("cfg(unix)")),
+            features: vec![String::from("std")],
+        };
+
+        assert_eq!(
+            get_edge_conditions(&dependency_edge, &input_edge_annotations),
+            expected_edge_conditions
+        );
+    }
+
     #[rstest]
     fn get_kind_group_name_test() {
         assert_eq!(
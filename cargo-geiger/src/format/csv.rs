@@ -0,0 +1,376 @@
+use crate::format::print_config::OutputFormat;
+use crate::format::table::TableParameters;
+use crate::graph::Graph;
+use crate::mapping::{CargoMetadataParameters, ToCargoGeigerPackageId};
+use crate::scan::{
+    package_unsafe_stats, root_package_not_in_graph, ScanResult,
+};
+
+use cargo_geiger_serde::{CounterBlock, Source};
+use cargo_metadata::PackageId;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{Bfs, EdgeRef, Walker};
+use petgraph::Direction;
+use std::collections::{BTreeSet, HashMap};
+
+const COUNTER_NAMES: [&str; 5] =
+    ["functions", "exprs", "item_impls", "item_traits", "methods"];
+
+/// The name, version and source of a package, only its id when the package
+/// is missing from the metadata
+type PackageColumns = (String, String, String);
+
+/// Renders the packages reachable from `root_package_id` as comma or tab
+/// separated values, depending on the output format, with one row per
+/// package or, with `per_file`, one row per scanned `.rs` file.
+pub fn create_csv_export(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: &PackageId,
+    table_parameters: &TableParameters,
+    per_file: bool,
+) -> ScanResult {
+    let separator = match table_parameters.print_config.output_format {
+        OutputFormat::Tsv => '\t',
+        _ => ',',
+    };
+    let root_index = match graph.nodes.get(root_package_id) {
+        Some(root_index) => *root_index,
+        None => return root_package_not_in_graph(root_package_id),
+    };
+    let depths = package_depths(graph, root_index);
+    let mut warning_count = 0;
+
+    let mut rows = Vec::<(PackageColumns, Vec<String>)>::new();
+    for node_index in Bfs::new(&graph.graph, root_index).iter(&graph.graph) {
+        let package_id = &graph.graph[node_index];
+        let package_columns =
+            package_columns(cargo_metadata_parameters, package_id);
+        let package_metrics = table_parameters
+            .geiger_context
+            .package_id_to_metrics
+            .get(package_id);
+        if package_metrics.is_none() {
+            eprintln!("WARNING: No metrics found for package: {}", package_id);
+            warning_count += 1;
+        }
+
+        if per_file {
+            let rs_files_used =
+                table_parameters.rs_files_used_by_package.get(package_id);
+            for (path, rs_file_metrics_wrapper) in package_metrics
+                .iter()
+                .flat_map(|package_metrics| &package_metrics.rs_path_to_metrics)
+            {
+                let mut row = vec![
+                    path.display().to_string(),
+                    rs_files_used
+                        .map(|rs_files_used| rs_files_used.contains(path))
+                        .unwrap_or(false)
+                        .to_string(),
+                    rs_file_metrics_wrapper.is_crate_entry_point.to_string(),
+                    rs_file_metrics_wrapper.metrics.forbids_unsafe.to_string(),
+                ];
+                row.extend(counter_columns(
+                    &rs_file_metrics_wrapper.metrics.counters,
+                ));
+                rows.push((package_columns.clone(), row));
+            }
+            continue;
+        }
+
+        let mut row = vec![
+            dependency_kinds(graph, node_index, &depths),
+            depths[&node_index].to_string(),
+        ];
+        match package_metrics {
+            Some(package_metrics) => {
+                let unsafe_info = package_unsafe_stats(
+                    package_metrics,
+                    package_id,
+                    table_parameters.rs_files_used_by_package,
+                );
+                row.push(unsafe_info.forbids_unsafe.to_string());
+                row.extend(counter_columns(&unsafe_info.used));
+                row.extend(counter_columns(&unsafe_info.unused));
+            }
+            // Forbid status and counters are unknown
+            None => {
+                row.extend(vec![String::new(); 1 + 2 * 2 * COUNTER_NAMES.len()])
+            }
+        }
+        rows.push((package_columns, row));
+    }
+    rows.sort();
+
+    let mut scan_output_lines =
+        vec![format_row(&header_columns(per_file), separator)];
+    for ((name, version, source), row) in rows {
+        let mut columns = vec![name, version, source];
+        columns.extend(row);
+        scan_output_lines.push(format_row(&columns, separator));
+    }
+
+    ScanResult {
+        scan_output_lines,
+        warning_count,
+    }
+}
+
+fn header_columns(per_file: bool) -> Vec<String> {
+    let mut columns = vec![
+        String::from("name"),
+        String::from("version"),
+        String::from("source"),
+    ];
+    let counter_header_columns = |prefix: &'static str| {
+        COUNTER_NAMES.iter().flat_map(move |counter_name| {
+            [
+                format!("{}{}_safe", prefix, counter_name),
+                format!("{}{}_unsafe", prefix, counter_name),
+            ]
+        })
+    };
+    if per_file {
+        columns.extend(
+            ["path", "used", "entry_point", "forbids_unsafe"]
+                .iter()
+                .map(|column| column.to_string()),
+        );
+        columns.extend(counter_header_columns(""));
+    } else {
+        columns.extend(
+            ["dependency_kinds", "depth", "forbids_unsafe"]
+                .iter()
+                .map(|column| column.to_string()),
+        );
+        columns.extend(counter_header_columns("used_"));
+        columns.extend(counter_header_columns("unused_"));
+    }
+    columns
+}
+
+fn package_columns(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    package_id: &PackageId,
+) -> PackageColumns {
+    match package_id
+        .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)
+    {
+        Some(package_id) => (
+            package_id.name,
+            package_id.version.to_string(),
+            format_source(&package_id.source),
+        ),
+        None => (package_id.repr.clone(), String::new(), String::new()),
+    }
+}
+
+/// The source in the format of the `source` field of `cargo metadata`
+fn format_source(source: &Source) -> String {
+    match source {
+        Source::Git { url, rev } => format!("git+{}#{}", url, rev),
+        Source::Registry { url, .. } => format!("registry+{}", url),
+        Source::Path(url) => format!("path+{}", url),
+    }
+}
+
+/// The distance of every package reachable from `root_index`, in
+/// dependencies
+fn package_depths(
+    graph: &Graph,
+    root_index: NodeIndex,
+) -> HashMap<NodeIndex, usize> {
+    let mut depths = HashMap::from([(root_index, 0)]);
+    for node_index in Bfs::new(&graph.graph, root_index).iter(&graph.graph) {
+        let depth = depths[&node_index];
+        for dependency_index in graph.graph.neighbors(node_index) {
+            depths.entry(dependency_index).or_insert(depth + 1);
+        }
+    }
+    depths
+}
+
+/// The kinds of the dependencies on the package at `node_index` from the
+/// packages reachable from the root, e.g. `build;normal`
+fn dependency_kinds(
+    graph: &Graph,
+    node_index: NodeIndex,
+    depths: &HashMap<NodeIndex, usize>,
+) -> String {
+    graph
+        .graph
+        .edges_directed(node_index, Direction::Incoming)
+        .filter(|edge| depths.contains_key(&edge.source()))
+        .map(|edge| edge.weight().kind.to_string())
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+/// The safe and unsafe counts of every field of `counter_block`, in the order
+/// of `COUNTER_NAMES`
+fn counter_columns(counter_block: &CounterBlock) -> Vec<String> {
+    [
+        &counter_block.functions,
+        &counter_block.exprs,
+        &counter_block.item_impls,
+        &counter_block.item_traits,
+        &counter_block.methods,
+    ]
+    .iter()
+    .flat_map(|count| [count.safe.to_string(), count.unsafe_.to_string()])
+    .collect()
+}
+
+/// Joins `columns` with `separator`. In comma separated values, fields
+/// containing the separator, a quote or a line break are quoted. Tab
+/// separated values can't be quoted, such characters are replaced by spaces.
+fn format_row(columns: &[String], separator: char) -> String {
+    columns
+        .iter()
+        .map(|column| {
+            if separator == '\t' {
+                column.replace(['\t', '\n', '\r'], " ")
+            } else if column.contains([separator, '"', '\n', '\r']) {
+                format!("\"{}\"", column.replace('"', "\"\""))
+            } else {
+                column.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(&separator.to_string())
+}
+
+#[cfg(test)]
+mod csv_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Count;
+    use cargo_metadata::DependencyKind;
+    use rstest::*;
+    use url::Url;
+
+    #[rstest]
+    fn package_depths_and_dependency_kinds_test() {
+        // 0 -> 1 (normal) -> 2 (build), 0 -> 2 (normal), 3 -> 2 (dev)
+        let mut graph = Graph {
+            graph: Default::default(),
+            nodes: HashMap::new(),
+        };
+        let node_indices = (0..4)
+            .map(|index| {
+                let package_id = PackageId {
+                    repr: format!("package_{}", index),
+                };
+                let node_index = graph.graph.add_node(package_id.clone());
+                graph.nodes.insert(package_id, node_index);
+                node_index
+            })
+            .collect::<Vec<NodeIndex>>();
+        for (from, to, kind) in [
+            (0, 1, DependencyKind::Normal),
+            (1, 2, DependencyKind::Build),
+            (0, 2, DependencyKind::Normal),
+            (3, 2, DependencyKind::Development),
+        ] {
+            graph.graph.add_edge(
+                node_indices[from],
+                node_indices[to],
+                kind.into(),
+            );
+        }
+
+        let depths = package_depths(&graph, node_indices[0]);
+
+        assert_eq!(depths[&node_indices[1]], 1);
+        assert_eq!(depths[&node_indices[2]], 1);
+        assert!(!depths.contains_key(&node_indices[3]));
+        assert_eq!(dependency_kinds(&graph, node_indices[0], &depths), "");
+        assert_eq!(
+            dependency_kinds(&graph, node_indices[2], &depths),
+            "build;normal"
+        );
+    }
+
+    #[rstest]
+    fn counter_columns_test() {
+        let counter_block = CounterBlock {
+            functions: Count {
+                safe: 1,
+                unsafe_: 2,
+            },
+            methods: Count {
+                safe: 3,
+                unsafe_: 4,
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            counter_columns(&counter_block),
+            vec!["1", "2", "0", "0", "0", "0", "0", "0", "3", "4"]
+        );
+    }
+
+    #[rstest]
+    fn header_columns_test() {
+        let package_header_columns = header_columns(false);
+        let file_header_columns = header_columns(true);
+
+        assert_eq!(package_header_columns.len(), 6 + 20);
+        assert_eq!(package_header_columns[6], "used_functions_safe");
+        assert_eq!(package_header_columns[25], "unused_methods_unsafe");
+        assert_eq!(file_header_columns.len(), 7 + 10);
+        assert_eq!(file_header_columns[3], "path");
+    }
+
+    #[rstest(
+        input_source,
+        expected_source,
+        case(
+            Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap()
+            },
+            "registry+https://github.com/rust-lang/crates.io-index"
+        ),
+        case(
+            Source::Git {
+                url: Url::parse("https://github.com/a/b").unwrap(),
+                rev: String::from("abc123")
+            },
+            "git+https://github.com/a/b#abc123"
+        ),
+        case(
+            Source::Path(Url::parse("file:///a/b").unwrap()),
+            "path+file:///a/b"
+        )
+    )]
+    fn format_source_test(input_source: Source, expected_source: &str) {
+        assert_eq!(format_source(&input_source), expected_source);
+    }
+
+    #[rstest(
+        input_columns,
+        input_separator,
+        expected_row,
+        case(vec!["a", "b"], ',', "a,b"),
+        case(vec!["a,b", "say \"hi\""], ',', "\"a,b\",\"say \"\"hi\"\"\""),
+        case(vec!["a\tb", "c,d"], '\t', "a b\tc,d")
+    )]
+    fn format_row_test(
+        input_columns: Vec<&str>,
+        input_separator: char,
+        expected_row: &str,
+    ) {
+        let columns = input_columns
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        assert_eq!(format_row(&columns, input_separator), expected_row);
+    }
+}
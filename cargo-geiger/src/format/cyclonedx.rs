@@ -0,0 +1,334 @@
+use crate::scan::ScanResult;
+
+use cargo_geiger_serde::{
+    CounterBlock, PackageId, ReportEntry, SafetyReport, Source,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use url::form_urlencoded::byte_serialize;
+
+/// Index urls of crates.io, whose packages need no repository in their purl
+const CRATES_IO_INDEX_URLS: [&str; 2] = [
+    "https://github.com/rust-lang/crates.io-index",
+    "https://index.crates.io/",
+];
+
+/// Renders `report` as a `CycloneDX` JSON bill of materials, with a component
+/// for every package carrying its unsafe counters and forbid status as
+/// properties, and the dependencies of every package. The package with
+/// `root_package_id` is the subject of the bill of materials.
+pub fn create_cyclonedx_bom(
+    report: &SafetyReport,
+    root_package_id: Option<&PackageId>,
+) -> ScanResult {
+    let mut components = report
+        .packages
+        .values()
+        .map(|report_entry| {
+            (
+                &report_entry.package.id,
+                component(&report_entry.package.id, Some(report_entry)),
+            )
+        })
+        .chain(
+            report
+                .packages_without_metrics
+                .iter()
+                .map(|package_id| (package_id, component(package_id, None))),
+        )
+        .collect::<Vec<(&PackageId, Value)>>();
+    components.sort_by_key(|(package_id, _)| purl(package_id));
+    let root_component = root_package_id.and_then(|root_package_id| {
+        components
+            .iter()
+            .position(|(package_id, _)| *package_id == root_package_id)
+            .map(|position| components.remove(position).1)
+    });
+
+    let mut dependencies = report
+        .packages
+        .values()
+        .map(|report_entry| {
+            let package = &report_entry.package;
+            let depends_on = package
+                .dependencies
+                .iter()
+                .chain(&package.build_dependencies)
+                .chain(&package.dev_dependencies)
+                .map(purl)
+                .collect::<BTreeSet<String>>();
+            json!({
+                "ref": purl(&package.id),
+                "dependsOn": depends_on,
+            })
+        })
+        .collect::<Vec<Value>>();
+    dependencies.sort_by(|a, b| a["ref"].as_str().cmp(&b["ref"].as_str()));
+
+    let mut metadata = json!({
+        "tools": [{
+            "vendor": "rust-secure-code",
+            "name": "cargo-geiger",
+            "version": env!("CARGO_PKG_VERSION"),
+        }],
+    });
+    if let Some(root_component) = root_component {
+        metadata["component"] = root_component;
+    }
+    let bom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.4",
+        "version": 1,
+        "metadata": metadata,
+        "components": components
+            .into_iter()
+            .map(|(_, component)| component)
+            .collect::<Vec<Value>>(),
+        "dependencies": dependencies,
+    });
+
+    ScanResult {
+        scan_output_lines: vec![serde_json::to_string(&bom).unwrap()],
+        warning_count: 0,
+    }
+}
+
+/// The component of `package_id`, with the counters of `report_entry` if the
+/// package was scanned
+fn component(
+    package_id: &PackageId,
+    report_entry: Option<&ReportEntry>,
+) -> Value {
+    let purl = purl(package_id);
+    let mut properties = vec![property(
+        "cargo-geiger:scanned",
+        report_entry.is_some().to_string(),
+    )];
+    if let Some(report_entry) = report_entry {
+        let unsafety = &report_entry.unsafety;
+        properties.push(property(
+            "cargo-geiger:forbids_unsafe",
+            unsafety.forbids_unsafe.to_string(),
+        ));
+        properties.extend(counter_properties("used", &unsafety.used));
+        properties.extend(counter_properties("unused", &unsafety.unused));
+    }
+
+    json!({
+        "type": "library",
+        "bom-ref": purl,
+        "name": package_id.name,
+        "version": package_id.version.to_string(),
+        "purl": purl,
+        "properties": properties,
+    })
+}
+
+fn counter_properties(
+    prefix: &str,
+    counter_block: &CounterBlock,
+) -> Vec<Value> {
+    [
+        ("functions", &counter_block.functions),
+        ("exprs", &counter_block.exprs),
+        ("item_impls", &counter_block.item_impls),
+        ("item_traits", &counter_block.item_traits),
+        ("methods", &counter_block.methods),
+    ]
+    .iter()
+    .flat_map(|(counter_name, count)| {
+        [
+            property(
+                &format!("cargo-geiger:{}:{}:safe", prefix, counter_name),
+                count.safe.to_string(),
+            ),
+            property(
+                &format!("cargo-geiger:{}:{}:unsafe", prefix, counter_name),
+                count.unsafe_.to_string(),
+            ),
+        ]
+    })
+    .collect()
+}
+
+fn property(name: &str, value: String) -> Value {
+    json!({ "name": name, "value": value })
+}
+
+/// The package url of `package_id`. Packages from registries other than
+/// crates.io carry the index url, git packages the repository and revision,
+/// if any, and path packages their location.
+fn purl(package_id: &PackageId) -> String {
+    let qualifier = match &package_id.source {
+        Source::Registry { url, .. } => {
+            if CRATES_IO_INDEX_URLS.contains(&url.as_str()) {
+                None
+            } else {
+                Some(("repository_url", url.to_string()))
+            }
+        }
+        // Without a `rev`, e.g. for a branch, the revision is unknown.
+        Source::Git { url, rev } if rev.is_empty() => {
+            Some(("vcs_url", format!("git+{}", url)))
+        }
+        Source::Git { url, rev } => {
+            Some(("vcs_url", format!("git+{}@{}", url, rev)))
+        }
+        Source::Path(url) => Some(("download_url", url.to_string())),
+    };
+
+    let purl = format!("pkg:cargo/{}@{}", package_id.name, package_id.version);
+    match qualifier {
+        Some((key, value)) => format!(
+            "{}?{}={}",
+            purl,
+            key,
+            byte_serialize(value.as_bytes()).collect::<String>()
+        ),
+        None => purl,
+    }
+}
+
+#[cfg(test)]
+mod cyclonedx_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{Count, DependencyKind, PackageInfo, UnsafeInfo};
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest(
+        input_source,
+        expected_purl,
+        case(
+            Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap()
+            },
+            "pkg:cargo/package@1.2.3"
+        ),
+        case(
+            Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://my-registry.com/index").unwrap()
+            },
+            "pkg:cargo/package@1.2.3?repository_url=https%3A%2F%2Fmy-registry.com%2Findex"
+        ),
+        case(
+            Source::Git {
+                url: Url::parse("https://github.com/a/b").unwrap(),
+                rev: String::from("abc123")
+            },
+            "pkg:cargo/package@1.2.3?vcs_url=git%2Bhttps%3A%2F%2Fgithub.com%2Fa%2Fb%40abc123"
+        ),
+        case(
+            Source::Git {
+                url: Url::parse("https://github.com/a/b").unwrap(),
+                rev: String::new()
+            },
+            "pkg:cargo/package@1.2.3?vcs_url=git%2Bhttps%3A%2F%2Fgithub.com%2Fa%2Fb"
+        ),
+        case(
+            Source::Path(Url::parse("file:///a/b").unwrap()),
+            "pkg:cargo/package@1.2.3?download_url=file%3A%2F%2F%2Fa%2Fb"
+        )
+    )]
+    fn purl_test(input_source: Source, expected_purl: &str) {
+        let package_id = PackageId {
+            name: String::from("package"),
+            version: Version::new(1, 2, 3),
+            source: input_source,
+        };
+
+        assert_eq!(purl(&package_id), expected_purl);
+    }
+
+    #[rstest]
+    fn create_cyclonedx_bom_test() {
+        let package_id = |name: &str| PackageId {
+            name: String::from(name),
+            version: Version::new(0, 1, 0),
+            source: Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            },
+        };
+        let mut root_package = PackageInfo::new(package_id("root"));
+        root_package.add_dependency(package_id("a"), DependencyKind::Normal);
+        root_package.add_dependency(package_id("b"), DependencyKind::Build);
+        let mut report = SafetyReport::default();
+        report.packages.insert(
+            package_id("root"),
+            ReportEntry {
+                package: root_package,
+                unsafety: UnsafeInfo {
+                    used: CounterBlock {
+                        exprs: Count {
+                            safe: 5,
+                            unsafe_: 2,
+                        },
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                modules: None,
+                targets: None,
+            },
+        );
+        report.packages.insert(
+            package_id("a"),
+            ReportEntry {
+                package: PackageInfo::new(package_id("a")),
+                unsafety: UnsafeInfo {
+                    forbids_unsafe: true,
+                    ..Default::default()
+                },
+                modules: None,
+                targets: None,
+            },
+        );
+        report.packages_without_metrics.insert(package_id("b"));
+
+        let scan_result =
+            create_cyclonedx_bom(&report, Some(&package_id("root")));
+        let bom =
+            serde_json::from_str::<Value>(&scan_result.scan_output_lines[0])
+                .unwrap();
+
+        assert_eq!(bom["metadata"]["component"]["name"], "root");
+        assert_eq!(
+            bom["components"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|component| component["purl"].as_str().unwrap())
+                .collect::<Vec<&str>>(),
+            vec!["pkg:cargo/a@0.1.0", "pkg:cargo/b@0.1.0"]
+        );
+        assert_eq!(
+            bom["components"][1]["properties"],
+            json!([{ "name": "cargo-geiger:scanned", "value": "false" }])
+        );
+        let root_properties = bom["metadata"]["component"]["properties"]
+            .as_array()
+            .unwrap();
+        assert_eq!(root_properties.len(), 2 + 2 * 2 * 5);
+        assert!(root_properties.contains(&json!({
+            "name": "cargo-geiger:used:exprs:unsafe",
+            "value": "2"
+        })));
+        assert_eq!(
+            bom["dependencies"],
+            json!([
+                { "ref": "pkg:cargo/a@0.1.0", "dependsOn": [] },
+                {
+                    "ref": "pkg:cargo/root@0.1.0",
+                    "dependsOn": ["pkg:cargo/a@0.1.0", "pkg:cargo/b@0.1.0"]
+                }
+            ])
+        );
+    }
+}
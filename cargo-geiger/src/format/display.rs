@@ -1,14 +1,141 @@
 use crate::format::pattern::Pattern;
-use crate::format::Chunk;
-use crate::mapping::{CargoMetadataParameters, GetPackageIdInformation};
+use crate::format::{Alignment, Chunk, UnsafeCounter};
+use crate::mapping::{
+    CargoMetadataParameters, GetPackageIdInformation, ToCargoGeigerPackageId,
+};
 
+use cargo_geiger_serde::{CounterBlock, Source, UnsafeInfo};
 use cargo_metadata::PackageId;
 use std::fmt;
 
 pub struct Display<'a> {
     pub cargo_metadata_parameters: &'a CargoMetadataParameters<'a>,
+    /// Depth of the package in the tree, for `{depth}`
+    pub depth: Option<usize>,
     pub pattern: &'a Pattern,
     pub package: &'a PackageId,
+    /// Unsafe usage of the package, for `{forbids}` and the `u.` counters
+    pub unsafe_info: Option<&'a UnsafeInfo>,
+}
+
+impl<'a> Display<'a> {
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn with_unsafe_info(mut self, unsafe_info: &'a UnsafeInfo) -> Self {
+        self.unsafe_info = Some(unsafe_info);
+        self
+    }
+
+    /// Whether `placeholder` has a value, for conditional chunks. Counters
+    /// have one when unsafe items of theirs are used, `{forbids}` when the
+    /// package forbids unsafe code, other placeholders when not empty.
+    fn has_value(&self, placeholder: &Chunk) -> bool {
+        match placeholder {
+            Chunk::Counter(unsafe_counter) => self
+                .unsafe_info
+                .map(|unsafe_info| {
+                    unsafe_count(&unsafe_info.used, *unsafe_counter) > 0
+                })
+                .unwrap_or(false),
+            Chunk::ForbidsUnsafe => self
+                .unsafe_info
+                .map(|unsafe_info| unsafe_info.forbids_unsafe)
+                .unwrap_or(false),
+            _ => self
+                .render(placeholder)
+                .map(|value| !value.is_empty())
+                .unwrap_or(false),
+        }
+    }
+
+    /// The value of `chunk`, `None` if unknown
+    fn render(&self, chunk: &Chunk) -> Option<String> {
+        let krates = self.cargo_metadata_parameters.krates;
+        match chunk {
+            Chunk::Authors => self
+                .package
+                .get_package_id_authors(krates)
+                .map(|authors| authors.join(", ")),
+            Chunk::Conditional(placeholder, chunks) => {
+                if self.has_value(placeholder) {
+                    Some(
+                        chunks
+                            .iter()
+                            .filter_map(|chunk| self.render(chunk))
+                            .collect(),
+                    )
+                } else {
+                    Some(String::new())
+                }
+            }
+            Chunk::Counter(unsafe_counter) => {
+                self.unsafe_info.map(|unsafe_info| {
+                    let used = unsafe_count(&unsafe_info.used, *unsafe_counter);
+                    let unused =
+                        unsafe_count(&unsafe_info.unused, *unsafe_counter);
+                    format!("{}/{}", used, used + unused)
+                })
+            }
+            Chunk::Depth => self.depth.map(|depth| depth.to_string()),
+            Chunk::Description => {
+                self.package.get_package_id_description(krates)
+            }
+            Chunk::Edition => self.package.get_package_id_edition(krates),
+            Chunk::ForbidsUnsafe => self
+                .unsafe_info
+                .map(|unsafe_info| unsafe_info.forbids_unsafe.to_string()),
+            Chunk::License => self.package.get_package_id_licence(krates),
+            Chunk::Name => self
+                .package
+                .get_package_id_name_and_version(krates)
+                .map(|(package_name, _)| package_name),
+            Chunk::Package => {
+                match self.package.get_package_id_name_and_version(krates) {
+                    Some((package_name, package_version)) => {
+                        Some(format!("{} {}", package_name, package_version))
+                    }
+                    None => {
+                        eprintln!("Failed to format Package: {}", self.package);
+                        None
+                    }
+                }
+            }
+            Chunk::Padded(placeholder, alignment, width) => {
+                let value = self.render(placeholder).unwrap_or_default();
+                Some(match alignment {
+                    Alignment::Left => format!("{:<1$}", value, width),
+                    Alignment::Right => format!("{:>1$}", value, width),
+                })
+            }
+            Chunk::Raw(s) => Some(s.clone()),
+            Chunk::Repository => self.package.get_package_id_repository(krates),
+            Chunk::SourceKind => self.source().map(|source| {
+                String::from(match source {
+                    Source::Git { .. } => "git",
+                    Source::Path(_) => "path",
+                    Source::Registry { .. } => "registry",
+                })
+            }),
+            Chunk::SourceUrl => self.source().map(|source| match source {
+                Source::Git { url, .. }
+                | Source::Path(url)
+                | Source::Registry { url, .. } => url.to_string(),
+            }),
+            Chunk::Version => self
+                .package
+                .get_package_id_name_and_version(krates)
+                .map(|(_, package_version)| package_version.to_string()),
+        }
+    }
+
+    fn source(&self) -> Option<Source> {
+        self.package
+            .to_cargo_geiger_package_id(self.cargo_metadata_parameters.metadata)
+            .map(|package_id| package_id.source)
+    }
 }
 
 impl<'a> fmt::Display for Display<'a> {
@@ -20,43 +147,34 @@ impl<'a> fmt::Display for Display<'a> {
     #[allow(clippy::print_in_format_impl)]
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         for chunk in &self.pattern.chunks {
-            match *chunk {
-                Chunk::License => {
-                    if let Some(ref license) =
-                        self.package.get_package_id_licence(
-                            self.cargo_metadata_parameters.krates,
-                        )
-                    {
-                        (write!(fmt, "{}", license))?
-                    }
-                }
-                Chunk::Package => {
-                    if let Some((package_name, package_version)) =
-                        self.package.get_package_id_name_and_version(
-                            self.cargo_metadata_parameters.krates,
-                        )
-                    {
-                        (write!(fmt, "{} {}", package_name, package_version))?
-                    } else {
-                        eprintln!("Failed to format Package: {}", self.package)
-                    }
-                }
-                Chunk::Raw(ref s) => (fmt.write_str(s))?,
-                Chunk::Repository => {
-                    if let Some(ref repository) =
-                        self.package.get_package_id_repository(
-                            self.cargo_metadata_parameters.krates,
-                        )
-                    {
-                        (write!(fmt, "{}", repository))?
-                    }
-                }
+            if let Some(value) = self.render(chunk) {
+                (fmt.write_str(&value))?
             }
         }
         Ok(())
     }
 }
 
+fn unsafe_count(
+    counter_block: &CounterBlock,
+    unsafe_counter: UnsafeCounter,
+) -> u64 {
+    match unsafe_counter {
+        UnsafeCounter::Functions => counter_block.functions.unsafe_,
+        UnsafeCounter::Exprs => counter_block.exprs.unsafe_,
+        UnsafeCounter::Impls => counter_block.item_impls.unsafe_,
+        UnsafeCounter::Traits => counter_block.item_traits.unsafe_,
+        UnsafeCounter::Methods => counter_block.methods.unsafe_,
+        UnsafeCounter::Total => {
+            counter_block.functions.unsafe_
+                + counter_block.exprs.unsafe_
+                + counter_block.item_impls.unsafe_
+                + counter_block.item_traits.unsafe_
+                + counter_block.methods.unsafe_
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod display_tests {
     use super::*;
@@ -64,7 +182,8 @@ pub mod display_tests {
     use crate::format::pattern::Pattern;
     use crate::format::Chunk;
 
-    use cargo_metadata::{CargoOpt, MetadataCommand};
+    use cargo_geiger_serde::Count;
+    use cargo_metadata::{CargoOpt, Metadata, MetadataCommand};
     use krates::Builder as KratesBuilder;
     use rstest::*;
 
@@ -105,10 +224,107 @@ pub mod display_tests {
                 krates: &krates,
                 metadata: &metadata,
             },
+            depth: None,
             pattern: &input_pattern,
             package: &package_id,
+            unsafe_info: None,
         };
 
         assert_eq!(format!("{}", display), expected_formatted_string);
     }
+
+    #[rstest(
+        input_format,
+        input_forbids_unsafe,
+        expected_formatted_string,
+        case("{name}|{version}|{edition}", false, "a|1.2.3|2021"),
+        case(
+            "{description}|{authors}",
+            false,
+            "Does things|Jane <jane@example.com>, John"
+        ),
+        case(
+            "{source}|{source_url}",
+            false,
+            "registry|https://github.com/rust-lang/crates.io-index"
+        ),
+        case("{u.exprs} {u.methods} {u.total}", false, "2/5 0/0 3/6"),
+        case("{depth}|{forbids}", true, "3|true"),
+        case("[{name:<4}][{u.exprs:>5}][{l:2}]", false, "[a   ][  2/5][  ]"),
+        case("{?forbids:lock }{?u.exprs:rads }{?l:({l})}", false, "rads "),
+        case("{?forbids:lock }{?u.methods:rads }{p}", true, "lock a 1.2.3")
+    )]
+    fn display_placeholders_test(
+        input_format: &str,
+        input_forbids_unsafe: bool,
+        expected_formatted_string: &str,
+    ) {
+        let package_id =
+            "a 1.2.3 (registry+https://github.com/rust-lang/crates.io-index)";
+        let metadata: Metadata = serde_json::from_value(serde_json::json!({
+            "packages": [{
+                "name": "a",
+                "version": "1.2.3",
+                "id": package_id,
+                "authors": ["Jane <jane@example.com>", "John"],
+                "description": "Does things",
+                "edition": "2021",
+                "source": "registry+https://github.com/rust-lang/crates.io-index",
+                "dependencies": [],
+                "targets": [],
+                "features": {},
+                "manifest_path": "/a-1.2.3/Cargo.toml",
+            }],
+            "workspace_members": [package_id],
+            "resolve": {
+                "nodes": [{
+                    "id": package_id,
+                    "dependencies": [],
+                    "deps": [],
+                    "features": [],
+                }],
+                "root": package_id,
+            },
+            "workspace_root": "/",
+            "target_directory": "/target",
+            "version": 1,
+        }))
+        .unwrap();
+        let krates = KratesBuilder::new()
+            .build_with_metadata(metadata.clone(), |_| ())
+            .unwrap();
+        let cargo_metadata_parameters = CargoMetadataParameters {
+            krates: &krates,
+            metadata: &metadata,
+        };
+        let unsafe_info = UnsafeInfo {
+            used: CounterBlock {
+                exprs: Count {
+                    safe: 4,
+                    unsafe_: 2,
+                },
+                item_impls: Count {
+                    safe: 0,
+                    unsafe_: 1,
+                },
+                ..Default::default()
+            },
+            unused: CounterBlock {
+                exprs: Count {
+                    safe: 0,
+                    unsafe_: 3,
+                },
+                ..Default::default()
+            },
+            forbids_unsafe: input_forbids_unsafe,
+        };
+        let pattern = Pattern::try_build(input_format).unwrap();
+
+        let display = pattern
+            .display(&cargo_metadata_parameters, &metadata.packages[0].id)
+            .with_depth(3)
+            .with_unsafe_info(&unsafe_info);
+
+        assert_eq!(display.to_string(), expected_formatted_string);
+    }
 }
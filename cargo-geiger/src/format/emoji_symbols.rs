@@ -47,5 +47,6 @@ impl EmojiSymbols {
         (self.output_format == OutputFormat::Utf8
             && console::Term::stdout().features().wants_emoji())
             || self.output_format == OutputFormat::GitHubMarkdown
+            || self.output_format == OutputFormat::Html
     }
 }
@@ -0,0 +1,499 @@
+use crate::format::print_config::OutputFormat;
+use crate::format::table::{get_module_detection_status, TableParameters};
+use crate::format::CrateDetectionStatus;
+use crate::graph::Graph;
+use crate::mapping::CargoMetadataParameters;
+use crate::scan::{
+    package_unsafe_stats, root_package_not_in_graph, ScanResult,
+};
+
+use cargo_metadata::{DependencyKind, PackageId};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{Bfs, EdgeRef, Reversed, Walker};
+use petgraph::Direction;
+use std::collections::{HashMap, HashSet};
+
+struct ExportNode {
+    index: usize,
+    label: String,
+    /// `None` if no metrics were found for the package
+    status: Option<CrateDetectionStatus>,
+    /// Number of safe dependencies collapsed into the node
+    collapsed_dependencies: usize,
+}
+
+struct ExportEdge {
+    from: usize,
+    to: usize,
+    kind: DependencyKind,
+}
+
+/// Renders the part of `graph` reachable from `root_package_id` as a DOT or
+/// Mermaid graph, with nodes coloured by their `CrateDetectionStatus` and
+/// edges styled by their `DependencyKind`. With `collapse_safe`, packages
+/// whose dependencies are all free of unsafe code are shown as a single node.
+pub fn create_graph_export(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: &PackageId,
+    table_parameters: &TableParameters,
+    collapse_safe: bool,
+) -> ScanResult {
+    let root_index = match graph.nodes.get(root_package_id) {
+        Some(root_index) => *root_index,
+        None => return root_package_not_in_graph(root_package_id),
+    };
+    let mut warning_count = 0;
+
+    let mut node_statuses =
+        HashMap::<NodeIndex, (Option<CrateDetectionStatus>, u64)>::new();
+    for node_index in Bfs::new(&graph.graph, root_index).iter(&graph.graph) {
+        let package_id = &graph.graph[node_index];
+        let node_status = match table_parameters
+            .geiger_context
+            .package_id_to_metrics
+            .get(package_id)
+        {
+            Some(package_metrics) => {
+                let unsafe_info = package_unsafe_stats(
+                    package_metrics,
+                    package_id,
+                    table_parameters.rs_files_used_by_package,
+                );
+                (
+                    Some(get_module_detection_status(&unsafe_info)),
+                    unsafe_info.used.exprs.unsafe_,
+                )
+            }
+            None => {
+                eprintln!(
+                    "WARNING: No metrics found for package: {}",
+                    package_id
+                );
+                warning_count += 1;
+                (None, 0)
+            }
+        };
+        node_statuses.insert(node_index, node_status);
+    }
+
+    // Packages without metrics are never collapsed, as they may be unsafe.
+    let unsafe_node_indices = node_statuses
+        .iter()
+        .filter(|(_, (status, _))| {
+            !matches!(
+                status,
+                Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe)
+                    | Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe)
+            )
+        })
+        .map(|(node_index, _)| *node_index)
+        .collect::<HashSet<NodeIndex>>();
+    let (mut visible_node_indices, collapsed_dependencies) = if collapse_safe {
+        collapse_safe_subtrees(graph, root_index, &unsafe_node_indices)
+    } else {
+        (node_statuses.keys().copied().collect(), HashMap::new())
+    };
+    visible_node_indices.sort();
+
+    let nodes = visible_node_indices
+        .iter()
+        .map(|node_index| {
+            let (status, unsafe_expressions) =
+                node_statuses[node_index].clone();
+            ExportNode {
+                index: node_index.index(),
+                label: format!(
+                    "{}\n{} unsafe expressions",
+                    table_parameters.print_config.format.display(
+                        cargo_metadata_parameters,
+                        &graph.graph[*node_index]
+                    ),
+                    unsafe_expressions
+                ),
+                status,
+                collapsed_dependencies: collapsed_dependencies
+                    .get(node_index)
+                    .copied()
+                    .unwrap_or(0),
+            }
+        })
+        .collect::<Vec<ExportNode>>();
+    let edges =
+        export_edges(graph, &visible_node_indices, &collapsed_dependencies);
+
+    let scan_output_lines = match table_parameters.print_config.output_format {
+        OutputFormat::Mermaid => mermaid_lines(&nodes, &edges),
+        _ => dot_lines(&nodes, &edges),
+    };
+
+    ScanResult {
+        scan_output_lines,
+        warning_count,
+    }
+}
+
+/// The dependencies of the visible nodes that are not collapsed. Parallel
+/// edges of the same kind, e.g. for different targets, are only exported once.
+fn export_edges(
+    graph: &Graph,
+    visible_node_indices: &[NodeIndex],
+    collapsed_dependencies: &HashMap<NodeIndex, usize>,
+) -> Vec<ExportEdge> {
+    visible_node_indices
+        .iter()
+        .filter(|node_index| !collapsed_dependencies.contains_key(node_index))
+        .flat_map(|node_index| {
+            let mut edges = graph
+                .graph
+                .edges_directed(*node_index, Direction::Outgoing)
+                .map(|edge| ExportEdge {
+                    from: edge.source().index(),
+                    to: edge.target().index(),
+                    kind: edge.weight().kind,
+                })
+                .collect::<Vec<ExportEdge>>();
+            edges.sort_by_key(|edge| (edge.to, edge.kind.to_string()));
+            edges.dedup_by_key(|edge| (edge.to, edge.kind));
+            edges
+        })
+        .collect()
+}
+
+/// The nodes reachable from `root_index` without passing through a node whose
+/// dependencies are all safe, and the number of dependencies hidden behind
+/// each of those collapsed nodes
+fn collapse_safe_subtrees(
+    graph: &Graph,
+    root_index: NodeIndex,
+    unsafe_node_indices: &HashSet<NodeIndex>,
+) -> (Vec<NodeIndex>, HashMap<NodeIndex, usize>) {
+    let reversed_graph = Reversed(&graph.graph);
+    let mut reaches_unsafe_node = HashSet::<NodeIndex>::new();
+    for unsafe_node_index in unsafe_node_indices {
+        if reaches_unsafe_node.contains(unsafe_node_index) {
+            continue;
+        }
+        reaches_unsafe_node.extend(
+            Bfs::new(reversed_graph, *unsafe_node_index).iter(reversed_graph),
+        );
+    }
+
+    let mut visible_node_indices = vec![root_index];
+    let mut visited = HashSet::from([root_index]);
+    let mut collapsed_dependencies = HashMap::<NodeIndex, usize>::new();
+    let mut index = 0;
+    while index < visible_node_indices.len() {
+        let node_index = visible_node_indices[index];
+        index += 1;
+        if !reaches_unsafe_node.contains(&node_index) {
+            let dependency_count = Bfs::new(&graph.graph, node_index)
+                .iter(&graph.graph)
+                .count()
+                - 1;
+            if dependency_count > 0 {
+                collapsed_dependencies.insert(node_index, dependency_count);
+                continue;
+            }
+        }
+        for dependency_index in graph.graph.neighbors(node_index) {
+            if visited.insert(dependency_index) {
+                visible_node_indices.push(dependency_index);
+            }
+        }
+    }
+
+    (visible_node_indices, collapsed_dependencies)
+}
+
+fn dot_lines(nodes: &[ExportNode], edges: &[ExportEdge]) -> Vec<String> {
+    let mut lines = vec![
+        String::from("digraph dependencies {"),
+        String::from("    node [shape=box, style=filled];"),
+    ];
+    for node in nodes {
+        lines.push(format!(
+            "    n{} [label=\"{}\", fillcolor=\"{}\"];",
+            node.index,
+            node_label(node)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n"),
+            status_colour(&node.status)
+        ));
+    }
+    for edge in edges {
+        let attributes = match edge.kind {
+            DependencyKind::Build => " [style=dashed, label=\"build\"]",
+            DependencyKind::Development => " [style=dotted, label=\"dev\"]",
+            _ => "",
+        };
+        lines
+            .push(format!("    n{} -> n{}{};", edge.from, edge.to, attributes));
+    }
+    lines.push(String::from("}"));
+    lines
+}
+
+fn mermaid_lines(nodes: &[ExportNode], edges: &[ExportEdge]) -> Vec<String> {
+    let mut lines = vec![String::from("graph TD")];
+    for node in nodes {
+        lines.push(format!(
+            "    n{}[\"{}\"]",
+            node.index,
+            node_label(node)
+                .replace('"', "#quot;")
+                .replace('\n', "<br/>")
+        ));
+    }
+    for edge in edges {
+        let arrow = match edge.kind {
+            DependencyKind::Build => "==>|build|",
+            DependencyKind::Development => "-.->|dev|",
+            _ => "-->",
+        };
+        lines.push(format!("    n{} {} n{}", edge.from, arrow, edge.to));
+    }
+    for status in [
+        Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe),
+        Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe),
+        Some(CrateDetectionStatus::UnsafeDetected),
+        None,
+    ] {
+        let class_name = status_class_name(&status);
+        let node_ids = nodes
+            .iter()
+            .filter(|node| node.status == status)
+            .map(|node| format!("n{}", node.index))
+            .collect::<Vec<String>>();
+        lines.push(format!(
+            "    classDef {} fill:{}",
+            class_name,
+            status_colour(&status)
+        ));
+        if !node_ids.is_empty() {
+            lines.push(format!(
+                "    class {} {}",
+                node_ids.join(","),
+                class_name
+            ));
+        }
+    }
+    lines
+}
+
+fn node_label(node: &ExportNode) -> String {
+    match node.collapsed_dependencies {
+        0 => node.label.clone(),
+        collapsed_dependencies => format!(
+            "{}\n+{} safe dependencies",
+            node.label, collapsed_dependencies
+        ),
+    }
+}
+
+fn status_class_name(status: &Option<CrateDetectionStatus>) -> &'static str {
+    match status {
+        Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe) => "forbids",
+        Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe) => "allows",
+        Some(CrateDetectionStatus::UnsafeDetected) => "unsafe",
+        None => "unknown",
+    }
+}
+
+fn status_colour(status: &Option<CrateDetectionStatus>) -> &'static str {
+    match status {
+        Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe) => "#b7e1cd",
+        Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe) => "#fff2cc",
+        Some(CrateDetectionStatus::UnsafeDetected) => "#f4cccc",
+        None => "#d9d9d9",
+    }
+}
+
+#[cfg(test)]
+mod graph_export_tests {
+    use super::*;
+
+    use crate::graph::DependencyEdge;
+
+    use rstest::*;
+
+    #[rstest]
+    fn collapse_safe_subtrees_test() {
+        // 0 -> 1 -> 2 (unsafe), 0 -> 3 -> 4, 0 -> 5
+        let mut graph = Graph {
+            graph: Default::default(),
+            nodes: HashMap::new(),
+        };
+        let node_indices = (0..6)
+            .map(|index| {
+                let package_id = PackageId {
+                    repr: format!("package_{}", index),
+                };
+                let node_index = graph.graph.add_node(package_id.clone());
+                graph.nodes.insert(package_id, node_index);
+                node_index
+            })
+            .collect::<Vec<NodeIndex>>();
+        for (from, to) in [(0, 1), (1, 2), (0, 3), (3, 4), (0, 5)] {
+            graph.graph.add_edge(
+                node_indices[from],
+                node_indices[to],
+                DependencyKind::Normal.into(),
+            );
+        }
+
+        let (mut visible_node_indices, collapsed_dependencies) =
+            collapse_safe_subtrees(
+                &graph,
+                node_indices[0],
+                &HashSet::from([node_indices[2]]),
+            );
+        visible_node_indices.sort();
+
+        assert_eq!(
+            visible_node_indices,
+            vec![
+                node_indices[0],
+                node_indices[1],
+                node_indices[2],
+                node_indices[3],
+                node_indices[5]
+            ]
+        );
+        assert_eq!(
+            collapsed_dependencies,
+            HashMap::from([(node_indices[3], 1)])
+        );
+    }
+
+    #[rstest]
+    fn export_edges_test() {
+        let mut graph = Graph {
+            graph: Default::default(),
+            nodes: HashMap::new(),
+        };
+        let node_indices = (0..2)
+            .map(|index| {
+                let package_id = PackageId {
+                    repr: format!("package_{}", index),
+                };
+                let node_index = graph.graph.add_node(package_id.clone());
+                graph.nodes.insert(package_id, node_index);
+                node_index
+            })
+            .collect::<Vec<NodeIndex>>();
+        for (kind, target) in [
+            (DependencyKind::Normal, Some("cfg(unix)")),
+            (DependencyKind::Normal, Some("cfg(windows)")),
+            (DependencyKind::Build, None),
+        ] {
+            graph.graph.add_edge(
+                node_indices[0],
+                node_indices[1],
+                DependencyEdge {
+                    kind,
+                    target: target.map(String::from),
+                    features: vec![],
+                },
+            );
+        }
+
+        let edges = export_edges(&graph, &node_indices, &HashMap::new());
+
+        assert_eq!(
+            edges
+                .iter()
+                .map(|edge| (edge.from, edge.to, edge.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 1, DependencyKind::Build),
+                (0, 1, DependencyKind::Normal)
+            ]
+        );
+    }
+
+    #[rstest]
+    fn dot_lines_test() {
+        let (nodes, edges) = create_nodes_and_edges();
+
+        assert_eq!(
+            dot_lines(&nodes, &edges),
+            vec![
+                "digraph dependencies {",
+                "    node [shape=box, style=filled];",
+                "    n0 [label=\"root 0.1.0\\n3 unsafe expressions\", fillcolor=\"#f4cccc\"];",
+                "    n1 [label=\"build_dep 1.0.0\\n0 unsafe expressions\\n+2 safe dependencies\", fillcolor=\"#b7e1cd\"];",
+                "    n2 [label=\"dev_dep 1.0.0\\n0 unsafe expressions\", fillcolor=\"#d9d9d9\"];",
+                "    n0 -> n1 [style=dashed, label=\"build\"];",
+                "    n0 -> n2 [style=dotted, label=\"dev\"];",
+                "}",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn mermaid_lines_test() {
+        let (nodes, edges) = create_nodes_and_edges();
+
+        assert_eq!(
+            mermaid_lines(&nodes, &edges),
+            vec![
+                "graph TD",
+                "    n0[\"root 0.1.0<br/>3 unsafe expressions\"]",
+                "    n1[\"build_dep 1.0.0<br/>0 unsafe expressions<br/>+2 safe dependencies\"]",
+                "    n2[\"dev_dep 1.0.0<br/>0 unsafe expressions\"]",
+                "    n0 ==>|build| n1",
+                "    n0 -.->|dev| n2",
+                "    classDef forbids fill:#b7e1cd",
+                "    class n1 forbids",
+                "    classDef allows fill:#fff2cc",
+                "    classDef unsafe fill:#f4cccc",
+                "    class n0 unsafe",
+                "    classDef unknown fill:#d9d9d9",
+                "    class n2 unknown",
+            ]
+        );
+    }
+
+    fn create_nodes_and_edges() -> (Vec<ExportNode>, Vec<ExportEdge>) {
+        (
+            vec![
+                ExportNode {
+                    index: 0,
+                    label: String::from("root 0.1.0\n3 unsafe expressions"),
+                    status: Some(CrateDetectionStatus::UnsafeDetected),
+                    collapsed_dependencies: 0,
+                },
+                ExportNode {
+                    index: 1,
+                    label: String::from(
+                        "build_dep 1.0.0\n0 unsafe expressions",
+                    ),
+                    status: Some(
+                        CrateDetectionStatus::NoneDetectedForbidsUnsafe,
+                    ),
+                    collapsed_dependencies: 2,
+                },
+                ExportNode {
+                    index: 2,
+                    label: String::from("dev_dep 1.0.0\n0 unsafe expressions"),
+                    status: None,
+                    collapsed_dependencies: 0,
+                },
+            ],
+            vec![
+                ExportEdge {
+                    from: 0,
+                    to: 1,
+                    kind: DependencyKind::Build,
+                },
+                ExportEdge {
+                    from: 0,
+                    to: 2,
+                    kind: DependencyKind::Development,
+                },
+            ],
+        )
+    }
+}
@@ -0,0 +1,554 @@
+use crate::format::emoji_symbols::EmojiSymbols;
+use crate::format::print_config::OutputFormat;
+use crate::format::table::{get_module_detection_status, TableParameters};
+use crate::format::{CrateDetectionStatus, SymbolKind};
+use crate::graph::Graph;
+use crate::mapping::CargoMetadataParameters;
+use crate::scan::{
+    package_unsafe_stats, root_package_not_in_graph, RsFileMetricsWrapper,
+    ScanResult,
+};
+
+use cargo_geiger_serde::{CounterBlock, UnsafeInfo};
+use cargo_metadata::PackageId;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{Bfs, Walker};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+const COUNTER_NAMES: [&str; 5] =
+    ["Functions", "Expressions", "Impls", "Traits", "Methods"];
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.5em; }
+th { background: #eee; }
+table.sortable th { cursor: pointer; }
+td.number { text-align: right; }
+ul.tree { list-style: none; padding-left: 1.5em; }
+tr.forbids { background: #b7e1cd; }
+tr.allows { background: #fff2cc; }
+tr.unsafe { background: #f4cccc; }
+tr.unknown { background: #d9d9d9; }
+";
+
+/// Sorts a table by the `data-sort` values of the column whose header is
+/// clicked, numbers numerically, toggling between ascending and descending
+const SCRIPT: &str = "
+document.querySelectorAll('table.sortable th').forEach(function (th, column) {
+  th.addEventListener('click', function () {
+    var tbody = th.closest('table').tBodies[0];
+    var ascending = th.dataset.order !== 'ascending';
+    th.dataset.order = ascending ? 'ascending' : 'descending';
+    var rows = Array.from(tbody.rows);
+    rows.sort(function (a, b) {
+      var x = a.cells[column].dataset.sort, y = b.cells[column].dataset.sort;
+      var order = isNaN(x) || isNaN(y) ? x.localeCompare(y) : x - y;
+      return ascending ? order : -order;
+    });
+    rows.forEach(function (row) { tbody.appendChild(row); });
+  });
+});
+";
+
+struct HtmlPackage {
+    index: usize,
+    name: String,
+    /// `None` if no metrics were found for the package
+    unsafe_info: Option<UnsafeInfo>,
+    /// Scanned files, sorted by path, with whether they were used by the
+    /// build
+    files: Vec<(PathBuf, bool, RsFileMetricsWrapper)>,
+}
+
+impl HtmlPackage {
+    fn status(&self) -> Option<CrateDetectionStatus> {
+        self.unsafe_info.as_ref().map(get_module_detection_status)
+    }
+}
+
+/// Renders the part of `graph` reachable from `root_package_id` as a single
+/// HTML document without external assets: a collapsible dependency tree, a
+/// sortable table of the unsafe counters of every package, the counters of
+/// every scanned file of each package and the files used by the build that
+/// were not scanned.
+pub fn create_html_report(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: &PackageId,
+    table_parameters: &TableParameters,
+    used_but_not_scanned_files: &[PathBuf],
+) -> ScanResult {
+    let root_index = match graph.nodes.get(root_package_id) {
+        Some(root_index) => *root_index,
+        None => return root_package_not_in_graph(root_package_id),
+    };
+    let emoji_symbols = EmojiSymbols::new(OutputFormat::Html);
+    let mut warning_count = 0;
+
+    let mut packages = HashMap::<NodeIndex, HtmlPackage>::new();
+    for node_index in Bfs::new(&graph.graph, root_index).iter(&graph.graph) {
+        let package_id = &graph.graph[node_index];
+        let name = table_parameters
+            .print_config
+            .format
+            .display(cargo_metadata_parameters, package_id)
+            .to_string();
+        let package = match table_parameters
+            .geiger_context
+            .package_id_to_metrics
+            .get(package_id)
+        {
+            Some(package_metrics) => {
+                let rs_files_used =
+                    table_parameters.rs_files_used_by_package.get(package_id);
+                let mut files = package_metrics
+                    .rs_path_to_metrics
+                    .iter()
+                    .map(|(path, rs_file_metrics_wrapper)| {
+                        (
+                            path.clone(),
+                            rs_files_used
+                                .map(|rs_files_used| {
+                                    rs_files_used.contains(path)
+                                })
+                                .unwrap_or(false),
+                            rs_file_metrics_wrapper.clone(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                files.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+                HtmlPackage {
+                    index: node_index.index(),
+                    name,
+                    unsafe_info: Some(package_unsafe_stats(
+                        package_metrics,
+                        package_id,
+                        table_parameters.rs_files_used_by_package,
+                    )),
+                    files,
+                }
+            }
+            None => {
+                eprintln!(
+                    "WARNING: No metrics found for package: {}",
+                    package_id
+                );
+                warning_count += 1;
+                HtmlPackage {
+                    index: node_index.index(),
+                    name,
+                    unsafe_info: None,
+                    files: vec![],
+                }
+            }
+        };
+        packages.insert(node_index, package);
+    }
+
+    let mut ordered_packages = packages.values().collect::<Vec<_>>();
+    ordered_packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut lines = vec![
+        String::from("<!DOCTYPE html>"),
+        String::from("<html>"),
+        String::from("<head>"),
+        String::from("<meta charset=\"utf-8\">"),
+        format!(
+            "<title>cargo-geiger report for {}</title>",
+            escape(&packages[&root_index].name)
+        ),
+        format!("<style>{}</style>", STYLE),
+        String::from("</head>"),
+        String::from("<body>"),
+        format!(
+            "<h1>cargo-geiger report for {}</h1>",
+            escape(&packages[&root_index].name)
+        ),
+        String::from("<h2>Dependency tree</h2>"),
+        String::from("<ul class=\"tree\">"),
+    ];
+    tree_lines(
+        graph,
+        root_index,
+        &packages,
+        &emoji_symbols,
+        &mut HashSet::new(),
+        &mut lines,
+    );
+    lines.push(String::from("</ul>"));
+    lines.push(String::from("<h2>Packages</h2>"));
+    lines.extend(package_table_lines(&ordered_packages, &emoji_symbols));
+    lines.push(String::from("<h2>Files</h2>"));
+    for package in &ordered_packages {
+        lines.extend(package_file_lines(package));
+    }
+    lines.extend(used_but_not_scanned_lines(used_but_not_scanned_files));
+    lines.push(format!("<script>{}</script>", SCRIPT));
+    lines.push(String::from("</body>"));
+    lines.push(String::from("</html>"));
+
+    ScanResult {
+        scan_output_lines: lines,
+        warning_count,
+    }
+}
+
+/// A list item for the package at `node_index`, with its dependencies in a
+/// collapsible list. Packages already shown are marked with `(*)` and not
+/// expanded again.
+fn tree_lines(
+    graph: &Graph,
+    node_index: NodeIndex,
+    packages: &HashMap<NodeIndex, HtmlPackage>,
+    emoji_symbols: &EmojiSymbols,
+    visited: &mut HashSet<NodeIndex>,
+    lines: &mut Vec<String>,
+) {
+    let package = &packages[&node_index];
+    let label = format!(
+        "{} <a href=\"#package-{}\">{}</a>",
+        status_icon(&package.status(), emoji_symbols),
+        package.index,
+        escape(&package.name)
+    );
+    if !visited.insert(node_index) {
+        lines.push(format!("<li>{} (*)</li>", label));
+        return;
+    }
+
+    let mut dependency_indices = graph
+        .graph
+        .neighbors(node_index)
+        .collect::<HashSet<NodeIndex>>()
+        .into_iter()
+        .collect::<Vec<NodeIndex>>();
+    dependency_indices.sort_by(|a, b| packages[a].name.cmp(&packages[b].name));
+    if dependency_indices.is_empty() {
+        lines.push(format!("<li>{}</li>", label));
+        return;
+    }
+    lines.push(format!(
+        "<li><details open><summary>{}</summary><ul class=\"tree\">",
+        label
+    ));
+    for dependency_index in dependency_indices {
+        tree_lines(
+            graph,
+            dependency_index,
+            packages,
+            emoji_symbols,
+            visited,
+            lines,
+        );
+    }
+    lines.push(String::from("</ul></details></li>"));
+}
+
+fn package_table_lines(
+    packages: &[&HtmlPackage],
+    emoji_symbols: &EmojiSymbols,
+) -> Vec<String> {
+    let mut header = String::from("<tr><th>Package</th><th>Status</th>");
+    for counter_name in COUNTER_NAMES {
+        header.push_str(&format!(
+            "<th>{0} used</th><th>{0} unused</th>",
+            counter_name
+        ));
+    }
+    header.push_str("</tr>");
+    let mut lines = vec![
+        String::from("<table class=\"sortable\">"),
+        format!("<thead>{}</thead>", header),
+        String::from("<tbody>"),
+    ];
+
+    for package in packages {
+        let status = package.status();
+        let mut row = format!(
+            "<tr class=\"{}\"><td data-sort=\"{}\"><a href=\"#package-{}\">{}</a></td><td data-sort=\"{}\">{}</td>",
+            status_class_name(&status),
+            escape(&package.name),
+            package.index,
+            escape(&package.name),
+            status_class_name(&status),
+            status_icon(&status, emoji_symbols)
+        );
+        match &package.unsafe_info {
+            Some(unsafe_info) => {
+                for (used, unused) in unsafe_counts(&unsafe_info.used)
+                    .iter()
+                    .zip(unsafe_counts(&unsafe_info.unused))
+                {
+                    row.push_str(&number_cell(*used));
+                    row.push_str(&number_cell(unused));
+                }
+            }
+            None => {
+                for _ in 0..COUNTER_NAMES.len() * 2 {
+                    row.push_str("<td data-sort=\"-1\"></td>");
+                }
+            }
+        }
+        row.push_str("</tr>");
+        lines.push(row);
+    }
+
+    lines.push(String::from("</tbody>"));
+    lines.push(String::from("</table>"));
+    lines
+}
+
+/// A collapsible section with the unsafe counters of every scanned file of
+/// `package`
+fn package_file_lines(package: &HtmlPackage) -> Vec<String> {
+    let mut lines = vec![format!(
+        "<details id=\"package-{}\"><summary>{}</summary>",
+        package.index,
+        escape(&package.name)
+    )];
+    if package.files.is_empty() {
+        lines.push(String::from("<p>No metrics found.</p>"));
+        lines.push(String::from("</details>"));
+        return lines;
+    }
+
+    let mut header = String::from(
+        "<tr><th>File</th><th>Used by build</th><th>Entry point</th><th>Forbids unsafe</th>",
+    );
+    for counter_name in COUNTER_NAMES {
+        header.push_str(&format!("<th>{}</th>", counter_name));
+    }
+    header.push_str("</tr>");
+    lines.push(String::from("<table class=\"sortable\">"));
+    lines.push(format!("<thead>{}</thead>", header));
+    lines.push(String::from("<tbody>"));
+    for (path, used, rs_file_metrics_wrapper) in &package.files {
+        let path = path.display().to_string();
+        let mut row = format!(
+            "<tr><td data-sort=\"{0}\">{0}</td>{1}{2}{3}",
+            escape(&path),
+            flag_cell(*used),
+            flag_cell(rs_file_metrics_wrapper.is_crate_entry_point),
+            flag_cell(rs_file_metrics_wrapper.metrics.forbids_unsafe)
+        );
+        for count in unsafe_counts(&rs_file_metrics_wrapper.metrics.counters) {
+            row.push_str(&number_cell(count));
+        }
+        row.push_str("</tr>");
+        lines.push(row);
+    }
+    lines.push(String::from("</tbody>"));
+    lines.push(String::from("</table>"));
+    lines.push(String::from("</details>"));
+    lines
+}
+
+fn used_but_not_scanned_lines(
+    used_but_not_scanned_files: &[PathBuf],
+) -> Vec<String> {
+    let mut lines = vec![String::from("<h2>Files used but not scanned</h2>")];
+    if used_but_not_scanned_files.is_empty() {
+        lines.push(String::from("<p>None.</p>"));
+        return lines;
+    }
+    let mut paths = used_but_not_scanned_files.to_vec();
+    paths.sort();
+    lines.push(String::from("<ul>"));
+    for path in paths {
+        lines.push(format!("<li>{}</li>", escape(&path.display().to_string())));
+    }
+    lines.push(String::from("</ul>"));
+    lines
+}
+
+/// The unsafe counts of `counter_block`, in the order of `COUNTER_NAMES`
+fn unsafe_counts(counter_block: &CounterBlock) -> [u64; 5] {
+    [
+        counter_block.functions.unsafe_,
+        counter_block.exprs.unsafe_,
+        counter_block.item_impls.unsafe_,
+        counter_block.item_traits.unsafe_,
+        counter_block.methods.unsafe_,
+    ]
+}
+
+fn number_cell(count: u64) -> String {
+    format!("<td class=\"number\" data-sort=\"{0}\">{0}</td>", count)
+}
+
+fn flag_cell(flag: bool) -> String {
+    let text = if flag { "yes" } else { "no" };
+    format!("<td data-sort=\"{0}\">{0}</td>", text)
+}
+
+fn status_icon(
+    status: &Option<CrateDetectionStatus>,
+    emoji_symbols: &EmojiSymbols,
+) -> String {
+    match status {
+        Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe) => {
+            emoji_symbols.emoji(SymbolKind::Lock).to_string()
+        }
+        Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe) => {
+            emoji_symbols.emoji(SymbolKind::QuestionMark).to_string()
+        }
+        Some(CrateDetectionStatus::UnsafeDetected) => {
+            emoji_symbols.emoji(SymbolKind::Rads).to_string()
+        }
+        None => String::from("&#8212;"),
+    }
+}
+
+fn status_class_name(status: &Option<CrateDetectionStatus>) -> &'static str {
+    match status {
+        Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe) => "forbids",
+        Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe) => "allows",
+        Some(CrateDetectionStatus::UnsafeDetected) => "unsafe",
+        None => "unknown",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod html_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Count;
+    use cargo_metadata::DependencyKind;
+    use geiger::RsFileMetrics;
+    use rstest::*;
+
+    #[rstest]
+    fn tree_lines_test() {
+        // 0 -> 1 -> 2, 0 -> 2
+        let mut graph = Graph {
+            graph: Default::default(),
+            nodes: HashMap::new(),
+        };
+        let mut packages = HashMap::new();
+        let node_indices = (0..3)
+            .map(|index| {
+                let package_id = PackageId {
+                    repr: format!("package_{}", index),
+                };
+                let node_index = graph.graph.add_node(package_id.clone());
+                graph.nodes.insert(package_id, node_index);
+                packages.insert(
+                    node_index,
+                    HtmlPackage {
+                        index,
+                        name: format!("package_{}", index),
+                        unsafe_info: None,
+                        files: vec![],
+                    },
+                );
+                node_index
+            })
+            .collect::<Vec<NodeIndex>>();
+        for (from, to) in [(0, 1), (1, 2), (0, 2)] {
+            graph.graph.add_edge(
+                node_indices[from],
+                node_indices[to],
+                DependencyKind::Normal.into(),
+            );
+        }
+        let mut lines = vec![];
+
+        tree_lines(
+            &graph,
+            node_indices[0],
+            &packages,
+            &EmojiSymbols::new(OutputFormat::Html),
+            &mut HashSet::new(),
+            &mut lines,
+        );
+
+        assert_eq!(
+            lines,
+            vec![
+                "<li><details open><summary>&#8212; <a href=\"#package-0\">package_0</a></summary><ul class=\"tree\">",
+                "<li><details open><summary>&#8212; <a href=\"#package-1\">package_1</a></summary><ul class=\"tree\">",
+                "<li>&#8212; <a href=\"#package-2\">package_2</a></li>",
+                "</ul></details></li>",
+                "<li>&#8212; <a href=\"#package-2\">package_2</a> (*)</li>",
+                "</ul></details></li>",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn package_file_lines_test() {
+        let package = HtmlPackage {
+            index: 3,
+            name: String::from("<package>"),
+            unsafe_info: Some(UnsafeInfo::default()),
+            files: vec![(
+                PathBuf::from("src/lib.rs"),
+                true,
+                RsFileMetricsWrapper {
+                    metrics: RsFileMetrics {
+                        counters: CounterBlock {
+                            exprs: Count {
+                                safe: 4,
+                                unsafe_: 2,
+                            },
+                            ..Default::default()
+                        },
+                        forbids_unsafe: false,
+                    },
+                    is_crate_entry_point: true,
+                },
+            )],
+        };
+
+        let lines = package_file_lines(&package);
+
+        assert_eq!(
+            lines[0],
+            "<details id=\"package-3\"><summary>&lt;package&gt;</summary>"
+        );
+        assert_eq!(
+            lines[4],
+            "<tr><td data-sort=\"src/lib.rs\">src/lib.rs</td><td data-sort=\"yes\">yes</td><td data-sort=\"yes\">yes</td><td data-sort=\"no\">no</td>\
+             <td class=\"number\" data-sort=\"0\">0</td><td class=\"number\" data-sort=\"2\">2</td>\
+             <td class=\"number\" data-sort=\"0\">0</td><td class=\"number\" data-sort=\"0\">0</td>\
+             <td class=\"number\" data-sort=\"0\">0</td></tr>"
+        );
+    }
+
+    #[rstest(
+        input_used_but_not_scanned_files,
+        expected_lines,
+        case(
+            vec![],
+            vec!["<h2>Files used but not scanned</h2>", "<p>None.</p>"]
+        ),
+        case(
+            vec![PathBuf::from("b.rs"), PathBuf::from("a&.rs")],
+            vec![
+                "<h2>Files used but not scanned</h2>",
+                "<ul>",
+                "<li>a&amp;.rs</li>",
+                "<li>b.rs</li>",
+                "</ul>"
+            ]
+        )
+    )]
+    fn used_but_not_scanned_lines_test(
+        input_used_but_not_scanned_files: Vec<PathBuf>,
+        expected_lines: Vec<&str>,
+    ) {
+        assert_eq!(
+            used_but_not_scanned_lines(&input_used_but_not_scanned_files),
+            expected_lines
+        );
+    }
+}
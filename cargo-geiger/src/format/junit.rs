@@ -0,0 +1,251 @@
+use crate::format::table::TableParameters;
+use crate::graph::Graph;
+use crate::mapping::CargoMetadataParameters;
+use crate::scan::{
+    package_unsafe_stats, root_package_not_in_graph, ScanResult,
+};
+
+use cargo_geiger_serde::{Count, CounterBlock, UnsafeInfo};
+use cargo_metadata::PackageId;
+use petgraph::visit::{Bfs, Walker};
+
+/// The outcome of the test case of a package
+#[derive(Debug, Eq, PartialEq)]
+enum TestCaseResult {
+    Passed,
+    /// Message and details of the failure
+    Failed(String, String),
+    /// No metrics were found for the package
+    Error,
+}
+
+/// Renders the packages reachable from `root_package_id` as a `JUnit` XML
+/// test suite, one test case per package. A package fails when it doesn't
+/// forbid unsafe code and uses more unsafe items than `max_unsafe`, none by
+/// default. Packages without metrics are reported as errors.
+pub fn create_junit_report(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: &PackageId,
+    table_parameters: &TableParameters,
+    max_unsafe: Option<u64>,
+) -> ScanResult {
+    let root_index = match graph.nodes.get(root_package_id) {
+        Some(root_index) => *root_index,
+        None => return root_package_not_in_graph(root_package_id),
+    };
+    let display = |package_id: &PackageId| {
+        table_parameters
+            .print_config
+            .format
+            .display(cargo_metadata_parameters, package_id)
+            .to_string()
+    };
+    let mut warning_count = 0;
+
+    let mut test_cases = Bfs::new(&graph.graph, root_index)
+        .iter(&graph.graph)
+        .map(|node_index| {
+            let package_id = &graph.graph[node_index];
+            let test_case_result = match table_parameters
+                .geiger_context
+                .package_id_to_metrics
+                .get(package_id)
+            {
+                Some(package_metrics) => test_case_result(
+                    &package_unsafe_stats(
+                        package_metrics,
+                        package_id,
+                        table_parameters.rs_files_used_by_package,
+                    ),
+                    max_unsafe.unwrap_or(0),
+                ),
+                None => {
+                    eprintln!(
+                        "WARNING: No metrics found for package: {}",
+                        package_id
+                    );
+                    warning_count += 1;
+                    TestCaseResult::Error
+                }
+            };
+            (display(package_id), test_case_result)
+        })
+        .collect::<Vec<(String, TestCaseResult)>>();
+    test_cases.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let failures = test_cases
+        .iter()
+        .filter(|(_, result)| matches!(result, TestCaseResult::Failed(..)))
+        .count();
+    let errors = test_cases
+        .iter()
+        .filter(|(_, result)| *result == TestCaseResult::Error)
+        .count();
+    let suite_attributes = format!(
+        "name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\"",
+        escape(&display(root_package_id)),
+        test_cases.len(),
+        failures,
+        errors
+    );
+
+    let mut lines = vec![
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"),
+        format!("<testsuites {}>", suite_attributes),
+        format!("  <testsuite {}>", suite_attributes),
+    ];
+    for (name, result) in test_cases {
+        let test_case = format!(
+            "<testcase classname=\"cargo-geiger\" name=\"{}\"",
+            escape(&name)
+        );
+        match result {
+            TestCaseResult::Passed => {
+                lines.push(format!("    {}/>", test_case))
+            }
+            TestCaseResult::Failed(message, details) => {
+                lines.push(format!("    {}>", test_case));
+                lines.push(format!(
+                    "      <failure type=\"unsafe\" message=\"{}\">{}</failure>",
+                    escape(&message),
+                    escape(&details)
+                ));
+                lines.push(String::from("    </testcase>"));
+            }
+            TestCaseResult::Error => {
+                lines.push(format!("    {}>", test_case));
+                lines.push(String::from(
+                    "      <error type=\"missing-metrics\" message=\"No metrics found for the package\"/>",
+                ));
+                lines.push(String::from("    </testcase>"));
+            }
+        }
+    }
+    lines.push(String::from("  </testsuite>"));
+    lines.push(String::from("</testsuites>"));
+
+    ScanResult {
+        scan_output_lines: lines,
+        warning_count,
+    }
+}
+
+fn test_case_result(
+    unsafe_info: &UnsafeInfo,
+    max_unsafe: u64,
+) -> TestCaseResult {
+    let used_unsafe = unsafe_count(&unsafe_info.used);
+    if unsafe_info.forbids_unsafe || used_unsafe <= max_unsafe {
+        return TestCaseResult::Passed;
+    }
+    TestCaseResult::Failed(
+        format!(
+            "{} used unsafe items, more than the {} allowed",
+            used_unsafe, max_unsafe
+        ),
+        format!(
+            "Used/total unsafe: functions {}, expressions {}, impls {}, traits {}, methods {}",
+            used_and_total(&unsafe_info.used.functions, &unsafe_info.unused.functions),
+            used_and_total(&unsafe_info.used.exprs, &unsafe_info.unused.exprs),
+            used_and_total(&unsafe_info.used.item_impls, &unsafe_info.unused.item_impls),
+            used_and_total(&unsafe_info.used.item_traits, &unsafe_info.unused.item_traits),
+            used_and_total(&unsafe_info.used.methods, &unsafe_info.unused.methods),
+        ),
+    )
+}
+
+fn unsafe_count(counter_block: &CounterBlock) -> u64 {
+    counter_block.functions.unsafe_
+        + counter_block.exprs.unsafe_
+        + counter_block.item_impls.unsafe_
+        + counter_block.item_traits.unsafe_
+        + counter_block.methods.unsafe_
+}
+
+fn used_and_total(used: &Count, unused: &Count) -> String {
+    format!("{}/{}", used.unsafe_, used.unsafe_ + unused.unsafe_)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod junit_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_forbids_unsafe,
+        input_used_exprs,
+        input_max_unsafe,
+        expected_failed,
+        case(true, 0, 0, false),
+        case(false, 0, 0, false),
+        case(false, 3, 0, true),
+        case(false, 3, 3, false),
+        case(false, 4, 3, true)
+    )]
+    fn test_case_result_test(
+        input_forbids_unsafe: bool,
+        input_used_exprs: u64,
+        input_max_unsafe: u64,
+        expected_failed: bool,
+    ) {
+        let unsafe_info = UnsafeInfo {
+            used: CounterBlock {
+                exprs: Count {
+                    safe: 0,
+                    unsafe_: input_used_exprs,
+                },
+                ..Default::default()
+            },
+            unused: CounterBlock {
+                exprs: Count {
+                    safe: 0,
+                    unsafe_: 1,
+                },
+                ..Default::default()
+            },
+            forbids_unsafe: input_forbids_unsafe,
+        };
+
+        let result = test_case_result(&unsafe_info, input_max_unsafe);
+
+        match result {
+            TestCaseResult::Failed(message, details) => {
+                assert!(expected_failed);
+                assert_eq!(
+                    message,
+                    format!(
+                        "{} used unsafe items, more than the {} allowed",
+                        input_used_exprs, input_max_unsafe
+                    )
+                );
+                assert_eq!(
+                    details,
+                    format!(
+                        "Used/total unsafe: functions 0/0, expressions {}/{}, impls 0/0, traits 0/0, methods 0/0",
+                        input_used_exprs,
+                        input_used_exprs + 1
+                    )
+                );
+            }
+            _ => assert!(!expected_failed),
+        }
+    }
+
+    #[rstest]
+    fn escape_test() {
+        assert_eq!(
+            escape("<a & 'b' \"c\">"),
+            "&lt;a &amp; &apos;b&apos; &quot;c&quot;&gt;"
+        );
+    }
+}
@@ -16,7 +16,36 @@ impl<'a> Parser<'a> {
     }
 
     fn argument(&mut self) -> RawChunk<'a> {
-        RawChunk::Argument(self.name())
+        let name = self.name();
+        if self.consume(':') {
+            RawChunk::FormattedArgument(name, self.format_spec())
+        } else {
+            RawChunk::Argument(name)
+        }
+    }
+
+    /// The placeholder and text of `{?name:text}`, following the `?`. The text
+    /// may contain placeholders of its own.
+    fn conditional(&mut self) -> RawChunk<'a> {
+        let name = self.name();
+        if !self.consume(':') {
+            for _ in &mut self.it {}
+            return RawChunk::Error("expected ':'");
+        }
+        let start = self.position();
+        let mut depth = 0;
+        while let Some((pos, ch)) = self.it.next() {
+            match ch {
+                '{' if self.consume('{') => {}
+                '{' => depth += 1,
+                '}' if depth == 0 => {
+                    return RawChunk::Conditional(name, &self.s[start..pos])
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        RawChunk::Error("expected '}'")
     }
 
     fn consume(&mut self, ch: char) -> bool {
@@ -29,6 +58,17 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn format_spec(&mut self) -> &'a str {
+        let start = self.position();
+        while let Some(&(pos, ch)) = self.it.peek() {
+            if ch == '}' {
+                return &self.s[start..pos];
+            }
+            self.it.next();
+        }
+        &self.s[start..]
+    }
+
     fn name(&mut self) -> &'a str {
         let start = match self.it.peek() {
             Some(&(pos, ch)) if ch.is_alphabetic() => {
@@ -40,7 +80,9 @@ impl<'a> Parser<'a> {
 
         loop {
             match self.it.peek() {
-                Some(&(_, ch)) if ch.is_alphanumeric() => {
+                Some(&(_, ch))
+                    if ch.is_alphanumeric() || ch == '.' || ch == '_' =>
+                {
                     self.it.next();
                 }
                 Some(&(end, _)) => return &self.s[start..end],
@@ -49,10 +91,14 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn position(&mut self) -> usize {
+        self.it.peek().map(|&(pos, _)| pos).unwrap_or(self.s.len())
+    }
+
     fn text(&mut self, start: usize) -> RawChunk<'a> {
         while let Some(&(pos, ch)) = self.it.peek() {
             match ch {
-                '{' | '}' | ')' => return RawChunk::Text(&self.s[start..pos]),
+                '{' | '}' => return RawChunk::Text(&self.s[start..pos]),
                 _ => {
                     self.it.next();
                 }
@@ -71,6 +117,8 @@ impl<'a> Iterator for Parser<'a> {
                 self.it.next();
                 if self.consume('{') {
                     Some(RawChunk::Text("{"))
+                } else if self.consume('?') {
+                    Some(self.conditional())
                 } else {
                     let chunk = self.argument();
                     if self.consume('}') {
@@ -115,13 +163,53 @@ pub mod parse_tests {
         input_s_string,
         expected_name_string,
         case("parser 1.2.3", "parser"),
-        case("1.2.3 parser", "")
+        case("1.2.3 parser", ""),
+        case("u.exprs}", "u.exprs"),
+        case("source_url}", "source_url")
     )]
     fn parser_name_test(input_s_string: &str, expected_name_string: &str) {
         let mut parser = Parser::new(input_s_string);
         assert_eq!(parser.name(), expected_name_string)
     }
 
+    #[rstest(
+        input_format,
+        expected_raw_chunks,
+        case(
+            "{p:<20}|",
+            vec![
+                RawChunk::FormattedArgument("p", "<20"),
+                RawChunk::Text("|")
+            ]
+        ),
+        case(
+            "{?l: ({{{l})}{r}",
+            vec![
+                RawChunk::Conditional("l", " ({{{l})"),
+                RawChunk::Argument("r")
+            ]
+        ),
+        case(
+            "({p})",
+            vec![
+                RawChunk::Text("("),
+                RawChunk::Argument("p"),
+                RawChunk::Text(")")
+            ]
+        ),
+        case("{?l}", vec![RawChunk::Error("expected ':'")]),
+        case("{?l: ({l})", vec![RawChunk::Error("expected '}'")])
+    )]
+    fn parser_iterator_test(
+        input_format: &str,
+        expected_raw_chunks: Vec<RawChunk>,
+    ) {
+        assert_eq!(
+            Parser::new(input_format).collect::<Vec<RawChunk>>(),
+            expected_raw_chunks
+        );
+    }
+
     #[rstest]
     fn parser_text_test() {
         let parser_s = "parser 1.2.3";
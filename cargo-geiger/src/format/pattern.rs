@@ -1,13 +1,14 @@
 use crate::format::parse::Parser;
-use crate::format::{Chunk, RawChunk};
+use crate::format::{Alignment, Chunk, RawChunk, UnsafeCounter};
 use crate::mapping::CargoMetadataParameters;
 
 use super::display::Display;
 
 use cargo_metadata::PackageId;
 use std::error::Error;
+use std::str::FromStr;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Pattern {
     pub chunks: Vec<Chunk>,
 }
@@ -24,22 +25,50 @@ impl Pattern {
     ) -> Display<'a> {
         Display {
             cargo_metadata_parameters,
+            depth: None,
             pattern: self,
             package,
+            unsafe_info: None,
         }
     }
 
+    /// Whether the pattern shows the unsafe usage of packages, in which case
+    /// it lays out the whole line of each package in table mode
+    pub fn shows_unsafe_usage(&self) -> bool {
+        fn shows_unsafe_usage(chunk: &Chunk) -> bool {
+            match chunk {
+                Chunk::Counter(_) | Chunk::ForbidsUnsafe => true,
+                Chunk::Conditional(placeholder, chunks) => {
+                    shows_unsafe_usage(placeholder)
+                        || chunks.iter().any(shows_unsafe_usage)
+                }
+                Chunk::Padded(placeholder, ..) => {
+                    shows_unsafe_usage(placeholder)
+                }
+                _ => false,
+            }
+        }
+        self.chunks.iter().any(shows_unsafe_usage)
+    }
+
     pub fn try_build(format: &str) -> Result<Pattern, Box<dyn Error>> {
         let mut chunks = vec![];
 
         for raw in Parser::new(format) {
             let chunk = match raw {
                 RawChunk::Text(text) => Chunk::Raw(text.to_owned()),
-                RawChunk::Argument("p") => Chunk::Package,
-                RawChunk::Argument("l") => Chunk::License,
-                RawChunk::Argument("r") => Chunk::Repository,
-                RawChunk::Argument(ref a) => {
-                    return Err(format!("unsupported pattern `{}`", a).into());
+                RawChunk::Argument(name) => placeholder(name)?,
+                RawChunk::Conditional(name, text) => Chunk::Conditional(
+                    Box::new(placeholder(name)?),
+                    Pattern::try_build(text)?.chunks,
+                ),
+                RawChunk::FormattedArgument(name, format_spec) => {
+                    let (alignment, width) = padding(format_spec)?;
+                    Chunk::Padded(
+                        Box::new(placeholder(name)?),
+                        alignment,
+                        width,
+                    )
                 }
                 RawChunk::Error(err) => return Err(err.into()),
             };
@@ -50,6 +79,49 @@ impl Pattern {
     }
 }
 
+fn placeholder(name: &str) -> Result<Chunk, Box<dyn Error>> {
+    let chunk = match name {
+        "p" => Chunk::Package,
+        "l" => Chunk::License,
+        "r" => Chunk::Repository,
+        "authors" => Chunk::Authors,
+        "depth" => Chunk::Depth,
+        "description" => Chunk::Description,
+        "edition" => Chunk::Edition,
+        "forbids" => Chunk::ForbidsUnsafe,
+        "name" => Chunk::Name,
+        "source" => Chunk::SourceKind,
+        "source_url" => Chunk::SourceUrl,
+        "version" => Chunk::Version,
+        _ => match name
+            .strip_prefix("u.")
+            .and_then(|counter| UnsafeCounter::from_str(counter).ok())
+        {
+            Some(unsafe_counter) => Chunk::Counter(unsafe_counter),
+            None => {
+                return Err(format!("unsupported pattern `{}`", name).into())
+            }
+        },
+    };
+    Ok(chunk)
+}
+
+/// The alignment and width of a format spec, e.g. `<20` or `>8`, left aligned
+/// if not given
+fn padding(format_spec: &str) -> Result<(Alignment, usize), Box<dyn Error>> {
+    let (alignment, width) = match format_spec.strip_prefix('>') {
+        Some(width) => (Alignment::Right, width),
+        None => (
+            Alignment::Left,
+            format_spec.strip_prefix('<').unwrap_or(format_spec),
+        ),
+    };
+    width
+        .parse()
+        .map(|width| (alignment, width))
+        .map_err(|_| format!("invalid padding `{}`", format_spec).into())
+}
+
 #[cfg(test)]
 mod pattern_tests {
     use super::*;
@@ -84,4 +156,97 @@ mod pattern_tests {
         assert!(pattern_result.is_ok());
         assert_eq!(pattern_result.unwrap(), expected_pattern);
     }
+
+    #[rstest(
+        input_format_string,
+        expected_pattern,
+        case(
+            "{name:<20}{version:>8}",
+            Pattern::new(vec![
+                Chunk::Padded(Box::new(Chunk::Name), Alignment::Left, 20),
+                Chunk::Padded(Box::new(Chunk::Version), Alignment::Right, 8)
+            ])
+        ),
+        case(
+            "{u.exprs:6}{u.total}",
+            Pattern::new(vec![
+                Chunk::Padded(
+                    Box::new(Chunk::Counter(UnsafeCounter::Exprs)),
+                    Alignment::Left,
+                    6
+                ),
+                Chunk::Counter(UnsafeCounter::Total)
+            ])
+        ),
+        case(
+            "{?forbids:🔒 }{p}",
+            Pattern::new(vec![
+                Chunk::Conditional(
+                    Box::new(Chunk::ForbidsUnsafe),
+                    vec![Chunk::Raw(String::from("🔒 "))]
+                ),
+                Chunk::Package
+            ])
+        ),
+        case(
+            "{?source_url: <{source_url}>}",
+            Pattern::new(vec![Chunk::Conditional(
+                Box::new(Chunk::SourceUrl),
+                vec![
+                    Chunk::Raw(String::from(" <")),
+                    Chunk::SourceUrl,
+                    Chunk::Raw(String::from(">"))
+                ]
+            )])
+        )
+    )]
+    fn pattern_try_build_placeholders_test(
+        input_format_string: &str,
+        expected_pattern: Pattern,
+    ) {
+        assert_eq!(
+            Pattern::try_build(input_format_string).unwrap(),
+            expected_pattern
+        );
+    }
+
+    #[rstest(
+        input_format_string,
+        expected_error,
+        case("{u.lines}", "unsupported pattern `u.lines`"),
+        case("{p:^20}", "invalid padding `^20`"),
+        case("{?x:{p}}", "unsupported pattern `x`")
+    )]
+    fn pattern_try_build_error_test(
+        input_format_string: &str,
+        expected_error: &str,
+    ) {
+        assert_eq!(
+            Pattern::try_build(input_format_string)
+                .unwrap_err()
+                .to_string(),
+            expected_error
+        );
+    }
+
+    #[rstest(
+        input_format_string,
+        expected_shows_unsafe_usage,
+        case("{p}", false),
+        case("{p} {depth}", false),
+        case("{p} {u.exprs}", true),
+        case("{p:<20}{forbids:6}", true),
+        case("{p}{?l: {u.methods}}", true)
+    )]
+    fn pattern_shows_unsafe_usage_test(
+        input_format_string: &str,
+        expected_shows_unsafe_usage: bool,
+    ) {
+        assert_eq!(
+            Pattern::try_build(input_format_string)
+                .unwrap()
+                .shows_unsafe_usage(),
+            expected_shows_unsafe_usage
+        );
+    }
 }
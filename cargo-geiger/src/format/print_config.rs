@@ -1,6 +1,6 @@
 use crate::args::Args;
 use crate::format::pattern::Pattern;
-use crate::format::{CrateDetectionStatus, FormatError};
+use crate::format::{CrateDetectionStatus, EdgeAnnotation, FormatError};
 
 use cargo::util::errors::CliError;
 use colored::{ColoredString, Colorize};
@@ -16,11 +16,19 @@ pub enum Prefix {
 }
 
 #[derive(Clone, Copy, Debug, EnumString, Eq, PartialEq)]
+#[strum(ascii_case_insensitive)]
 pub enum OutputFormat {
     Ascii,
+    Csv,
+    CycloneDx,
+    Dot,
     Json,
     GitHubMarkdown,
+    Html,
+    JUnit,
+    Mermaid,
     Ratio,
+    Tsv,
     Utf8,
 }
 
@@ -30,14 +38,21 @@ impl Default for OutputFormat {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct PrintConfig {
     /// Don't truncate dependencies that have already been displayed.
     pub all: bool,
 
     pub allow_partial_results: bool,
+
+    /// Dependencies deeper than this are hidden, but still counted.
+    pub depth: Option<usize>,
+
     pub direction: EdgeDirection,
 
+    /// Conditions of the dependencies shown in the tree.
+    pub edge_annotations: Vec<EdgeAnnotation>,
+
     // Is anyone using this? This is a carry-over from cargo-tree.
     // TODO: Open a github issue to discuss deprecation.
     pub format: Pattern,
@@ -49,8 +64,7 @@ pub struct PrintConfig {
 
 impl PrintConfig {
     pub fn new(args: &Args) -> Result<Self, CliError> {
-        // TODO: Add command line flag for this and make it default to false?
-        let allow_partial_results = true;
+        let allow_partial_results = !args.strict;
 
         let direction = match args.invert {
             true => EdgeDirection::Incoming,
@@ -81,13 +95,20 @@ impl PrintConfig {
         Ok(PrintConfig {
             all: args.all,
             allow_partial_results,
+            depth: args.depth,
             direction,
+            edge_annotations: args.edges.clone(),
             format,
             include_tests,
             output_format: args.output_format,
             prefix,
         })
     }
+
+    /// Whether lines at `depth` are displayed with the `--depth` limit
+    pub fn is_depth_displayed(&self, depth: usize) -> bool {
+        !matches!(self.depth, Some(max_depth) if depth > max_depth)
+    }
 }
 
 impl Default for PrintConfig {
@@ -95,7 +116,9 @@ impl Default for PrintConfig {
         PrintConfig {
             all: false,
             allow_partial_results: false,
+            depth: None,
             direction: Direction::Outgoing,
+            edge_annotations: vec![],
             format: Pattern::try_build("p").unwrap(),
             include_tests: IncludeTests::Yes,
             prefix: Prefix::Depth,
@@ -110,7 +133,9 @@ pub fn colorize(
     string: String,
 ) -> ColoredString {
     match output_format {
-        OutputFormat::GitHubMarkdown => ColoredString::from(string.as_str()),
+        OutputFormat::GitHubMarkdown | OutputFormat::Html => {
+            ColoredString::from(string.as_str())
+        }
         _ => match crate_detection_status {
             CrateDetectionStatus::NoneDetectedForbidsUnsafe => string.green(),
             CrateDetectionStatus::NoneDetectedAllowsUnsafe => string.normal(),
@@ -153,6 +178,30 @@ mod print_config_tests {
         );
     }
 
+    #[rstest(
+        input_strict_bool,
+        expected_allow_partial_results,
+        case(true, false),
+        case(false, true)
+    )]
+    fn print_config_new_test_strict(
+        input_strict_bool: bool,
+        expected_allow_partial_results: bool,
+    ) {
+        let args = Args {
+            strict: input_strict_bool,
+            ..Default::default()
+        };
+
+        let print_config_result = PrintConfig::new(&args);
+
+        assert!(print_config_result.is_ok());
+        assert_eq!(
+            print_config_result.unwrap().allow_partial_results,
+            expected_allow_partial_results
+        );
+    }
+
     #[rstest(
         input_format_string,
         expected_format,
@@ -307,4 +356,29 @@ mod print_config_tests {
             expected_colored_string
         );
     }
+
+    #[rstest(
+        input_depth,
+        input_line_depth,
+        expected_is_displayed,
+        case(None, 10, true),
+        case(Some(1), 0, true),
+        case(Some(1), 1, true),
+        case(Some(1), 2, false)
+    )]
+    fn is_depth_displayed_test(
+        input_depth: Option<usize>,
+        input_line_depth: usize,
+        expected_is_displayed: bool,
+    ) {
+        let print_config = PrintConfig {
+            depth: input_depth,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            print_config.is_depth_displayed(input_line_depth),
+            expected_is_displayed
+        );
+    }
 }
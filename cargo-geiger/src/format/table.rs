@@ -1,5 +1,10 @@
+mod blast_radius;
+mod duplicates;
+mod flat;
 mod handle_text_tree_line;
+mod modules;
 mod total_package_counts;
+mod why;
 
 use crate::format::emoji_symbols::EmojiSymbols;
 use crate::format::print_config::{colorize, OutputFormat, PrintConfig};
@@ -11,12 +16,24 @@ use crate::tree::TextTreeLine;
 use handle_text_tree_line::{
     text_tree_line_extra_deps_group_to_table_line_string,
     text_tree_line_package_to_table_line_string, HandlePackageParameters,
+    TreePackageLine,
 };
 use total_package_counts::TotalPackageCounts;
 
+pub use blast_radius::{
+    blast_radii, create_table_from_direct_dependency_ranking, BlastRadius,
+};
+pub use duplicates::create_table_from_duplicates;
+pub use flat::create_table_from_flat_ranking;
+pub use modules::{
+    create_table_from_module_entries, get_module_detection_status,
+};
+pub use why::create_table_from_why;
+
 use cargo_geiger_serde::{Count, CounterBlock};
+use cargo_metadata::PackageId;
 use colored::ColoredString;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 // TODO: use a table library, or factor the tableness out in a smarter way. This
@@ -31,7 +48,10 @@ pub const UNSAFE_COUNTERS_HEADER: [&str; 6] = [
     "Dependency",
 ];
 
+/// Renders the dependency tree, with the `BlastRadius` of each package
+/// following its name if `blast_radii` are given
 pub fn create_table_from_text_tree_lines(
+    blast_radii: Option<&HashMap<PackageId, BlastRadius>>,
     cargo_metadata_parameters: &CargoMetadataParameters,
     table_parameters: &TableParameters,
     text_tree_lines: Vec<TextTreeLine>,
@@ -43,33 +63,47 @@ pub fn create_table_from_text_tree_lines(
     let emoji_symbols =
         EmojiSymbols::new(table_parameters.print_config.output_format);
     let mut handle_package_parameters = HandlePackageParameters {
+        blast_radii,
         total_package_counts: &mut total_package_counts,
         visited_package_ids: &mut visited_package_ids,
         warning_count: &mut warning_count,
     };
 
-    for table_line in text_tree_lines.into_iter().filter_map(|text_tree_line| {
-        match text_tree_line {
+    for text_tree_line in text_tree_lines {
+        // Lines deeper than `--depth` are still handled, to be included in
+        // the totals.
+        let is_displayed = table_parameters
+            .print_config
+            .is_depth_displayed(text_tree_line.depth());
+        let table_line = match text_tree_line {
             TextTreeLine::ExtraDepsGroup {
                 kind: dep_kind,
                 tree_vines,
+                ..
             } => text_tree_line_extra_deps_group_to_table_line_string(
                 dep_kind, tree_vines,
             ),
             TextTreeLine::Package {
                 id: package_id,
                 tree_vines,
+                edge_conditions,
+                depth,
             } => text_tree_line_package_to_table_line_string(
                 cargo_metadata_parameters,
                 &emoji_symbols,
                 &mut handle_package_parameters,
-                package_id,
                 table_parameters,
-                tree_vines,
+                TreePackageLine {
+                    depth,
+                    edge_conditions,
+                    package_id,
+                    tree_vines,
+                },
             ),
+        };
+        if let (Some(table_line), true) = (table_line, is_displayed) {
+            table_lines.push(table_line);
         }
-    }) {
-        table_lines.push(table_line);
     }
 
     table_lines.push(String::new());
@@ -97,7 +131,7 @@ pub fn create_table_from_text_tree_lines(
 pub struct TableParameters<'a> {
     pub geiger_context: &'a GeigerContext,
     pub print_config: &'a PrintConfig,
-    pub rs_files_used: &'a HashSet<PathBuf>,
+    pub rs_files_used_by_package: &'a HashMap<PackageId, HashSet<PathBuf>>,
 }
 
 fn table_footer_unsafe_counts(
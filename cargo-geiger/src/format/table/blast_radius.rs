@@ -0,0 +1,369 @@
+use crate::format::emoji_symbols::EmojiSymbols;
+use crate::format::print_config::colorize;
+use crate::format::{CrateDetectionStatus, SymbolKind};
+use crate::graph::Graph;
+use crate::mapping::CargoMetadataParameters;
+use crate::scan::{
+    package_unsafe_stats, root_package_not_in_graph, ScanResult,
+};
+
+use super::handle_text_tree_line::construct_package_text_tree_line;
+use super::modules::get_module_detection_status;
+use super::{table_row, table_row_empty, TableParameters};
+
+use cargo_geiger_serde::UnsafeInfo;
+use cargo_metadata::PackageId;
+use std::collections::{HashMap, HashSet};
+
+/// The unsafe counters of everything a package pulls in, each package
+/// counted once
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlastRadius {
+    /// Totals of the package and all of its dependencies
+    pub subtree: UnsafeInfo,
+    /// Totals of the package and the dependencies only reachable through it
+    pub exclusive: UnsafeInfo,
+}
+
+/// The `BlastRadius` of every package of `graph` reachable from
+/// `root_package_id`
+pub fn blast_radii(
+    graph: &Graph,
+    root_package_id: &PackageId,
+    table_parameters: &TableParameters,
+) -> HashMap<PackageId, BlastRadius> {
+    graph
+        .subtree_packages(root_package_id)
+        .into_iter()
+        .map(|package_id| {
+            let blast_radius = BlastRadius {
+                subtree: unsafe_totals(
+                    &graph.subtree_packages(&package_id),
+                    table_parameters,
+                ),
+                exclusive: unsafe_totals(
+                    &graph.exclusive_subtree_packages(
+                        root_package_id,
+                        &package_id,
+                    ),
+                    table_parameters,
+                ),
+            };
+            (package_id, blast_radius)
+        })
+        .collect()
+}
+
+/// The unsafe expressions in the subtree and the exclusive subtree of a
+/// package, following its name in the tree
+pub fn blast_radius_annotation(blast_radius: &BlastRadius) -> String {
+    let fmt = |unsafe_info: &UnsafeInfo| {
+        format!(
+            "{}/{}",
+            unsafe_info.used.exprs.unsafe_,
+            unsafe_info.used.exprs.unsafe_ + unsafe_info.unused.exprs.unsafe_
+        )
+    };
+    format!(
+        " [subtree: {}, exclusive: {}]",
+        fmt(&blast_radius.subtree),
+        fmt(&blast_radius.exclusive)
+    )
+}
+
+/// Renders the direct dependencies of `root_package_id`, ordered by the
+/// unsafe code that would disappear along with each of them. The counters
+/// are those of the exclusive subtree of each dependency.
+pub fn create_table_from_direct_dependency_ranking(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: &PackageId,
+    table_parameters: &TableParameters,
+) -> ScanResult {
+    let output_format = table_parameters.print_config.output_format;
+    let emoji_symbols = EmojiSymbols::new(output_format);
+    let blast_radii = blast_radii(graph, root_package_id, table_parameters);
+
+    let root_index = match graph.nodes.get(root_package_id) {
+        Some(root_index) => *root_index,
+        None => return root_package_not_in_graph(root_package_id),
+    };
+    let mut direct_dependencies = graph
+        .graph
+        .neighbors(root_index)
+        .map(|index| graph.graph[index].clone())
+        .filter(|package_id| package_id != root_package_id)
+        .collect::<HashSet<PackageId>>()
+        .into_iter()
+        .map(|package_id| {
+            let blast_radius =
+                blast_radii.get(&package_id).cloned().unwrap_or_default();
+            (package_id, blast_radius)
+        })
+        .collect::<Vec<(PackageId, BlastRadius)>>();
+    rank_direct_dependencies(&mut direct_dependencies);
+
+    let mut table_lines = vec![format!(
+        "{}Direct dependencies by the unsafe code only they pull in:",
+        table_row_empty()
+    )];
+    for (package_id, blast_radius) in direct_dependencies {
+        let crate_detection_status =
+            get_module_detection_status(&blast_radius.exclusive);
+        let icon = match crate_detection_status {
+            CrateDetectionStatus::NoneDetectedForbidsUnsafe => {
+                emoji_symbols.emoji(SymbolKind::Lock)
+            }
+            CrateDetectionStatus::NoneDetectedAllowsUnsafe => {
+                emoji_symbols.emoji(SymbolKind::QuestionMark)
+            }
+            CrateDetectionStatus::UnsafeDetected => {
+                emoji_symbols.emoji(SymbolKind::Rads)
+            }
+        };
+        let package_name = colorize(
+            &crate_detection_status,
+            output_format,
+            format!(
+                "{}{}",
+                table_parameters
+                    .print_config
+                    .format
+                    .display(cargo_metadata_parameters, &package_id),
+                blast_radius_annotation(&blast_radius)
+            ),
+        );
+        let unsafe_row = colorize(
+            &crate_detection_status,
+            output_format,
+            table_row(
+                &blast_radius.exclusive.used,
+                &blast_radius.exclusive.unused,
+                output_format,
+            ),
+        );
+        table_lines.push(construct_package_text_tree_line(
+            crate_detection_status,
+            &emoji_symbols,
+            icon,
+            package_name,
+            table_parameters,
+            String::new(),
+            unsafe_row,
+        ));
+    }
+    table_lines.push(String::new());
+
+    ScanResult {
+        scan_output_lines: table_lines,
+        warning_count: 0,
+    }
+}
+
+/// Orders by used unsafe expressions in the exclusive subtree, then in the
+/// whole subtree, most first
+fn rank_direct_dependencies(
+    direct_dependencies: &mut [(PackageId, BlastRadius)],
+) {
+    direct_dependencies.sort_by(|(a_id, a), (b_id, b)| {
+        b.exclusive
+            .used
+            .exprs
+            .unsafe_
+            .cmp(&a.exclusive.used.exprs.unsafe_)
+            .then_with(|| {
+                b.subtree
+                    .used
+                    .exprs
+                    .unsafe_
+                    .cmp(&a.subtree.used.exprs.unsafe_)
+            })
+            .then_with(|| a_id.cmp(b_id))
+    });
+}
+
+/// The counters of `package_ids` added up. Packages without metrics are left
+/// out of the counters, the tree warns about them. Only a non-empty set of
+/// packages which are all known to forbid unsafe code forbids it.
+fn unsafe_totals(
+    package_ids: &HashSet<PackageId>,
+    table_parameters: &TableParameters,
+) -> UnsafeInfo {
+    let mut unsafe_totals = UnsafeInfo {
+        forbids_unsafe: !package_ids.is_empty(),
+        ..Default::default()
+    };
+    for package_id in package_ids {
+        match table_parameters
+            .geiger_context
+            .package_id_to_metrics
+            .get(package_id)
+        {
+            Some(package_metrics) => {
+                let unsafe_info = package_unsafe_stats(
+                    package_metrics,
+                    package_id,
+                    table_parameters.rs_files_used_by_package,
+                );
+                unsafe_totals.used += unsafe_info.used;
+                unsafe_totals.unused += unsafe_info.unused;
+                unsafe_totals.forbids_unsafe &= unsafe_info.forbids_unsafe;
+            }
+            None => unsafe_totals.forbids_unsafe = false,
+        }
+    }
+    unsafe_totals
+}
+
+#[cfg(test)]
+mod blast_radius_tests {
+    use super::*;
+
+    use crate::format::print_config::PrintConfig;
+    use crate::scan::{GeigerContext, PackageMetrics, RsFileMetricsWrapper};
+
+    use cargo_geiger_serde::{Count, CounterBlock};
+    use cargo_metadata::DependencyKind;
+    use geiger::RsFileMetrics;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn blast_radii_test() {
+        // 0 -> 1 -> 3, 0 -> 2 -> 3, 1 -> 4
+        let unsafe_exprs = [0, 1, 2, 4, 8];
+        let package_ids = (0..unsafe_exprs.len())
+            .map(|index| PackageId {
+                repr: format!("package_{}", index),
+            })
+            .collect::<Vec<PackageId>>();
+        let mut graph = Graph {
+            graph: Default::default(),
+            nodes: HashMap::new(),
+        };
+        for package_id in &package_ids {
+            let index = graph.graph.add_node(package_id.clone());
+            graph.nodes.insert(package_id.clone(), index);
+        }
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 3), (1, 4)] {
+            graph.graph.add_edge(
+                graph.nodes[&package_ids[from]],
+                graph.nodes[&package_ids[to]],
+                DependencyKind::Normal.into(),
+            );
+        }
+        let mut geiger_context = GeigerContext::default();
+        let mut rs_files_used_by_package = HashMap::new();
+        for (package_id, unsafe_exprs) in package_ids.iter().zip(unsafe_exprs) {
+            let rs_path = PathBuf::from(format!("{}/lib.rs", package_id.repr));
+            geiger_context.package_id_to_metrics.insert(
+                package_id.clone(),
+                PackageMetrics {
+                    rs_path_to_metrics: HashMap::from([(
+                        rs_path.clone(),
+                        RsFileMetricsWrapper {
+                            metrics: RsFileMetrics {
+                                counters: CounterBlock {
+                                    exprs: Count {
+                                        safe: 0,
+                                        unsafe_: unsafe_exprs,
+                                    },
+                                    ..Default::default()
+                                },
+                                forbids_unsafe: unsafe_exprs == 0,
+                            },
+                            is_crate_entry_point: true,
+                        },
+                    )]),
+                },
+            );
+            rs_files_used_by_package
+                .insert(package_id.clone(), HashSet::from([rs_path]));
+        }
+        let print_config = PrintConfig::default();
+        let table_parameters = TableParameters {
+            geiger_context: &geiger_context,
+            print_config: &print_config,
+            rs_files_used_by_package: &rs_files_used_by_package,
+        };
+
+        let blast_radii =
+            blast_radii(&graph, &package_ids[0], &table_parameters);
+
+        let used_unsafe_exprs = |package: usize| {
+            let blast_radius = &blast_radii[&package_ids[package]];
+            (
+                blast_radius.subtree.used.exprs.unsafe_,
+                blast_radius.exclusive.used.exprs.unsafe_,
+            )
+        };
+        assert_eq!(used_unsafe_exprs(0), (15, 15));
+        assert_eq!(used_unsafe_exprs(1), (13, 9));
+        assert_eq!(used_unsafe_exprs(2), (6, 2));
+        assert_eq!(used_unsafe_exprs(3), (4, 4));
+        assert!(!blast_radii[&package_ids[0]].subtree.forbids_unsafe);
+        assert_eq!(
+            blast_radius_annotation(&blast_radii[&package_ids[1]]),
+            " [subtree: 13/13, exclusive: 9/9]"
+        );
+
+        let mut direct_dependencies = vec![
+            (package_ids[2].clone(), blast_radii[&package_ids[2]].clone()),
+            (package_ids[1].clone(), blast_radii[&package_ids[1]].clone()),
+        ];
+        rank_direct_dependencies(&mut direct_dependencies);
+        assert_eq!(direct_dependencies[0].0, package_ids[1]);
+    }
+
+    #[rstest(
+        input_package_indices,
+        expected_forbids_unsafe,
+        case(vec![], false),
+        case(vec![0], true),
+        case(vec![0, 1], false)
+    )]
+    fn unsafe_totals_test_forbids_unsafe(
+        input_package_indices: Vec<usize>,
+        expected_forbids_unsafe: bool,
+    ) {
+        // Package 0 forbids unsafe code, package 1 has no metrics.
+        let package_ids = (0..2)
+            .map(|index| PackageId {
+                repr: format!("package_{}", index),
+            })
+            .collect::<Vec<PackageId>>();
+        let mut geiger_context = GeigerContext::default();
+        geiger_context.package_id_to_metrics.insert(
+            package_ids[0].clone(),
+            PackageMetrics {
+                rs_path_to_metrics: HashMap::from([(
+                    PathBuf::from("package_0/lib.rs"),
+                    RsFileMetricsWrapper {
+                        metrics: RsFileMetrics {
+                            counters: CounterBlock::default(),
+                            forbids_unsafe: true,
+                        },
+                        is_crate_entry_point: true,
+                    },
+                )]),
+            },
+        );
+        let print_config = PrintConfig::default();
+        let rs_files_used_by_package = HashMap::new();
+        let table_parameters = TableParameters {
+            geiger_context: &geiger_context,
+            print_config: &print_config,
+            rs_files_used_by_package: &rs_files_used_by_package,
+        };
+
+        let unsafe_totals = unsafe_totals(
+            &input_package_indices
+                .into_iter()
+                .map(|index| package_ids[index].clone())
+                .collect(),
+            &table_parameters,
+        );
+
+        assert_eq!(unsafe_totals.forbids_unsafe, expected_forbids_unsafe);
+    }
+}
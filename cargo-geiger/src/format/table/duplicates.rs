@@ -0,0 +1,274 @@
+use crate::format::emoji_symbols::EmojiSymbols;
+use crate::graph::Graph;
+use crate::mapping::{CargoMetadataParameters, GetPackageIdInformation};
+use crate::scan::ScanResult;
+
+use super::handle_text_tree_line::package_to_table_line_string;
+use super::{table_row_empty, TableParameters};
+
+use cargo_metadata::semver::Version;
+use cargo_metadata::PackageId;
+use std::collections::BTreeMap;
+
+/// Renders every package of `graph` that is present in more than one version
+/// or from more than one source, with the unsafe counters of each copy and
+/// the dependency paths pulling it in.
+pub fn create_table_from_duplicates(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: &PackageId,
+    table_parameters: &TableParameters,
+) -> ScanResult {
+    let output_format = table_parameters.print_config.output_format;
+    let emoji_symbols = EmojiSymbols::new(output_format);
+    let mut table_lines = Vec::<String>::new();
+    let mut warning_count = 0;
+
+    let duplicate_packages =
+        find_duplicate_packages(cargo_metadata_parameters, graph);
+    if duplicate_packages.is_empty() {
+        table_lines.push(String::from("No duplicate packages found."));
+    }
+
+    for (name, package_ids) in duplicate_packages {
+        table_lines.push(format!(
+            "{}{} ({} copies)",
+            table_row_empty(),
+            name,
+            package_ids.len()
+        ));
+
+        for package_id in package_ids {
+            match package_to_table_line_string(
+                cargo_metadata_parameters,
+                &emoji_symbols,
+                &package_id,
+                table_parameters,
+                String::from("    "),
+                "",
+            ) {
+                Some(table_line) => table_lines.push(table_line),
+                None => {
+                    warning_count += 1;
+                    continue;
+                }
+            }
+
+            for dependency_path in
+                graph.dependency_paths(root_package_id, &package_id)
+            {
+                table_lines.push(format!(
+                    "{}        {}",
+                    table_row_empty(),
+                    format_dependency_path(
+                        cargo_metadata_parameters,
+                        &dependency_path
+                    )
+                ));
+            }
+        }
+        table_lines.push(String::new());
+    }
+
+    ScanResult {
+        scan_output_lines: table_lines,
+        warning_count,
+    }
+}
+
+/// The packages of `graph` sharing their name with another package, grouped
+/// by name and ordered by version
+fn find_duplicate_packages(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+) -> Vec<(String, Vec<PackageId>)> {
+    let mut packages_by_name =
+        BTreeMap::<String, Vec<(Version, PackageId)>>::new();
+    for package_id in graph.graph.node_weights() {
+        if let Some((name, version)) = package_id
+            .get_package_id_name_and_version(cargo_metadata_parameters.krates)
+        {
+            packages_by_name
+                .entry(name)
+                .or_default()
+                .push((version, package_id.clone()));
+        }
+    }
+
+    packages_by_name
+        .into_iter()
+        .filter(|(_, packages)| packages.len() > 1)
+        .map(|(name, mut packages)| {
+            packages.sort();
+            (
+                name,
+                packages
+                    .into_iter()
+                    .map(|(_, package_id)| package_id)
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+pub fn format_dependency_path(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    dependency_path: &[PackageId],
+) -> String {
+    dependency_path
+        .iter()
+        .map(|package_id| {
+            match package_id.get_package_id_name_and_version(
+                cargo_metadata_parameters.krates,
+            ) {
+                Some((name, version)) => format!("{} {}", name, version),
+                None => package_id.repr.clone(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" -> ")
+}
+
+#[cfg(test)]
+mod duplicates_tests {
+    use super::*;
+
+    use cargo_metadata::{DependencyKind, Metadata};
+    use krates::Builder as KratesBuilder;
+    use rstest::*;
+    use std::collections::HashMap;
+
+    #[rstest]
+    fn find_duplicate_packages_test() {
+        // root -> a 1.0.0, root -> b -> a 2.0.0
+        let metadata = create_metadata(&[
+            ("root", "0.1.0", &[("a", "1.0.0"), ("b", "0.1.0")]),
+            ("a", "1.0.0", &[]),
+            ("a", "2.0.0", &[]),
+            ("b", "0.1.0", &[("a", "2.0.0")]),
+        ]);
+        let krates = KratesBuilder::new()
+            .build_with_metadata(metadata.clone(), |_| ())
+            .unwrap();
+        let cargo_metadata_parameters = CargoMetadataParameters {
+            metadata: &metadata,
+            krates: &krates,
+        };
+        let mut graph = Graph {
+            graph: Default::default(),
+            nodes: HashMap::new(),
+        };
+        for package in &metadata.packages {
+            let index = graph.graph.add_node(package.id.clone());
+            graph.nodes.insert(package.id.clone(), index);
+        }
+        for (from, to) in [(0, 1), (0, 3), (3, 2)] {
+            graph.graph.add_edge(
+                graph.nodes[&metadata.packages[from].id],
+                graph.nodes[&metadata.packages[to].id],
+                DependencyKind::Normal.into(),
+            );
+        }
+
+        let duplicate_packages =
+            find_duplicate_packages(&cargo_metadata_parameters, &graph);
+
+        assert_eq!(
+            duplicate_packages,
+            vec![(
+                String::from("a"),
+                vec![
+                    metadata.packages[1].id.clone(),
+                    metadata.packages[2].id.clone()
+                ]
+            )]
+        );
+        assert_eq!(
+            graph
+                .dependency_paths(
+                    &metadata.packages[0].id,
+                    &metadata.packages[2].id
+                )
+                .iter()
+                .map(|dependency_path| format_dependency_path(
+                    &cargo_metadata_parameters,
+                    dependency_path
+                ))
+                .collect::<Vec<String>>(),
+            vec![String::from("root 0.1.0 -> b 0.1.0 -> a 2.0.0")]
+        );
+    }
+
+    /// Name, version and the name and version of each dependency
+    type SyntheticPackage<'a> = (&'a str, &'a str, &'a [(&'a str, &'a str)]);
+
+    fn create_metadata(packages: &[SyntheticPackage]) -> Metadata {
+        let package_id = |name: &str, version: &str| {
+            format!(
+                "{} {} (registry+https://github.com/rust-lang/crates.io-index)",
+                name, version
+            )
+        };
+        let json_packages = packages
+            .iter()
+            .map(|(name, version, dependencies)| {
+                serde_json::json!({
+                    "name": name,
+                    "version": version,
+                    "id": package_id(name, version),
+                    "source": "registry+https://github.com/rust-lang/crates.io-index",
+                    "dependencies": dependencies
+                        .iter()
+                        .map(|(dependency_name, dependency_version)| serde_json::json!({
+                            "name": dependency_name,
+                            "source": "registry+https://github.com/rust-lang/crates.io-index",
+                            "req": format!("={}", dependency_version),
+                            "kind": null,
+                            "optional": false,
+                            "uses_default_features": true,
+                            "features": [],
+                            "target": null,
+                            "rename": null,
+                            "registry": null,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "targets": [],
+                    "features": {},
+                    "manifest_path": format!("/{}-{}/Cargo.toml", name, version),
+                })
+            })
+            .collect::<Vec<_>>();
+        let nodes = packages
+            .iter()
+            .map(|(name, version, dependencies)| {
+                serde_json::json!({
+                    "id": package_id(name, version),
+                    "dependencies": dependencies
+                        .iter()
+                        .map(|(dependency_name, dependency_version)| package_id(dependency_name, dependency_version))
+                        .collect::<Vec<_>>(),
+                    "deps": dependencies
+                        .iter()
+                        .map(|(dependency_name, dependency_version)| serde_json::json!({
+                            "name": dependency_name,
+                            "pkg": package_id(dependency_name, dependency_version),
+                            "dep_kinds": [{ "kind": null, "target": null }],
+                        }))
+                        .collect::<Vec<_>>(),
+                    "features": [],
+                })
+            })
+            .collect::<Vec<_>>();
+        let root_package_id = package_id(packages[0].0, packages[0].1);
+
+        serde_json::from_value(serde_json::json!({
+            "packages": json_packages,
+            "workspace_members": [root_package_id],
+            "resolve": { "nodes": nodes, "root": root_package_id },
+            "workspace_root": "/",
+            "target_directory": "/target",
+            "version": 1,
+        }))
+        .unwrap()
+    }
+}
@@ -0,0 +1,211 @@
+use crate::format::emoji_symbols::EmojiSymbols;
+use crate::format::SortBy;
+use crate::graph::Graph;
+use crate::mapping::CargoMetadataParameters;
+use crate::scan::{package_unsafe_stats, ScanResult};
+
+use super::handle_text_tree_line::package_to_table_line_string;
+use super::{table_row_empty, TableParameters};
+
+use cargo_geiger_serde::{CounterBlock, UnsafeInfo};
+use cargo_metadata::PackageId;
+use std::cmp::Ordering;
+
+/// Renders every package of `graph` reachable from `root_package_id` once,
+/// ranked by `sort_by`, worst first. Only the first `top` packages are shown
+/// if given.
+pub fn create_table_from_flat_ranking(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: &PackageId,
+    table_parameters: &TableParameters,
+    sort_by: SortBy,
+    top: Option<usize>,
+) -> ScanResult {
+    let emoji_symbols =
+        EmojiSymbols::new(table_parameters.print_config.output_format);
+    let mut warning_count = 0;
+
+    let mut ranked_packages = Vec::<(PackageId, UnsafeInfo)>::new();
+    for package_id in graph.subtree_packages(root_package_id) {
+        match table_parameters
+            .geiger_context
+            .package_id_to_metrics
+            .get(&package_id)
+        {
+            Some(package_metrics) => {
+                let unsafe_info = package_unsafe_stats(
+                    package_metrics,
+                    &package_id,
+                    table_parameters.rs_files_used_by_package,
+                );
+                ranked_packages.push((package_id, unsafe_info));
+            }
+            None => {
+                eprintln!(
+                    "WARNING: No metrics found for package: {}",
+                    package_id
+                );
+                warning_count += 1;
+            }
+        }
+    }
+    rank_packages(&mut ranked_packages, sort_by);
+
+    let package_count = ranked_packages.len();
+    let shown_package_count = top.unwrap_or(package_count).min(package_count);
+    let mut table_lines = vec![format!(
+        "{}Packages ranked by {}:",
+        table_row_empty(),
+        sort_by_description(sort_by)
+    )];
+    for (rank, (package_id, _)) in
+        ranked_packages.iter().take(shown_package_count).enumerate()
+    {
+        if let Some(table_line) = package_to_table_line_string(
+            cargo_metadata_parameters,
+            &emoji_symbols,
+            package_id,
+            table_parameters,
+            format!("{:>3}. ", rank + 1),
+            "",
+        ) {
+            table_lines.push(table_line);
+        }
+    }
+    if shown_package_count < package_count {
+        table_lines.push(format!(
+            "{}     ... and {} more",
+            table_row_empty(),
+            package_count - shown_package_count
+        ));
+    }
+    table_lines.push(String::new());
+
+    ScanResult {
+        scan_output_lines: table_lines,
+        warning_count,
+    }
+}
+
+/// Orders by the metric selected by `sort_by`, then by all used unsafe items,
+/// most first
+fn rank_packages(packages: &mut [(PackageId, UnsafeInfo)], sort_by: SortBy) {
+    packages.sort_by(|(a_id, a), (b_id, b)| {
+        metric(b, sort_by)
+            .partial_cmp(&metric(a, sort_by))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| unsafe_total(&b.used).cmp(&unsafe_total(&a.used)))
+            .then_with(|| a_id.cmp(b_id))
+    });
+}
+
+fn metric(unsafe_info: &UnsafeInfo, sort_by: SortBy) -> f64 {
+    let used = &unsafe_info.used;
+    match sort_by {
+        SortBy::Functions => used.functions.unsafe_ as f64,
+        SortBy::Exprs => used.exprs.unsafe_ as f64,
+        SortBy::Impls => used.item_impls.unsafe_ as f64,
+        SortBy::Traits => used.item_traits.unsafe_ as f64,
+        SortBy::Methods => used.methods.unsafe_ as f64,
+        SortBy::Total => unsafe_total(used) as f64,
+        SortBy::Ratio => {
+            let all = unsafe_info.used.clone() + unsafe_info.unused.clone();
+            let unsafe_items = unsafe_total(&all);
+            let items = unsafe_items + safe_total(&all);
+            if items == 0 {
+                0.0
+            } else {
+                unsafe_items as f64 / items as f64
+            }
+        }
+    }
+}
+
+fn unsafe_total(counter_block: &CounterBlock) -> u64 {
+    counter_block.functions.unsafe_
+        + counter_block.exprs.unsafe_
+        + counter_block.item_impls.unsafe_
+        + counter_block.item_traits.unsafe_
+        + counter_block.methods.unsafe_
+}
+
+fn safe_total(counter_block: &CounterBlock) -> u64 {
+    counter_block.functions.safe
+        + counter_block.exprs.safe
+        + counter_block.item_impls.safe
+        + counter_block.item_traits.safe
+        + counter_block.methods.safe
+}
+
+fn sort_by_description(sort_by: SortBy) -> &'static str {
+    match sort_by {
+        SortBy::Functions => "used unsafe functions",
+        SortBy::Exprs => "used unsafe expressions",
+        SortBy::Impls => "used unsafe impls",
+        SortBy::Traits => "used unsafe traits",
+        SortBy::Methods => "used unsafe methods",
+        SortBy::Total => "all used unsafe items",
+        SortBy::Ratio => "share of unsafe items",
+    }
+}
+
+#[cfg(test)]
+mod flat_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+
+    #[rstest(
+        input_sort_by,
+        expected_ranking,
+        case(SortBy::Exprs, vec!["b", "a", "c"]),
+        case(SortBy::Functions, vec!["a", "b", "c"]),
+        case(SortBy::Total, vec!["a", "b", "c"]),
+        case(SortBy::Ratio, vec!["c", "a", "b"])
+    )]
+    fn rank_packages_test(input_sort_by: SortBy, expected_ranking: Vec<&str>) {
+        let unsafe_info =
+            |functions: u64, exprs: u64, unused_safe: u64| UnsafeInfo {
+                used: CounterBlock {
+                    functions: Count {
+                        safe: 0,
+                        unsafe_: functions,
+                    },
+                    exprs: Count {
+                        safe: 0,
+                        unsafe_: exprs,
+                    },
+                    ..Default::default()
+                },
+                unused: CounterBlock {
+                    exprs: Count {
+                        safe: unused_safe,
+                        unsafe_: 0,
+                    },
+                    ..Default::default()
+                },
+                forbids_unsafe: false,
+            };
+        let package_id = |name: &str| PackageId {
+            repr: String::from(name),
+        };
+        // Ratios: a 5/15, b 4/16, c 1/1
+        let mut packages = vec![
+            (package_id("c"), unsafe_info(0, 1, 0)),
+            (package_id("a"), unsafe_info(3, 2, 10)),
+            (package_id("b"), unsafe_info(0, 4, 12)),
+        ];
+
+        rank_packages(&mut packages, input_sort_by);
+
+        assert_eq!(
+            packages
+                .iter()
+                .map(|(package_id, _)| package_id.repr.as_str())
+                .collect::<Vec<&str>>(),
+            expected_ranking
+        );
+    }
+}
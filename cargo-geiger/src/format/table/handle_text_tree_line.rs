@@ -1,19 +1,33 @@
 use crate::format::emoji_symbols::EmojiSymbols;
 use crate::format::print_config::{colorize, OutputFormat};
-use crate::format::{get_kind_group_name, CrateDetectionStatus, SymbolKind};
+use crate::format::{
+    format_edge_conditions, get_kind_group_name, CrateDetectionStatus,
+    SymbolKind,
+};
 use crate::mapping::CargoMetadataParameters;
-use crate::scan::unsafe_stats;
+use crate::scan::package_unsafe_stats;
 
+use super::blast_radius::{blast_radius_annotation, BlastRadius};
+use super::modules::get_module_detection_status;
 use super::total_package_counts::TotalPackageCounts;
 use super::TableParameters;
 use super::{table_row, table_row_empty};
 
 use cargo_metadata::{DependencyKind, PackageId};
 use colored::ColoredString;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 
+/// A package line of the dependency tree
+pub struct TreePackageLine {
+    pub depth: usize,
+    pub edge_conditions: Vec<String>,
+    pub package_id: PackageId,
+    pub tree_vines: String,
+}
+
 pub struct HandlePackageParameters<'a> {
+    pub blast_radii: Option<&'a HashMap<PackageId, BlastRadius>>,
     pub total_package_counts: &'a mut TotalPackageCounts,
     pub visited_package_ids: &'a mut HashSet<PackageId>,
     pub warning_count: &'a mut u64,
@@ -31,10 +45,15 @@ pub fn text_tree_line_package_to_table_line_string(
     cargo_metadata_parameters: &CargoMetadataParameters,
     emoji_symbols: &EmojiSymbols,
     handle_package_parameters: &mut HandlePackageParameters,
-    package_id: PackageId,
     table_parameters: &TableParameters,
-    tree_vines: String,
+    tree_package_line: TreePackageLine,
 ) -> Option<String> {
+    let TreePackageLine {
+        depth,
+        edge_conditions,
+        package_id,
+        tree_vines,
+    } = tree_package_line;
     let package_is_new = handle_package_parameters
         .visited_package_ids
         .insert(package_id.clone());
@@ -51,8 +70,11 @@ pub fn text_tree_line_package_to_table_line_string(
             return None;
         }
     };
-    let unsafe_info =
-        unsafe_stats(package_metrics, table_parameters.rs_files_used);
+    let unsafe_info = package_unsafe_stats(
+        package_metrics,
+        &package_id,
+        table_parameters.rs_files_used_by_package,
+    );
     if package_is_new {
         handle_package_parameters
             .total_package_counts
@@ -88,13 +110,25 @@ pub fn text_tree_line_package_to_table_line_string(
         &crate_detection_status,
         table_parameters.print_config.output_format,
         format!(
-            "{}",
+            "{}{}{}",
             table_parameters
                 .print_config
                 .format
                 .display(cargo_metadata_parameters, &package_id)
+                .with_depth(depth)
+                .with_unsafe_info(&unsafe_info),
+            format_edge_conditions(&edge_conditions),
+            handle_package_parameters
+                .blast_radii
+                .and_then(|blast_radii| blast_radii.get(&package_id))
+                .map(blast_radius_annotation)
+                .unwrap_or_default()
         ),
     );
+    // The pattern lays out the whole line when it shows the unsafe usage.
+    if table_parameters.print_config.format.shows_unsafe_usage() {
+        return Some(format!("{}{}", tree_vines, package_name));
+    }
     let unsafe_info = colorize(
         &crate_detection_status,
         table_parameters.print_config.output_format,
@@ -116,7 +150,80 @@ pub fn text_tree_line_package_to_table_line_string(
     ))
 }
 
-fn construct_package_text_tree_line(
+/// A table line for `package_id` outside of the dependency tree, with
+/// `annotation` following the package name. `None` if the package has no
+/// metrics.
+pub fn package_to_table_line_string(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    emoji_symbols: &EmojiSymbols,
+    package_id: &PackageId,
+    table_parameters: &TableParameters,
+    tree_vines: String,
+    annotation: &str,
+) -> Option<String> {
+    let output_format = table_parameters.print_config.output_format;
+    let package_metrics = match table_parameters
+        .geiger_context
+        .package_id_to_metrics
+        .get(package_id)
+    {
+        Some(package_metrics) => package_metrics,
+        None => {
+            eprintln!("WARNING: No metrics found for package: {}", package_id);
+            return None;
+        }
+    };
+    let unsafe_info = package_unsafe_stats(
+        package_metrics,
+        package_id,
+        table_parameters.rs_files_used_by_package,
+    );
+    let crate_detection_status = get_module_detection_status(&unsafe_info);
+    let icon = match crate_detection_status {
+        CrateDetectionStatus::NoneDetectedForbidsUnsafe => {
+            emoji_symbols.emoji(SymbolKind::Lock)
+        }
+        CrateDetectionStatus::NoneDetectedAllowsUnsafe => {
+            emoji_symbols.emoji(SymbolKind::QuestionMark)
+        }
+        CrateDetectionStatus::UnsafeDetected => {
+            emoji_symbols.emoji(SymbolKind::Rads)
+        }
+    };
+    let package_name = colorize(
+        &crate_detection_status,
+        output_format,
+        format!(
+            "{}{}",
+            table_parameters
+                .print_config
+                .format
+                .display(cargo_metadata_parameters, package_id)
+                .with_unsafe_info(&unsafe_info),
+            annotation
+        ),
+    );
+    if table_parameters.print_config.format.shows_unsafe_usage() {
+        return Some(format!("{}{}", tree_vines, package_name));
+    }
+    let unsafe_row = colorize(
+        &crate_detection_status,
+        output_format,
+        table_row(&unsafe_info.used, &unsafe_info.unused, output_format),
+    );
+
+    Some(construct_package_text_tree_line(
+        crate_detection_status,
+        emoji_symbols,
+        icon,
+        package_name,
+        table_parameters,
+        tree_vines,
+        unsafe_row,
+    ))
+}
+
+pub fn construct_package_text_tree_line(
     crate_detection_status: CrateDetectionStatus,
     emoji_symbols: &EmojiSymbols,
     icon: Box<dyn Display>,
@@ -259,7 +366,7 @@ mod handle_text_tree_line_tests {
                 output_format: input_output_format,
                 ..Default::default()
             },
-            rs_files_used: &Default::default(),
+            rs_files_used_by_package: &Default::default(),
         };
         let tree_vines = String::from("tree_vines");
         let unsafe_info = ColoredString::from("unsafe_info").normal();
@@ -334,6 +441,7 @@ mod handle_text_tree_line_tests {
         expected_unsafe_detected: i32,
     ) {
         let mut handle_package_parameters = HandlePackageParameters {
+            blast_radii: None,
             total_package_counts: &mut TotalPackageCounts {
                 none_detected_forbids_unsafe: 0,
                 none_detected_allows_unsafe: 0,
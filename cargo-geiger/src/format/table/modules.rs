@@ -0,0 +1,249 @@
+use crate::format::emoji_symbols::EmojiSymbols;
+use crate::format::print_config::colorize;
+use crate::format::{CrateDetectionStatus, SymbolKind};
+use crate::scan::{parent_module_name, ScanResult};
+use crate::tree::construct_tree_vines_string;
+
+use super::handle_text_tree_line::construct_package_text_tree_line;
+use super::{table_footer, table_row, table_row_empty, TableParameters};
+
+use cargo_geiger_serde::{CounterBlock, ModuleEntry, UnsafeInfo};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Renders the modules of a single package as one tree per crate entry point,
+/// followed by a footer with the totals of the package.
+pub fn create_table_from_module_entries(
+    module_entries: &[ModuleEntry],
+    table_parameters: &TableParameters,
+) -> ScanResult {
+    let output_format = table_parameters.print_config.output_format;
+    let emoji_symbols = EmojiSymbols::new(output_format);
+    let mut table_lines = Vec::<String>::new();
+
+    let mut crate_roots = Vec::<&Option<PathBuf>>::new();
+    for module_entry in module_entries {
+        if !crate_roots.contains(&&module_entry.crate_root) {
+            crate_roots.push(&module_entry.crate_root);
+        }
+    }
+
+    for crate_root in crate_roots {
+        let crate_module_entries = module_entries
+            .iter()
+            .filter(|m| &m.crate_root == crate_root)
+            .collect::<Vec<&ModuleEntry>>();
+
+        let crate_root_name = match crate_root {
+            Some(path_buf) => path_buf.display().to_string(),
+            None => String::from("(outside of any crate)"),
+        };
+        table_lines.push(format!("{}{}", table_row_empty(), crate_root_name));
+
+        let names = crate_module_entries
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect::<HashSet<&str>>();
+        let mut children = HashMap::<Option<&str>, Vec<&ModuleEntry>>::new();
+        for module_entry in &crate_module_entries {
+            children
+                .entry(parent_module_name(&module_entry.name, &names))
+                .or_default()
+                .push(module_entry);
+        }
+
+        let mut levels_continue = vec![];
+        walk_module_tree(
+            &children,
+            &emoji_symbols,
+            &mut levels_continue,
+            None,
+            table_parameters,
+            &mut table_lines,
+        );
+    }
+
+    let mut used = CounterBlock::default();
+    let mut unused = CounterBlock::default();
+    for module_entry in module_entries {
+        used += module_entry.unsafety.used.clone();
+        unused += module_entry.unsafety.unused.clone();
+    }
+    let total_detection_status = get_module_detection_status(&UnsafeInfo {
+        used: used.clone(),
+        unused: unused.clone(),
+        forbids_unsafe: module_entries
+            .iter()
+            .filter(|m| m.crate_root.is_some())
+            .all(|m| m.unsafety.forbids_unsafe),
+    });
+
+    table_lines.push(String::new());
+    table_lines.push(format!(
+        "{}",
+        table_footer(used, unused, output_format, total_detection_status)
+    ));
+    table_lines.push(String::new());
+
+    ScanResult {
+        scan_output_lines: table_lines,
+        warning_count: 0,
+    }
+}
+
+pub fn get_module_detection_status(
+    unsafety: &UnsafeInfo,
+) -> CrateDetectionStatus {
+    match (unsafety.forbids_unsafe, unsafety.used.has_unsafe()) {
+        (_, true) => CrateDetectionStatus::UnsafeDetected,
+        (true, false) => CrateDetectionStatus::NoneDetectedForbidsUnsafe,
+        (false, false) => CrateDetectionStatus::NoneDetectedAllowsUnsafe,
+    }
+}
+
+fn walk_module_tree(
+    children: &HashMap<Option<&str>, Vec<&ModuleEntry>>,
+    emoji_symbols: &EmojiSymbols,
+    levels_continue: &mut Vec<bool>,
+    parent: Option<&str>,
+    table_parameters: &TableParameters,
+    table_lines: &mut Vec<String>,
+) {
+    let module_entries = match children.get(&parent) {
+        Some(module_entries) => module_entries,
+        None => return,
+    };
+
+    let mut module_entry_iterator = module_entries.iter().peekable();
+    while let Some(module_entry) = module_entry_iterator.next() {
+        levels_continue.push(module_entry_iterator.peek().is_some());
+
+        let output_format = table_parameters.print_config.output_format;
+        let crate_detection_status =
+            get_module_detection_status(&module_entry.unsafety);
+        let icon = match crate_detection_status {
+            CrateDetectionStatus::NoneDetectedForbidsUnsafe => {
+                emoji_symbols.emoji(SymbolKind::Lock)
+            }
+            CrateDetectionStatus::NoneDetectedAllowsUnsafe => {
+                emoji_symbols.emoji(SymbolKind::QuestionMark)
+            }
+            CrateDetectionStatus::UnsafeDetected => {
+                emoji_symbols.emoji(SymbolKind::Rads)
+            }
+        };
+        let module_name = colorize(
+            &crate_detection_status,
+            output_format,
+            module_entry.name.clone(),
+        );
+        let unsafe_info = colorize(
+            &crate_detection_status,
+            output_format,
+            table_row(
+                &module_entry.unsafety.used,
+                &module_entry.unsafety.unused,
+                output_format,
+            ),
+        );
+        let tree_vines = construct_tree_vines_string(
+            levels_continue,
+            table_parameters.print_config,
+        );
+
+        table_lines.push(construct_package_text_tree_line(
+            crate_detection_status,
+            emoji_symbols,
+            icon,
+            module_name,
+            table_parameters,
+            tree_vines,
+            unsafe_info,
+        ));
+
+        walk_module_tree(
+            children,
+            emoji_symbols,
+            levels_continue,
+            Some(module_entry.name.as_str()),
+            table_parameters,
+            table_lines,
+        );
+        levels_continue.pop();
+    }
+}
+
+#[cfg(test)]
+mod modules_tests {
+    use super::*;
+
+    use crate::format::print_config::{OutputFormat, Prefix, PrintConfig};
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+
+    #[rstest]
+    fn create_table_from_module_entries_test() {
+        let module_entries = vec![
+            create_module_entry("crate", 0, true),
+            create_module_entry("crate::foo", 2, false),
+            create_module_entry("crate::foo::bar", 1, false),
+            create_module_entry("crate::qux", 0, false),
+        ];
+        let print_config = PrintConfig {
+            output_format: OutputFormat::Ascii,
+            prefix: Prefix::Indent,
+            ..Default::default()
+        };
+        let table_parameters = TableParameters {
+            geiger_context: &Default::default(),
+            print_config: &print_config,
+            rs_files_used_by_package: &Default::default(),
+        };
+
+        let ScanResult {
+            scan_output_lines,
+            warning_count,
+        } = create_table_from_module_entries(
+            &module_entries,
+            &table_parameters,
+        );
+
+        let module_lines = scan_output_lines
+            .iter()
+            .skip(1)
+            .take(module_entries.len())
+            .map(|line| line.split(" ! ").last().unwrap().to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(warning_count, 0);
+        assert!(scan_output_lines[0].ends_with("src/lib.rs"));
+        assert!(module_lines[0].ends_with("`-- crate"));
+        assert!(module_lines[1].ends_with("    |-- crate::foo"));
+        assert!(module_lines[2].ends_with("    |   `-- crate::foo::bar"));
+        assert!(module_lines[3].ends_with("    `-- crate::qux"));
+    }
+
+    fn create_module_entry(
+        name: &str,
+        unsafe_exprs: u64,
+        forbids_unsafe: bool,
+    ) -> ModuleEntry {
+        ModuleEntry {
+            crate_root: Some(PathBuf::from("src/lib.rs")),
+            name: String::from(name),
+            path: PathBuf::from(name),
+            unsafety: UnsafeInfo {
+                used: CounterBlock {
+                    exprs: Count {
+                        safe: 1,
+                        unsafe_: unsafe_exprs,
+                    },
+                    ..Default::default()
+                },
+                unused: Default::default(),
+                forbids_unsafe,
+            },
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::format::emoji_symbols::EmojiSymbols;
+use crate::format::{get_edge_conditions, EdgeAnnotation};
+use crate::graph::{DependencyEdge, Graph};
+use crate::mapping::{CargoMetadataParameters, QueryResolve};
+use crate::scan::ScanResult;
+use crate::tree::get_tree_symbols;
+
+use super::handle_text_tree_line::package_to_table_line_string;
+use super::{table_row_empty, TableParameters};
+
+use cargo_metadata::PackageId;
+use strum::IntoEnumIterator;
+
+/// Paths beyond this are not searched for, the number of paths grows
+/// exponentially with the depth of the graph.
+const MAX_WHY_PATHS: usize = 100;
+
+/// Renders the paths from `root_package_id` to the packages matching
+/// `package_spec`, with the unsafe counters of every package on them and the
+/// kind, enabling features and target of every dependency.
+pub fn create_table_from_why(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    package_spec: &str,
+    root_package_id: &PackageId,
+    table_parameters: &TableParameters,
+) -> ScanResult {
+    let output_format = table_parameters.print_config.output_format;
+    let emoji_symbols = EmojiSymbols::new(output_format);
+    let tree_symbols = get_tree_symbols(output_format);
+    let mut table_lines = Vec::<String>::new();
+    let mut warning_count = 0;
+
+    let mut package_ids = cargo_metadata_parameters
+        .krates
+        .query_resolve_all(package_spec)
+        .into_iter()
+        .filter(|package_id| graph.nodes.contains_key(package_id))
+        .collect::<Vec<PackageId>>();
+    package_ids.sort();
+    if package_ids.is_empty() {
+        table_lines.push(format!(
+            "No package in the dependency graph matches: {}",
+            package_spec
+        ));
+    }
+
+    for package_id in package_ids {
+        let paths = graph.all_dependency_paths(
+            root_package_id,
+            &package_id,
+            MAX_WHY_PATHS,
+        );
+        table_lines.push(format!(
+            "{}{} is pulled in by {} path(s){}",
+            table_row_empty(),
+            table_parameters
+                .print_config
+                .format
+                .display(cargo_metadata_parameters, &package_id),
+            paths.len(),
+            if paths.len() == MAX_WHY_PATHS {
+                format!(", only the {} shortest are shown", MAX_WHY_PATHS)
+            } else {
+                String::new()
+            }
+        ));
+
+        for path in paths {
+            table_lines.push(String::new());
+            for (depth, path_package_id) in path.iter().enumerate() {
+                let (tree_vines, annotation) = match depth {
+                    0 => (String::new(), String::new()),
+                    _ => (
+                        format!(
+                            "{}{}{2}{2} ",
+                            "    ".repeat(depth - 1),
+                            tree_symbols.ell,
+                            tree_symbols.right
+                        ),
+                        edge_annotation(
+                            graph,
+                            &path[depth - 1],
+                            path_package_id,
+                        ),
+                    ),
+                };
+                match package_to_table_line_string(
+                    cargo_metadata_parameters,
+                    &emoji_symbols,
+                    path_package_id,
+                    table_parameters,
+                    tree_vines,
+                    &annotation,
+                ) {
+                    Some(table_line) => table_lines.push(table_line),
+                    None => warning_count += 1,
+                }
+            }
+        }
+        table_lines.push(String::new());
+    }
+
+    ScanResult {
+        scan_output_lines: table_lines,
+        warning_count,
+    }
+}
+
+/// Describes the dependencies of `package_id` on `dependency_package_id`, e.g.
+/// ` (normal, feature "std", cfg(unix))`
+fn edge_annotation(
+    graph: &Graph,
+    package_id: &PackageId,
+    dependency_package_id: &PackageId,
+) -> String {
+    let mut dependency_edges = graph
+        .graph
+        .edges_connecting(
+            graph.nodes[package_id],
+            graph.nodes[dependency_package_id],
+        )
+        .map(|edge| edge.weight())
+        .collect::<Vec<&DependencyEdge>>();
+    dependency_edges
+        .sort_by_key(|dependency_edge| dependency_edge.kind.to_string());
+
+    let edge_annotations = EdgeAnnotation::iter().collect::<Vec<_>>();
+    let mut annotations = Vec::<String>::new();
+    for dependency_edge in dependency_edges {
+        let kind = dependency_edge.kind.to_string();
+        if !annotations.contains(&kind) {
+            annotations.push(kind);
+        }
+        annotations
+            .extend(get_edge_conditions(dependency_edge, &edge_annotations));
+    }
+    annotations.dedup();
+
+    format!(" ({})", annotations.join(", "))
+}
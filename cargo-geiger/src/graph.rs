@@ -4,27 +4,356 @@ use extra_deps::ExtraDeps;
 
 use crate::args::{Args, DepsArgs, TargetArgs};
 use crate::cli::get_cfgs;
-use crate::mapping::{
-    CargoMetadataParameters, DepsNotReplaced, MatchesIgnoringSource,
-};
+use crate::mapping::{CargoMetadataParameters, QueryResolve};
 
 use cargo::util::CargoResult;
-use cargo_metadata::{Dependency, DependencyKind, Package, PackageId};
-use cargo_platform::Cfg;
+use cargo_metadata::{
+    DepKindInfo, Dependency, DependencyKind, Metadata, Node, NodeDep, Package,
+    PackageId,
+};
+use cargo_platform::{Cfg, Platform};
+use krates::Krates;
 use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::EdgeDirection;
+use std::cmp::Reverse;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 
 /// Representation of the package dependency graph
 pub struct Graph {
-    pub graph: petgraph::Graph<PackageId, DependencyKind>,
+    pub graph: petgraph::Graph<PackageId, DependencyEdge>,
     pub nodes: HashMap<PackageId, NodeIndex>,
 }
 
-// Almost unmodified compared to the original in cargo-tree, should be fairly
-// simple to move this and the dependency graph structure out to a library.
-/// Function to build a graph of packages dependencies
+/// A dependency of a package on another and the conditions it is subject to
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DependencyEdge {
+    pub kind: DependencyKind,
+    /// Platform the dependency is limited to, a `cfg` expression or a target
+    /// triple
+    pub target: Option<String>,
+    /// Features of the dependent package activating the optional dependency
+    pub features: Vec<String>,
+}
+
+impl DependencyEdge {
+    pub fn is_conditional(&self) -> bool {
+        self.target.is_some() || !self.features.is_empty()
+    }
+}
+
+impl From<DependencyKind> for DependencyEdge {
+    fn from(kind: DependencyKind) -> Self {
+        DependencyEdge {
+            kind,
+            target: None,
+            features: vec![],
+        }
+    }
+}
+
+impl Graph {
+    /// A copy of the graph containing only `package_ids` and `root_package_id`,
+    /// and the edges between them
+    pub fn retain_packages(
+        &self,
+        package_ids: &HashSet<PackageId>,
+        root_package_id: &PackageId,
+    ) -> Graph {
+        let graph = self.graph.filter_map(
+            |_, package_id| {
+                (package_ids.contains(package_id)
+                    || package_id == root_package_id)
+                    .then(|| package_id.clone())
+            },
+            |_, dependency_edge| Some(dependency_edge.clone()),
+        );
+        let nodes = graph
+            .node_indices()
+            .map(|index| (graph[index].clone(), index))
+            .collect();
+        Graph { graph, nodes }
+    }
+
+    /// One path from `root_package_id` to `package_id` through each package
+    /// depending directly on `package_id`, each as short as possible
+    pub fn dependency_paths(
+        &self,
+        root_package_id: &PackageId,
+        package_id: &PackageId,
+    ) -> Vec<Vec<PackageId>> {
+        let (root_index, package_index) =
+            match (self.nodes.get(root_package_id), self.nodes.get(package_id))
+            {
+                (Some(root_index), Some(package_index)) => {
+                    (*root_index, *package_index)
+                }
+                _ => return vec![],
+            };
+        if root_index == package_index {
+            return vec![vec![root_package_id.clone()]];
+        }
+
+        let mut parents = HashMap::<NodeIndex, NodeIndex>::new();
+        let mut pending_indices = VecDeque::from([root_index]);
+        while let Some(index) = pending_indices.pop_front() {
+            // Paths through the package itself are cycles back to it.
+            if index == package_index {
+                continue;
+            }
+            let mut neighbor_indices =
+                self.graph.neighbors(index).collect::<Vec<NodeIndex>>();
+            neighbor_indices.sort_by_key(|index| &self.graph[*index]);
+            for neighbor_index in neighbor_indices {
+                if neighbor_index != root_index
+                    && !parents.contains_key(&neighbor_index)
+                {
+                    parents.insert(neighbor_index, index);
+                    pending_indices.push_back(neighbor_index);
+                }
+            }
+        }
+
+        let mut dependent_indices = self
+            .graph
+            .neighbors_directed(package_index, EdgeDirection::Incoming)
+            .filter(|dependent_index| {
+                *dependent_index == root_index
+                    || parents.contains_key(dependent_index)
+            })
+            .collect::<Vec<NodeIndex>>();
+        dependent_indices.sort_by_key(|index| &self.graph[*index]);
+        dependent_indices.dedup();
+
+        dependent_indices
+            .into_iter()
+            .map(|dependent_index| {
+                let mut path = vec![self.graph[package_index].clone()];
+                let mut index = dependent_index;
+                path.push(self.graph[index].clone());
+                while let Some(parent_index) = parents.get(&index) {
+                    index = *parent_index;
+                    path.push(self.graph[index].clone());
+                }
+                path.reverse();
+                path
+            })
+            .collect()
+    }
+
+    /// The distinct paths from `root_package_id` to `package_id`, shortest
+    /// first. The search only visits the packages `package_id` can be reached
+    /// from and stops after `max_paths` paths, as the number of paths can grow
+    /// exponentially with the size of the graph.
+    pub fn all_dependency_paths(
+        &self,
+        root_package_id: &PackageId,
+        package_id: &PackageId,
+        max_paths: usize,
+    ) -> Vec<Vec<PackageId>> {
+        let (root_index, package_index) =
+            match (self.nodes.get(root_package_id), self.nodes.get(package_id))
+            {
+                (Some(root_index), Some(package_index)) => {
+                    (*root_index, *package_index)
+                }
+                _ => return vec![],
+            };
+        if root_index == package_index {
+            return vec![vec![root_package_id.clone()]];
+        }
+
+        // The distance of each package to `package_id`, for the packages it
+        // can be reached from.
+        let mut distances = HashMap::from([(package_index, 0)]);
+        let mut pending_indices = VecDeque::from([package_index]);
+        while let Some(index) = pending_indices.pop_front() {
+            let distance = distances[&index] + 1;
+            for dependent_index in self
+                .graph
+                .neighbors_directed(index, EdgeDirection::Incoming)
+            {
+                if let Entry::Vacant(entry) = distances.entry(dependent_index) {
+                    entry.insert(distance);
+                    pending_indices.push_back(dependent_index);
+                }
+            }
+        }
+        if !distances.contains_key(&root_index) {
+            return vec![];
+        }
+
+        // Partial paths are extended in the order of the length of their
+        // shortest possible completion, then of their packages, so complete
+        // paths are found shortest first and the search can stop early.
+        let mut paths = Vec::<Vec<PackageId>>::new();
+        let mut pending_paths = BinaryHeap::from([Reverse((
+            distances[&root_index],
+            vec![root_package_id.clone()],
+            vec![root_index],
+        ))]);
+        while let Some(Reverse((_, path, path_indices))) = pending_paths.pop() {
+            let index = *path_indices.last().unwrap();
+            if index == package_index {
+                paths.push(path);
+                if paths.len() >= max_paths {
+                    break;
+                }
+                continue;
+            }
+            let mut neighbor_indices =
+                self.graph.neighbors(index).collect::<Vec<NodeIndex>>();
+            // Parallel edges of different kinds lead to the same path.
+            neighbor_indices.sort();
+            neighbor_indices.dedup();
+            for neighbor_index in neighbor_indices {
+                let distance = match distances.get(&neighbor_index) {
+                    Some(distance)
+                        if !path_indices.contains(&neighbor_index) =>
+                    {
+                        distance
+                    }
+                    _ => continue,
+                };
+                let mut neighbor_path = path.clone();
+                neighbor_path.push(self.graph[neighbor_index].clone());
+                let mut neighbor_path_indices = path_indices.clone();
+                neighbor_path_indices.push(neighbor_index);
+                pending_paths.push(Reverse((
+                    path_indices.len() + distance,
+                    neighbor_path,
+                    neighbor_path_indices,
+                )));
+            }
+        }
+        paths
+    }
+
+    /// A copy of the graph without `pruned_package_ids` and the packages that
+    /// are only reachable from `root_package_id` through them
+    pub fn prune_packages(
+        &self,
+        pruned_package_ids: &HashSet<PackageId>,
+        root_package_id: &PackageId,
+    ) -> Graph {
+        let reachable_package_ids =
+            self.reachable_packages(root_package_id, pruned_package_ids);
+        self.retain_packages(&reachable_package_ids, root_package_id)
+    }
+
+    /// The packages reachable from `package_id`, including itself
+    pub fn subtree_packages(
+        &self,
+        package_id: &PackageId,
+    ) -> HashSet<PackageId> {
+        self.reachable_packages(package_id, &HashSet::new())
+    }
+
+    /// The packages of the subtree of `package_id`, including itself, that
+    /// are not reachable from `root_package_id` without going through
+    /// `package_id`, i.e. the packages that would disappear along with it
+    pub fn exclusive_subtree_packages(
+        &self,
+        root_package_id: &PackageId,
+        package_id: &PackageId,
+    ) -> HashSet<PackageId> {
+        let subtree_package_ids = self.subtree_packages(package_id);
+        if package_id == root_package_id {
+            return subtree_package_ids;
+        }
+        let other_package_ids = self.reachable_packages(
+            root_package_id,
+            &HashSet::from([package_id.clone()]),
+        );
+        subtree_package_ids
+            .difference(&other_package_ids)
+            .cloned()
+            .collect()
+    }
+
+    /// The packages reachable from `package_id` without going through any of
+    /// `skipped_package_ids`
+    fn reachable_packages(
+        &self,
+        package_id: &PackageId,
+        skipped_package_ids: &HashSet<PackageId>,
+    ) -> HashSet<PackageId> {
+        let mut reachable_package_ids = HashSet::new();
+        let mut pending_indices = self
+            .nodes
+            .get(package_id)
+            .into_iter()
+            .copied()
+            .collect::<Vec<_>>();
+        while let Some(index) = pending_indices.pop() {
+            if !reachable_package_ids.insert(self.graph[index].clone()) {
+                continue;
+            }
+            pending_indices.extend(self.graph.neighbors(index).filter(
+                |neighbor_index| {
+                    !skipped_package_ids.contains(&self.graph[*neighbor_index])
+                },
+            ));
+        }
+        reachable_package_ids
+    }
+
+    /// A copy of the graph without `excluded_package_ids`. The dependencies
+    /// of an excluded package become dependencies of its dependents, with the
+    /// kind and conditions of the dependency on the excluded package.
+    pub fn exclude_packages(
+        &self,
+        excluded_package_ids: &HashSet<PackageId>,
+        root_package_id: &PackageId,
+    ) -> Graph {
+        let mut graph = self.graph.clone();
+        for excluded_package_id in excluded_package_ids {
+            let excluded_index = match self.nodes.get(excluded_package_id) {
+                Some(index) if excluded_package_id != root_package_id => *index,
+                _ => continue,
+            };
+            let dependents = graph
+                .edges_directed(excluded_index, EdgeDirection::Incoming)
+                .map(|edge| (edge.source(), edge.weight().clone()))
+                .collect::<Vec<(NodeIndex, DependencyEdge)>>();
+            let dependencies = graph
+                .neighbors_directed(excluded_index, EdgeDirection::Outgoing)
+                .collect::<Vec<NodeIndex>>();
+            for (dependent_index, dependency_edge) in dependents {
+                for dependency_index in &dependencies {
+                    let edge_exists = graph
+                        .edges_connecting(dependent_index, *dependency_index)
+                        .any(|edge| *edge.weight() == dependency_edge);
+                    if dependent_index != excluded_index
+                        && *dependency_index != excluded_index
+                        && !edge_exists
+                    {
+                        graph.add_edge(
+                            dependent_index,
+                            *dependency_index,
+                            dependency_edge.clone(),
+                        );
+                    }
+                }
+            }
+        }
+        let retained_package_ids = graph
+            .node_weights()
+            .filter(|package_id| !excluded_package_ids.contains(package_id))
+            .cloned()
+            .collect();
+        Graph {
+            graph,
+            nodes: self.nodes.clone(),
+        }
+        .retain_packages(&retained_package_ids, root_package_id)
+    }
+}
+
+/// Function to build a graph of packages dependencies, following the
+/// dependencies resolved by cargo
 pub fn build_graph<'a>(
     args: &Args,
     cargo_metadata_parameters: &'a CargoMetadataParameters,
@@ -37,110 +366,354 @@ pub fn build_graph<'a>(
         &args.deps_args,
         &args.target_args,
     );
-    let cfgs = get_cfgs(global_rustc_path, &args.target_args.target)?;
-
-    let mut graph = Graph {
-        graph: petgraph::Graph::new(),
-        nodes: HashMap::new(),
+    let cfgs = get_cfgs(global_rustc_path, &args.target_args.target())?;
+    // Build dependencies and proc-macros are compiled for the host, which
+    // only differs from the target when one is given
+    let host_cfgs = match args.target_args.target() {
+        Some(_) => get_cfgs(global_rustc_path, &None)?,
+        None => cfgs.clone(),
     };
-    graph.nodes.insert(
-        root_package_id.clone(),
-        graph.graph.add_node(root_package_id.clone()),
-    );
-
-    let mut pending_packages = vec![root_package_id.clone()];
 
     let graph_configuration = GraphConfiguration {
         target,
         cfgs: cfgs.as_deref(),
+        host: config_host,
+        host_cfgs: host_cfgs.as_deref(),
         extra_deps,
     };
 
-    while let Some(package_id) = pending_packages.pop() {
-        let is_root_package = package_id == root_package_id;
-        add_package_dependencies_to_graph(
-            cargo_metadata_parameters,
-            package_id,
-            &graph_configuration,
-            &mut graph,
-            &mut pending_packages,
-            is_root_package,
+    let mut graph = if args.no_deps {
+        // With `--no-deps` the graph only contains the root package.
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+        };
+        graph.nodes.insert(
+            root_package_id.clone(),
+            graph.graph.add_node(root_package_id.clone()),
         );
+        graph
+    } else {
+        build_resolved_graph(
+            cargo_metadata_parameters.metadata,
+            &graph_configuration,
+            &root_package_id,
+        )?
+    };
+
+    let pruned_package_ids =
+        resolve_package_specs(cargo_metadata_parameters.krates, &args.prune);
+    let excluded_package_ids =
+        resolve_package_specs(cargo_metadata_parameters.krates, &args.exclude);
+    if !pruned_package_ids.is_empty() {
+        graph = graph.prune_packages(&pruned_package_ids, &root_package_id);
+    }
+    if !excluded_package_ids.is_empty() {
+        graph = graph.exclude_packages(&excluded_package_ids, &root_package_id);
     }
 
     Ok(graph)
 }
 
+/// The packages matching any of `package_specs`, in the `cargo pkgid` format
+fn resolve_package_specs(
+    krates: &Krates,
+    package_specs: &[String],
+) -> HashSet<PackageId> {
+    let mut package_ids = HashSet::new();
+    for package_spec in package_specs {
+        let matching_package_ids = krates.query_resolve_all(package_spec);
+        if matching_package_ids.is_empty() {
+            eprintln!(
+                "WARNING: No package matches the specification: {}",
+                package_spec
+            );
+        }
+        package_ids.extend(matching_package_ids);
+    }
+    package_ids
+}
+
 struct GraphConfiguration<'a> {
+    /// `None` when building for all targets
     target: Option<&'a str>,
     cfgs: Option<&'a [Cfg]>,
+    host: &'a str,
+    host_cfgs: Option<&'a [Cfg]>,
     extra_deps: ExtraDeps,
 }
 
+impl GraphConfiguration<'_> {
+    /// Whether a dependency limited to `platform` is built, on the host when
+    /// `for_host` is set
+    fn matches_platform(
+        &self,
+        platform: Option<&Platform>,
+        for_host: bool,
+    ) -> bool {
+        match (platform, self.target) {
+            (Some(platform), Some(target)) => {
+                let (name, cfgs) = if for_host {
+                    (self.host, self.host_cfgs)
+                } else {
+                    (target, self.cfgs)
+                };
+                cfgs.map(|cfgs| platform.matches(name, cfgs))
+                    .unwrap_or(false)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Walks the resolved dependencies of `metadata` from `root_package_id`.
+/// Dependencies of build scripts and proc-macros are filtered by the host
+/// platform, the others by the target platform.
+fn build_resolved_graph(
+    metadata: &Metadata,
+    graph_configuration: &GraphConfiguration,
+    root_package_id: &PackageId,
+) -> CargoResult<Graph> {
+    let resolve = metadata.resolve.as_ref().ok_or_else(|| {
+        anyhow::anyhow!("cargo metadata did not resolve the dependencies")
+    })?;
+    let resolved_nodes = resolve
+        .nodes
+        .iter()
+        .map(|node| (&node.id, node))
+        .collect::<HashMap<&PackageId, &Node>>();
+    let packages = metadata
+        .packages
+        .iter()
+        .map(|package| (&package.id, package))
+        .collect::<HashMap<&PackageId, &Package>>();
+
+    let mut graph = Graph {
+        graph: petgraph::Graph::new(),
+        nodes: HashMap::new(),
+    };
+    graph.nodes.insert(
+        root_package_id.clone(),
+        graph.graph.add_node(root_package_id.clone()),
+    );
+
+    // A package may be built both for the host and for the target, with
+    // different dependencies, so each is walked once per platform
+    let mut pending_packages = vec![(root_package_id.clone(), false)];
+    let mut walked_packages = HashSet::new();
+    while let Some((package_id, for_host)) = pending_packages.pop() {
+        if !walked_packages.insert((package_id.clone(), for_host)) {
+            continue;
+        }
+        match (resolved_nodes.get(&package_id), packages.get(&package_id)) {
+            (Some(node), Some(package)) => {
+                add_package_dependencies_to_graph(
+                    &package_id == root_package_id,
+                    for_host,
+                    graph_configuration,
+                    &mut graph,
+                    (node, package),
+                    &packages,
+                    &mut pending_packages,
+                );
+            }
+            _ => {
+                eprintln!("Failed to add package dependencies to graph for Package Id: {}", package_id)
+            }
+        }
+    }
+
+    Ok(graph)
+}
+
+fn add_package_dependencies_to_graph(
+    is_root_package: bool,
+    for_host: bool,
+    graph_configuration: &GraphConfiguration,
+    graph: &mut Graph,
+    (node, package): (&Node, &Package),
+    packages: &HashMap<&PackageId, &Package>,
+    pending_packages: &mut Vec<(PackageId, bool)>,
+) {
+    let index = graph.nodes[&node.id];
+
+    for node_dependency in &node.deps {
+        for dep_kind_info in &node_dependency.dep_kinds {
+            let kind = dep_kind_info.kind;
+            // As in `cargo tree`, only dev-dependencies of the root package
+            // are followed
+            if !graph_configuration.extra_deps.allows(kind)
+                || (kind == DependencyKind::Development && !is_root_package)
+            {
+                continue;
+            }
+            let dependency_for_host = for_host || kind == DependencyKind::Build;
+            if !graph_configuration.matches_platform(
+                dep_kind_info.target.as_ref(),
+                dependency_for_host,
+            ) {
+                continue;
+            }
+
+            let features = packages
+                .get(&node_dependency.pkg)
+                .and_then(|dependency_package| {
+                    find_declared_dependency(
+                        package,
+                        node_dependency,
+                        dependency_package,
+                        dep_kind_info,
+                    )
+                })
+                .map(|dependency| {
+                    enabling_features(package, &node.features, dependency)
+                })
+                .unwrap_or_default();
+            add_graph_node_if_not_present_and_edge(
+                DependencyEdge {
+                    kind,
+                    target: dep_kind_info
+                        .target
+                        .as_ref()
+                        .map(ToString::to_string),
+                    features,
+                },
+                node_dependency.pkg.clone(),
+                graph,
+                index,
+            );
+
+            let is_proc_macro = packages
+                .get(&node_dependency.pkg)
+                .map(|dependency_package| is_proc_macro(dependency_package))
+                .unwrap_or(false);
+            pending_packages.push((
+                node_dependency.pkg.clone(),
+                dependency_for_host || is_proc_macro,
+            ));
+        }
+    }
+}
+
 fn add_graph_node_if_not_present_and_edge(
-    dependency: &Dependency,
+    dependency_edge: DependencyEdge,
     dependency_package_id: PackageId,
     graph: &mut Graph,
     index: NodeIndex,
-    pending_packages: &mut Vec<PackageId>,
 ) {
     let dependency_index =
         match graph.nodes.entry(dependency_package_id.clone()) {
             Entry::Occupied(e) => *e.get(),
             Entry::Vacant(e) => {
-                pending_packages.push(dependency_package_id.clone());
                 *e.insert(graph.graph.add_node(dependency_package_id))
             }
         };
-    graph
+    // The same dependency is met again when its dependent is walked for
+    // both the host and the target
+    if graph
         .graph
-        .add_edge(index, dependency_index, dependency.kind);
+        .edges_connecting(index, dependency_index)
+        .all(|edge| *edge.weight() != dependency_edge)
+    {
+        graph
+            .graph
+            .add_edge(index, dependency_index, dependency_edge);
+    }
 }
 
-fn add_package_dependencies_to_graph(
-    cargo_metadata_parameters: &CargoMetadataParameters,
-    package_id: PackageId,
-    graph_configuration: &GraphConfiguration,
-    graph: &mut Graph,
-    pending_packages: &mut Vec<PackageId>,
-    is_root_package: bool,
-) {
-    let index = graph.nodes[&package_id];
-
-    let krates_node_option =
-        cargo_metadata_parameters.krates.node_for_kid(&package_id);
-
-    let dep_not_replaced_option = cargo_metadata_parameters
-        .metadata
-        .deps_not_replaced(&package_id, is_root_package);
+/// The dependency declared in the manifest of `package` that was resolved to
+/// `node_dependency`, the package `dependency_package`, with the kind and
+/// target of `dep_kind_info`. The declaration is matched against the resolved
+/// package, as the name of the dependency in the crate is that of the library
+/// of the package, which can differ from the package name.
+fn find_declared_dependency<'a>(
+    package: &'a Package,
+    node_dependency: &NodeDep,
+    dependency_package: &Package,
+    dep_kind_info: &DepKindInfo,
+) -> Option<&'a Dependency> {
+    let declared_dependencies = package
+        .dependencies
+        .iter()
+        .filter(|dependency| {
+            dependency.kind == dep_kind_info.kind
+                && dependency.target == dep_kind_info.target
+                && dependency.name == dependency_package.name
+        })
+        .collect::<Vec<&Dependency>>();
+    let resolved_dependencies = declared_dependencies
+        .iter()
+        .filter(|dependency| is_resolved_to(dependency, dependency_package))
+        .collect::<Vec<_>>();
+    // The same package can be declared more than once under different names,
+    // and a patched dependency is resolved to another version or source.
+    resolved_dependencies
+        .iter()
+        .find(|dependency| {
+            matches!(
+                &dependency.rename,
+                Some(rename) if rename.replace('-', "_") == node_dependency.name
+            )
+        })
+        .or_else(|| resolved_dependencies.first())
+        .copied()
+        .or_else(|| declared_dependencies.first())
+        .copied()
+}
 
-    match (krates_node_option, dep_not_replaced_option) {
-        (Some(krates_node), Some(dependencies)) => {
-            let package = krates_node.krate.clone();
+/// Whether `dependency` can be resolved to `package`, by its version
+/// requirement and source
+fn is_resolved_to(dependency: &Dependency, package: &Package) -> bool {
+    // The source of a git package also has the commit it was resolved to.
+    let package_source = package
+        .source
+        .as_ref()
+        .map(|source| source.repr.split('#').next().unwrap_or_default());
+    dependency.req.matches(&package.version)
+        && dependency.source.as_deref() == package_source
+}
 
-            for (dependency_package_id, _) in dependencies {
-                let dependency_iterator = filter_dependencies(
-                    cargo_metadata_parameters,
-                    &dependency_package_id,
-                    graph_configuration,
-                    &package,
-                );
+fn is_proc_macro(package: &Package) -> bool {
+    package.targets.iter().any(|target| {
+        target
+            .kind
+            .iter()
+            .any(|target_kind| target_kind == "proc-macro")
+    })
+}
 
-                for dependency in dependency_iterator {
-                    add_graph_node_if_not_present_and_edge(
-                        dependency,
-                        dependency_package_id.clone(),
-                        graph,
-                        index,
-                        pending_packages,
-                    );
-                }
-            }
-        }
-        _ => {
-            eprintln!("Failed to add package dependencies to graph for Package Id: {}", package_id)
-        }
+/// The enabled features of `package` that activate the optional
+/// `dependency`, empty if the dependency isn't optional
+fn enabling_features(
+    package: &Package,
+    enabled_features: &[String],
+    dependency: &Dependency,
+) -> Vec<String> {
+    if !dependency.optional {
+        return vec![];
     }
+    let dependency_name =
+        dependency.rename.as_ref().unwrap_or(&dependency.name);
+    let activates_dependency = |feature_value: &String| {
+        feature_value == dependency_name
+            || *feature_value == format!("dep:{}", dependency_name)
+            || feature_value.starts_with(&format!("{}/", dependency_name))
+    };
+
+    enabled_features
+        .iter()
+        .filter(|feature| {
+            *feature == dependency_name
+                || package
+                    .features
+                    .get(*feature)
+                    .map(|feature_values| {
+                        feature_values.iter().any(activates_dependency)
+                    })
+                    .unwrap_or(false)
+        })
+        .cloned()
+        .collect()
 }
 
 fn build_graph_prerequisites<'a>(
@@ -161,49 +734,428 @@ fn build_graph_prerequisites<'a>(
     let target = if target_args.all_targets {
         None
     } else {
-        Some(target_args.target.as_deref().unwrap_or(config_host))
+        Some(
+            target_args
+                .targets
+                .first()
+                .map_or(config_host, String::as_str),
+        )
     };
 
     (extra_deps, target)
 }
 
-fn filter_dependencies<'a>(
-    cargo_metadata_parameters: &'a CargoMetadataParameters,
-    dependency_package_id: &'a PackageId,
-    graph_configuration: &'a GraphConfiguration,
-    package: &'a Package,
-) -> Vec<&'a Dependency> {
-    package
-        .dependencies
-        .iter()
-        .filter(|d| {
-            d.matches_ignoring_source(
-                cargo_metadata_parameters.krates,
-                dependency_package_id,
-            )
-            .unwrap_or(false)
-        })
-        .filter(|d| graph_configuration.extra_deps.allows(d.kind))
-        .filter(|d| {
-            d.target
-                .as_ref()
-                .and_then(|p| {
-                    graph_configuration.target.map(
-                        |t| match graph_configuration.cfgs {
-                            None => false,
-                            Some(cfgs) => p.matches(t, cfgs),
-                        },
-                    )
-                })
-                .unwrap_or(true)
-        })
-        .collect::<Vec<&Dependency>>()
-}
-
 #[cfg(test)]
 mod graph_tests {
     use super::*;
+
+    use crate::cli::resolve_package_query;
+
+    use krates::Builder as KratesBuilder;
     use rstest::*;
+    use std::str::FromStr;
+
+    #[rstest(
+        input_optional,
+        input_rename,
+        input_enabled_features,
+        expected_enabling_features,
+        case(false, None, vec!["std"], vec![]),
+        case(true, None, vec!["std"], vec!["std"]),
+        case(true, None, vec!["serde"], vec!["serde"]),
+        case(true, None, vec!["derive", "default"], vec!["derive"]),
+        case(true, Some("serde1"), vec!["std", "serde1"], vec!["serde1"]),
+        case(true, None, vec!["default"], vec![])
+    )]
+    fn enabling_features_test(
+        input_optional: bool,
+        input_rename: Option<&str>,
+        input_enabled_features: Vec<&str>,
+        expected_enabling_features: Vec<&str>,
+    ) {
+        let package: Package = serde_json::from_value(serde_json::json!({
+            "name": "package",
+            "version": "0.1.0",
+            "id": "package 0.1.0 (path+file:///package)",
+            "source": null,
+            "dependencies": [],
+            "targets": [],
+            "features": {
+                "default": [],
+                "std": ["dep:serde"],
+                "derive": ["serde/derive"],
+            },
+            "manifest_path": "/package/Cargo.toml",
+        }))
+        .unwrap();
+        let dependency: Dependency =
+            serde_json::from_value(serde_json::json!({
+                "name": "serde",
+                "source": null,
+                "req": "^1",
+                "kind": null,
+                "optional": input_optional,
+                "uses_default_features": true,
+                "features": [],
+                "target": null,
+                "rename": input_rename,
+                "registry": null,
+            }))
+            .unwrap();
+        let enabled_features = input_enabled_features
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            enabling_features(&package, &enabled_features, &dependency),
+            expected_enabling_features
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[rstest]
+    fn retain_packages_test() {
+        let package_ids = ["root", "a", "b", "c"]
+            .iter()
+            .map(|repr| PackageId {
+                repr: String::from(*repr),
+            })
+            .collect::<Vec<PackageId>>();
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+        };
+        for package_id in &package_ids {
+            graph.nodes.insert(
+                package_id.clone(),
+                graph.graph.add_node(package_id.clone()),
+            );
+        }
+        for (from, to) in [(0, 1), (0, 2), (2, 3)] {
+            graph.graph.add_edge(
+                graph.nodes[&package_ids[from]],
+                graph.nodes[&package_ids[to]],
+                DependencyKind::Normal.into(),
+            );
+        }
+
+        let retained = graph.retain_packages(
+            &vec![package_ids[2].clone(), package_ids[3].clone()]
+                .into_iter()
+                .collect(),
+            &package_ids[0],
+        );
+
+        assert_eq!(retained.graph.node_count(), 3);
+        assert_eq!(retained.graph.edge_count(), 2);
+        assert!(!retained.nodes.contains_key(&package_ids[1]));
+        for package_id in [&package_ids[0], &package_ids[2], &package_ids[3]] {
+            assert_eq!(&retained.graph[retained.nodes[package_id]], package_id);
+        }
+    }
+
+    #[rstest(
+        input_package_index,
+        expected_paths,
+        case(0, vec![vec![0]]),
+        case(1, vec![vec![0, 1], vec![0, 2, 3, 4, 1]]),
+        case(3, vec![vec![0, 1, 3], vec![0, 2, 3]]),
+        case(4, vec![vec![0, 1, 3, 4]]),
+        case(5, vec![])
+    )]
+    fn dependency_paths_test(
+        input_package_index: usize,
+        expected_paths: Vec<Vec<usize>>,
+    ) {
+        let (mut graph, package_ids) = create_graph(
+            6,
+            &[
+                (0, 1, DependencyKind::Normal),
+                (0, 2, DependencyKind::Normal),
+                (1, 3, DependencyKind::Normal),
+                (2, 3, DependencyKind::Build),
+                (3, 4, DependencyKind::Normal),
+                (4, 1, DependencyKind::Normal),
+            ],
+        );
+        graph.nodes.remove(&package_ids[5]);
+
+        let paths = graph.dependency_paths(
+            &package_ids[0],
+            &package_ids[input_package_index],
+        );
+
+        assert_eq!(
+            paths,
+            expected_paths
+                .into_iter()
+                .map(|path| path
+                    .into_iter()
+                    .map(|index| package_ids[index].clone())
+                    .collect::<Vec<PackageId>>())
+                .collect::<Vec<Vec<PackageId>>>()
+        );
+    }
+
+    #[rstest(
+        input_package_index,
+        input_max_paths,
+        expected_paths,
+        case(0, 10, vec![vec![0]]),
+        case(3, 10, vec![vec![0, 1, 3], vec![0, 2, 3], vec![0, 2, 4, 3]]),
+        case(5, 10, vec![])
+    )]
+    fn all_dependency_paths_test(
+        input_package_index: usize,
+        input_max_paths: usize,
+        expected_paths: Vec<Vec<usize>>,
+    ) {
+        let (graph, package_ids) = create_graph(
+            6,
+            &[
+                (0, 1, DependencyKind::Normal),
+                (0, 2, DependencyKind::Normal),
+                (1, 3, DependencyKind::Normal),
+                (1, 3, DependencyKind::Build),
+                (2, 3, DependencyKind::Normal),
+                (2, 4, DependencyKind::Normal),
+                (4, 3, DependencyKind::Normal),
+                (3, 1, DependencyKind::Normal),
+            ],
+        );
+
+        let paths = graph.all_dependency_paths(
+            &package_ids[0],
+            &package_ids[input_package_index],
+            input_max_paths,
+        );
+
+        assert_eq!(
+            paths,
+            expected_paths
+                .into_iter()
+                .map(|path| path
+                    .into_iter()
+                    .map(|index| package_ids[index].clone())
+                    .collect::<Vec<PackageId>>())
+                .collect::<Vec<Vec<PackageId>>>()
+        );
+    }
+
+    #[rstest]
+    fn all_dependency_paths_test_stops_after_max_paths() {
+        let (graph, package_ids) = create_graph(
+            4,
+            &[
+                (0, 3, DependencyKind::Normal),
+                (0, 1, DependencyKind::Normal),
+                (0, 2, DependencyKind::Normal),
+                (1, 3, DependencyKind::Normal),
+                (2, 3, DependencyKind::Normal),
+            ],
+        );
+
+        assert_eq!(
+            graph.all_dependency_paths(&package_ids[0], &package_ids[3], 2),
+            vec![
+                vec![package_ids[0].clone(), package_ids[3].clone()],
+                vec![
+                    package_ids[0].clone(),
+                    package_ids[1].clone(),
+                    package_ids[3].clone()
+                ],
+            ]
+        );
+    }
+
+    #[rstest]
+    fn all_dependency_paths_test_wide_diamond() {
+        // 20 diamonds in a row, 2^20 paths from the top of the first to the
+        // bottom of the last, next to many packages not leading to it.
+        const DIAMOND_COUNT: usize = 20;
+        const OTHER_PACKAGE_COUNT: usize = 1000;
+        let target_index = 3 * DIAMOND_COUNT;
+        let mut edges = vec![];
+        for diamond in 0..DIAMOND_COUNT {
+            let top = 3 * diamond;
+            edges.push((top, top + 1, DependencyKind::Normal));
+            edges.push((top, top + 2, DependencyKind::Normal));
+            edges.push((top + 1, top + 3, DependencyKind::Normal));
+            edges.push((top + 2, top + 3, DependencyKind::Normal));
+        }
+        for other_index in 1..=OTHER_PACKAGE_COUNT {
+            edges.push((0, target_index + other_index, DependencyKind::Normal));
+        }
+        let (graph, package_ids) =
+            create_graph(target_index + OTHER_PACKAGE_COUNT + 1, &edges);
+
+        let paths = graph.all_dependency_paths(
+            &package_ids[0],
+            &package_ids[target_index],
+            3,
+        );
+
+        assert_eq!(paths.len(), 3);
+        assert!(paths.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(paths.iter().all(|path| path.len() == 2 * DIAMOND_COUNT + 1
+            && path.first() == Some(&package_ids[0])
+            && path.last() == Some(&package_ids[target_index])));
+    }
+
+    #[rstest]
+    fn prune_packages_test() {
+        let (graph, package_ids) = create_graph(
+            5,
+            &[
+                (0, 1, DependencyKind::Normal),
+                (0, 2, DependencyKind::Normal),
+                (1, 3, DependencyKind::Normal),
+                (2, 3, DependencyKind::Normal),
+                (1, 4, DependencyKind::Normal),
+            ],
+        );
+
+        let pruned = graph.prune_packages(
+            &vec![package_ids[1].clone()].into_iter().collect(),
+            &package_ids[0],
+        );
+
+        assert_eq!(pruned.graph.node_count(), 3);
+        assert_eq!(pruned.graph.edge_count(), 2);
+        assert!(!pruned.nodes.contains_key(&package_ids[1]));
+        assert!(pruned.nodes.contains_key(&package_ids[3]));
+        assert!(!pruned.nodes.contains_key(&package_ids[4]));
+    }
+
+    #[rstest(
+        input_package,
+        expected_subtree,
+        expected_exclusive_subtree,
+        case(0, vec![0, 1, 2, 3, 4], vec![0, 1, 2, 3, 4]),
+        case(1, vec![1, 3, 4], vec![1, 4]),
+        case(2, vec![2, 3], vec![2]),
+        case(3, vec![3], vec![3])
+    )]
+    fn subtree_packages_test(
+        input_package: usize,
+        expected_subtree: Vec<usize>,
+        expected_exclusive_subtree: Vec<usize>,
+    ) {
+        // 0 -> 1 -> 3, 0 -> 2 -> 3, 1 -> 4
+        let (graph, package_ids) = create_graph(
+            5,
+            &[
+                (0, 1, DependencyKind::Normal),
+                (0, 2, DependencyKind::Normal),
+                (1, 3, DependencyKind::Normal),
+                (2, 3, DependencyKind::Normal),
+                (1, 4, DependencyKind::Build),
+            ],
+        );
+        let to_package_ids = |indices: Vec<usize>| {
+            indices
+                .into_iter()
+                .map(|index| package_ids[index].clone())
+                .collect::<HashSet<PackageId>>()
+        };
+
+        assert_eq!(
+            graph.subtree_packages(&package_ids[input_package]),
+            to_package_ids(expected_subtree)
+        );
+        assert_eq!(
+            graph.exclusive_subtree_packages(
+                &package_ids[0],
+                &package_ids[input_package]
+            ),
+            to_package_ids(expected_exclusive_subtree)
+        );
+    }
+
+    #[rstest(
+        input_edges,
+        input_excluded,
+        expected_edges,
+        case(
+            vec![
+                (0, 1, DependencyKind::Normal),
+                (1, 2, DependencyKind::Normal),
+                (1, 3, DependencyKind::Normal),
+                (0, 3, DependencyKind::Development),
+            ],
+            vec![1],
+            vec![
+                (0, 2, DependencyKind::Normal),
+                (0, 3, DependencyKind::Development),
+                (0, 3, DependencyKind::Normal),
+            ]
+        ),
+        case(
+            vec![
+                (0, 1, DependencyKind::Build),
+                (1, 2, DependencyKind::Normal),
+                (2, 3, DependencyKind::Normal),
+            ],
+            vec![1, 2],
+            vec![(0, 3, DependencyKind::Build)]
+        ),
+        case(
+            vec![(0, 1, DependencyKind::Normal)],
+            vec![0],
+            vec![(0, 1, DependencyKind::Normal)]
+        )
+    )]
+    fn exclude_packages_test(
+        input_edges: Vec<(usize, usize, DependencyKind)>,
+        input_excluded: Vec<usize>,
+        expected_edges: Vec<(usize, usize, DependencyKind)>,
+    ) {
+        let (graph, package_ids) = create_graph(4, &input_edges);
+
+        let excluded = graph.exclude_packages(
+            &input_excluded
+                .iter()
+                .map(|index| package_ids[*index].clone())
+                .collect(),
+            &package_ids[0],
+        );
+
+        let mut edges = excluded
+            .graph
+            .edge_references()
+            .map(|edge| {
+                (
+                    excluded.graph[edge.source()].clone(),
+                    excluded.graph[edge.target()].clone(),
+                    edge.weight().kind,
+                )
+            })
+            .collect::<Vec<(PackageId, PackageId, DependencyKind)>>();
+        edges.sort_by_key(|(source, target, kind)| {
+            (source.clone(), target.clone(), format!("{:?}", kind))
+        });
+        let expected_edges = expected_edges
+            .into_iter()
+            .map(|(source, target, kind)| {
+                (
+                    package_ids[source].clone(),
+                    package_ids[target].clone(),
+                    kind,
+                )
+            })
+            .collect::<Vec<(PackageId, PackageId, DependencyKind)>>();
+        assert_eq!(edges, expected_edges);
+        for package_id in &input_excluded {
+            if *package_id != 0 {
+                assert!(!excluded
+                    .nodes
+                    .contains_key(&package_ids[*package_id]));
+            }
+        }
+    }
 
     #[rstest(
         input_deps_args,
@@ -263,20 +1215,20 @@ mod graph_tests {
         case(
             TargetArgs {
                 all_targets: true,
-                target: None
+                targets: vec![]
             },
             None
         ),
         case(
             TargetArgs {
                 all_targets: false,
-                target: None
+                targets: vec![]
             },
             Some("default_config_host")),
         case(
             TargetArgs {
                 all_targets: false,
-                target: Some(String::from("provided_config_host")),
+                targets: vec![String::from("provided_config_host")],
             },
             Some("provided_config_host")
         )
@@ -296,4 +1248,355 @@ mod graph_tests {
 
         assert_eq!(target, expected_target);
     }
+
+    #[rstest(
+        input_extra_deps,
+        input_target,
+        expected_edges,
+        case(
+            ExtraDeps::NoMore,
+            Some("x86_64-pc-windows-msvc"),
+            vec![
+                ("derive", "libc", "normal", Some("cfg(unix)")),
+                ("root", "derive", "normal", None),
+                ("root", "serde", "normal", Some("derive")),
+                ("root", "winapi", "normal", Some("cfg(windows)")),
+            ]
+        ),
+        case(
+            ExtraDeps::All,
+            Some("x86_64-pc-windows-msvc"),
+            vec![
+                ("cc", "libc", "normal", Some("cfg(unix)")),
+                ("derive", "libc", "normal", Some("cfg(unix)")),
+                ("root", "cc", "build", None),
+                ("root", "derive", "normal", None),
+                ("root", "serde", "normal", Some("derive")),
+                ("root", "tempfile", "dev", None),
+                ("root", "winapi", "normal", Some("cfg(windows)")),
+            ]
+        ),
+        case(
+            ExtraDeps::NoMore,
+            None,
+            vec![
+                ("derive", "libc", "normal", Some("cfg(unix)")),
+                ("root", "derive", "normal", None),
+                ("root", "libc", "normal", Some("cfg(unix)")),
+                ("root", "serde", "normal", Some("derive")),
+                ("root", "winapi", "normal", Some("cfg(windows)")),
+            ]
+        )
+    )]
+    fn build_resolved_graph_test(
+        input_extra_deps: ExtraDeps,
+        input_target: Option<&str>,
+        expected_edges: Vec<(&str, &str, &str, Option<&str>)>,
+    ) {
+        // Dependencies of the build dependency `cc` and the proc-macro
+        // `derive` on `libc` are only built for the unix host, that of `root`
+        // only for the unix target. `tempfile` is a dev-dependency of both
+        // `root` and `cc`.
+        let metadata = create_resolved_metadata(&[
+            (
+                "root",
+                "lib",
+                &[
+                    ("cc", Some("build"), None, false),
+                    ("derive", None, None, false),
+                    ("libc", None, Some("cfg(unix)"), false),
+                    ("serde", None, None, true),
+                    ("tempfile", Some("dev"), None, false),
+                    ("winapi", None, Some("cfg(windows)"), false),
+                ],
+            ),
+            (
+                "cc",
+                "lib",
+                &[
+                    ("libc", None, Some("cfg(unix)"), false),
+                    ("tempfile", Some("dev"), None, false),
+                ],
+            ),
+            (
+                "derive",
+                "proc-macro",
+                &[("libc", None, Some("cfg(unix)"), false)],
+            ),
+            ("libc", "lib", &[]),
+            ("serde", "lib", &[]),
+            ("tempfile", "lib", &[]),
+            ("winapi", "lib", &[]),
+        ]);
+        let cfgs = [Cfg::from_str("windows").unwrap()];
+        let host_cfgs = [Cfg::from_str("unix").unwrap()];
+        let graph_configuration = GraphConfiguration {
+            target: input_target,
+            cfgs: Some(&cfgs),
+            host: "x86_64-unknown-linux-gnu",
+            host_cfgs: Some(&host_cfgs),
+            extra_deps: input_extra_deps,
+        };
+
+        let graph = build_resolved_graph(
+            &metadata,
+            &graph_configuration,
+            &metadata.packages[0].id,
+        )
+        .unwrap();
+
+        let name = |index: NodeIndex| {
+            graph.graph[index]
+                .repr
+                .split(' ')
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        let mut edges = graph
+            .graph
+            .edge_references()
+            .map(|edge| {
+                (
+                    name(edge.source()),
+                    name(edge.target()),
+                    edge.weight().kind.to_string(),
+                    edge.weight()
+                        .target
+                        .clone()
+                        .or_else(|| edge.weight().features.first().cloned()),
+                )
+            })
+            .collect::<Vec<_>>();
+        edges.sort();
+        assert_eq!(
+            edges,
+            expected_edges
+                .into_iter()
+                .map(|(from, to, kind, condition)| (
+                    from.to_string(),
+                    to.to_string(),
+                    kind.to_string(),
+                    condition.map(String::from)
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn build_resolved_graph_test_custom_lib_name() {
+        // The library of `serde` is named `serde_core`, the name of the
+        // dependency in `root`.
+        let mut metadata = create_resolved_metadata(&[
+            ("root", "lib", &[("serde", None, None, true)]),
+            ("serde", "lib", &[]),
+        ]);
+        metadata.resolve.as_mut().unwrap().nodes[0].deps[0].name =
+            String::from("serde_core");
+        let graph_configuration = GraphConfiguration {
+            target: None,
+            cfgs: None,
+            host: "x86_64-unknown-linux-gnu",
+            host_cfgs: None,
+            extra_deps: ExtraDeps::NoMore,
+        };
+
+        let graph = build_resolved_graph(
+            &metadata,
+            &graph_configuration,
+            &metadata.packages[0].id,
+        )
+        .unwrap();
+
+        let features = graph
+            .graph
+            .edge_references()
+            .map(|edge| edge.weight().features.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(features, vec![vec![String::from("derive")]]);
+    }
+
+    #[rstest(
+        input_no_deps,
+        input_prune,
+        input_exclude,
+        expected_package_names,
+        case(false, vec![], vec![], vec!["dep", "leaf"]),
+        case(true, vec![], vec![], vec!["dep"]),
+        case(false, vec!["dep"], vec![], vec!["dep", "leaf"]),
+        case(false, vec![], vec!["dep"], vec!["dep", "leaf"])
+    )]
+    fn build_graph_test_package_root(
+        input_no_deps: bool,
+        input_prune: Vec<&str>,
+        input_exclude: Vec<&str>,
+        expected_package_names: Vec<&str>,
+    ) {
+        // As with `-p dep`, the root of the graph is a dependency of the
+        // root of the metadata, and is kept by `--no-deps`, `--prune` and
+        // `--exclude`.
+        let metadata = create_resolved_metadata(&[
+            ("root", "lib", &[("dep", None, None, false)]),
+            ("dep", "lib", &[("leaf", None, None, false)]),
+            ("leaf", "lib", &[]),
+        ]);
+        let krates = KratesBuilder::new()
+            .build_with_metadata(metadata.clone(), |_| ())
+            .unwrap();
+        let cargo_metadata_parameters = CargoMetadataParameters {
+            metadata: &metadata,
+            krates: &krates,
+        };
+        let args = Args {
+            exclude: input_exclude.into_iter().map(String::from).collect(),
+            no_deps: input_no_deps,
+            package: Some(String::from("dep")),
+            prune: input_prune.into_iter().map(String::from).collect(),
+            ..Default::default()
+        };
+        let root_package_id = resolve_package_query(
+            &args,
+            &krates,
+            metadata.packages[0].id.clone(),
+        );
+
+        let graph = build_graph(
+            &args,
+            &cargo_metadata_parameters,
+            "x86_64-unknown-linux-gnu",
+            &PathBuf::from("/nonexistent/rustc"),
+            root_package_id.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(root_package_id, metadata.packages[1].id);
+        let mut package_names = graph
+            .nodes
+            .keys()
+            .map(|package_id| package_id.repr.split(' ').next().unwrap())
+            .collect::<Vec<_>>();
+        package_names.sort_unstable();
+        assert_eq!(package_names, expected_package_names);
+    }
+
+    fn create_graph(
+        package_count: usize,
+        edges: &[(usize, usize, DependencyKind)],
+    ) -> (Graph, Vec<PackageId>) {
+        let package_ids = (0..package_count)
+            .map(|index| PackageId {
+                repr: format!("package_{}", index),
+            })
+            .collect::<Vec<PackageId>>();
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+        };
+        for package_id in &package_ids {
+            graph.nodes.insert(
+                package_id.clone(),
+                graph.graph.add_node(package_id.clone()),
+            );
+        }
+        for (from, to, dependency_kind) in edges {
+            graph.graph.add_edge(
+                graph.nodes[&package_ids[*from]],
+                graph.nodes[&package_ids[*to]],
+                (*dependency_kind).into(),
+            );
+        }
+        (graph, package_ids)
+    }
+
+    /// Name, target kind and the name, kind, target and optionality of each
+    /// dependency
+    type ResolvedPackage<'a> = (
+        &'a str,
+        &'a str,
+        &'a [(&'a str, Option<&'a str>, Option<&'a str>, bool)],
+    );
+
+    /// Metadata of packages at version 0.1.0 all resolved, with the
+    /// `derive` feature of the first package enabling its optional
+    /// dependencies
+    fn create_resolved_metadata(packages: &[ResolvedPackage]) -> Metadata {
+        let package_id = |name: &str| {
+            format!(
+                "{} 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                name
+            )
+        };
+        let json_packages = packages
+            .iter()
+            .map(|(name, target_kind, dependencies)| {
+                serde_json::json!({
+                    "name": name,
+                    "version": "0.1.0",
+                    "id": package_id(name),
+                    "source": "registry+https://github.com/rust-lang/crates.io-index",
+                    "dependencies": dependencies
+                        .iter()
+                        .map(|(dependency_name, kind, target, optional)| serde_json::json!({
+                            "name": dependency_name,
+                            "source": "registry+https://github.com/rust-lang/crates.io-index",
+                            "req": "^0.1",
+                            "kind": kind,
+                            "optional": optional,
+                            "uses_default_features": true,
+                            "features": [],
+                            "target": target,
+                            "rename": null,
+                            "registry": null,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "targets": [{
+                        "name": name,
+                        "kind": [target_kind],
+                        "crate_types": [target_kind],
+                        "src_path": format!("/{}/src/lib.rs", name),
+                    }],
+                    "features": {
+                        "derive": dependencies
+                            .iter()
+                            .filter(|(_, _, _, optional)| *optional)
+                            .map(|(dependency_name, _, _, _)| format!("dep:{}", dependency_name))
+                            .collect::<Vec<_>>(),
+                    },
+                    "manifest_path": format!("/{}/Cargo.toml", name),
+                })
+            })
+            .collect::<Vec<_>>();
+        let nodes = packages
+            .iter()
+            .map(|(name, _, dependencies)| {
+                serde_json::json!({
+                    "id": package_id(name),
+                    "dependencies": dependencies
+                        .iter()
+                        .map(|(dependency_name, _, _, _)| package_id(dependency_name))
+                        .collect::<Vec<_>>(),
+                    "deps": dependencies
+                        .iter()
+                        .map(|(dependency_name, kind, target, _)| serde_json::json!({
+                            "name": dependency_name,
+                            "pkg": package_id(dependency_name),
+                            "dep_kinds": [{ "kind": kind, "target": target }],
+                        }))
+                        .collect::<Vec<_>>(),
+                    "features": ["derive"],
+                })
+            })
+            .collect::<Vec<_>>();
+        let root_package_id = package_id(packages[0].0);
+
+        serde_json::from_value(serde_json::json!({
+            "packages": json_packages,
+            "workspace_members": [root_package_id],
+            "resolve": { "nodes": nodes, "root": root_package_id },
+            "workspace_root": "/",
+            "target_directory": "/target",
+            "version": 1,
+        }))
+        .unwrap()
+    }
 }
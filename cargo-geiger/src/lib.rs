@@ -11,14 +11,20 @@
 pub mod args;
 /// Bootstrapping functions for structs required by the CLI
 pub mod cli;
+/// Comparison of scans run with different configurations
+pub mod comparison;
 /// Construction of the dependency graph
 pub mod graph;
 /// Mapping functionality from `cargo::core` to `cargo_metadata`
 pub mod mapping;
+/// Destinations for the outputs of a scan
+pub mod output;
 /// Interaction with README.md files
 pub mod readme;
 /// Functions for scanning projects for unsafe code
 pub mod scan;
+/// Temporary projects for scanning published crates
+pub mod temporary_project;
 
 /// Inner display formatting
 mod format;
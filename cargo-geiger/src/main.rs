@@ -12,20 +12,30 @@ extern crate petgraph;
 extern crate strum;
 extern crate strum_macros;
 
-use cargo_geiger::args::{Args, HELP};
-use cargo_geiger::cli::{get_cargo_metadata, get_krates, get_workspace};
+use cargo_geiger::args::{Args, Verbosity, HELP};
+use cargo_geiger::cli::{
+    get_cargo_metadata, get_krates, get_root_package_id, get_workspace,
+    resolve_package_query,
+};
+use cargo_geiger::comparison::{
+    compare_runs, feature_matrix_runs, target_runs,
+};
 use cargo_geiger::graph::build_graph;
-use cargo_geiger::mapping::{CargoMetadataParameters, QueryResolve};
+use cargo_geiger::mapping::CargoMetadataParameters;
+use cargo_geiger::output::write_scan_output_lines;
 use cargo_geiger::readme::create_or_replace_section_in_readme;
-use cargo_geiger::scan::{scan, FoundWarningsError, ScanResult};
+use cargo_geiger::scan::{
+    construct_stats_lines, scan, scan_binary, BuildFailedError,
+    FoundWarningsError, ScanOutcome, ScanResult, ScanStatsCollector,
+};
+use cargo_geiger::temporary_project::create_temporary_project;
 
 use cargo::core::shell::Shell;
-use cargo::util::important_paths;
 use cargo::{CliError, CliResult, Config};
 
 const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 
-fn cli_result_main(args: &Args) -> CliResult {
+fn cli_result_main(args: &mut Args) -> CliResult {
     if args.version {
         println!("cargo-geiger {}", VERSION.unwrap_or("unknown version"));
         return Ok(());
@@ -35,10 +45,60 @@ fn cli_result_main(args: &Args) -> CliResult {
         return Ok(());
     }
 
+    // Kept alive until the scan has finished, the project directory is
+    // removed when this is dropped.
+    let _temporary_project = match args.crate_spec.clone() {
+        Some(crate_spec) => {
+            let temporary_project =
+                create_temporary_project(&crate_spec, &args.features_args)?;
+            args.manifest_path = Some(temporary_project.manifest_path.clone());
+            args.package = Some(crate_spec.to_package_spec());
+            // The features have been applied to the dependency declaration,
+            // the temporary project itself has none.
+            args.features_args = Default::default();
+            Some(temporary_project)
+        }
+        None => None,
+    };
+
     let mut config = Config::default()?;
     args.update_config(&mut config)?;
 
-    let cargo_metadata = get_cargo_metadata(args, &config)?;
+    if let Some(binary_path) = &args.binary {
+        let scan_results = scan_binary(args, binary_path, &config)?;
+        for (output, scan_result) in args.outputs.iter().zip(&scan_results) {
+            write_scan_output_lines(
+                &output.destination,
+                &scan_result.scan_output_lines,
+            )?;
+        }
+        return Ok(());
+    }
+
+    if args.feature_matrix {
+        let runs = feature_matrix_runs(args, &config)?;
+        let ScanResult {
+            scan_output_lines, ..
+        } = compare_runs(&config, &runs, "Feature matrix")?;
+        for scan_output_line in scan_output_lines {
+            println!("{}", scan_output_line);
+        }
+        return Ok(());
+    }
+
+    if args.target_args.targets.len() > 1 {
+        let ScanResult {
+            scan_output_lines, ..
+        } = compare_runs(&config, &target_runs(args), "Target comparison")?;
+        for scan_output_line in scan_output_lines {
+            println!("{}", scan_output_line);
+        }
+        return Ok(());
+    }
+
+    let stats = ScanStatsCollector::default();
+    let cargo_metadata =
+        stats.time_phase("metadata", || get_cargo_metadata(args, &config))?;
     let krates = get_krates(&cargo_metadata)?;
 
     let cargo_metadata_parameters = CargoMetadataParameters {
@@ -48,65 +108,69 @@ fn cli_result_main(args: &Args) -> CliResult {
 
     let workspace = get_workspace(&config, args.manifest_path.clone())?;
 
-    let cargo_metadata_root_package_id = if let Some(
-        cargo_metadata_root_package,
-    ) = cargo_metadata.root_package()
-    {
-        cargo_metadata_root_package.id.clone()
-    } else {
-        eprintln!(
-            "manifest path `{}` is a virtual manifest, but this command requires running against an actual package in this workspace",
-            match args.manifest_path.clone() {
-                Some(path) => path,
-                None => important_paths::find_root_manifest_for_wd(config.cwd())?,
-            }.as_os_str().to_str().unwrap()
-        );
-
-        return Err(CliError::code(1));
-    };
+    let cargo_metadata_root_package_id =
+        get_root_package_id(args, &cargo_metadata, &config)?;
 
     let global_rustc = config.load_global_rustc(Some(&workspace))?;
 
+    // The package selected with `--package` is the root kept by `--no-deps`,
+    // `--prune` and `--exclude`.
+    let query_resolve_root_package_id =
+        resolve_package_query(args, &krates, cargo_metadata_root_package_id);
+
     let graph = build_graph(
         args,
         &cargo_metadata_parameters,
         &global_rustc.host,
         &global_rustc.path,
-        cargo_metadata_root_package_id.clone(),
+        query_resolve_root_package_id.clone(),
     )?;
 
-    let query_resolve_root_package_id = args.package.as_ref().map_or(
-        cargo_metadata_root_package_id.clone(),
-        |package_query| {
-            krates
-                .query_resolve(package_query)
-                .map_or(cargo_metadata_root_package_id, |package_id| package_id)
-        },
-    );
-
-    let ScanResult {
-        scan_output_lines,
-        warning_count,
+    let ScanOutcome {
+        scan_results,
+        build_failed,
     } = scan(
         args,
         &cargo_metadata_parameters,
         &config,
         &graph,
         query_resolve_root_package_id,
+        &stats,
         &workspace,
     )?;
 
-    if args.readme_args.update_readme {
-        create_or_replace_section_in_readme(
-            &args.readme_args,
-            &scan_output_lines,
+    // Printed to stderr to keep the scan output, e.g. JSON, parseable.
+    if args.verbosity != Verbosity::Quiet {
+        for stats_line in construct_stats_lines(&stats.stats()) {
+            eprintln!("{}", stats_line);
+        }
+    }
+
+    for (output, scan_result) in args.outputs.iter().zip(&scan_results) {
+        write_scan_output_lines(
+            &output.destination,
+            &scan_result.scan_output_lines,
         )?;
-    } else {
-        for scan_output_line in scan_output_lines {
-            println!("{}", scan_output_line);
+    }
+    if args.readme_args.update_readme {
+        if let Some(scan_result) = scan_results.last() {
+            create_or_replace_section_in_readme(
+                &args.readme_args,
+                &scan_result.scan_output_lines,
+            )?;
         }
     }
 
+    if build_failed {
+        return Err(CliError::new(anyhow::Error::new(BuildFailedError), 1));
+    }
+
+    let warning_count = scan_results
+        .iter()
+        .map(|scan_result| scan_result.warning_count)
+        .max()
+        .unwrap_or_default();
+
     if warning_count > 0 {
         return Err(CliError::new(
             anyhow::Error::new(FoundWarningsError { warning_count }),
@@ -118,8 +182,8 @@ fn cli_result_main(args: &Args) -> CliResult {
 }
 
 fn main() {
-    let args = Args::parse_args(pico_args::Arguments::from_env()).unwrap();
-    if let Err(e) = cli_result_main(&args) {
+    let mut args = Args::parse_args(pico_args::Arguments::from_env()).unwrap();
+    if let Err(e) = cli_result_main(&mut args) {
         let mut shell = Shell::new();
         cargo::exit_with_error(e, &mut shell)
     }
@@ -1,3 +1,4 @@
+mod cargo_core;
 mod geiger;
 mod krates;
 mod metadata;
@@ -40,6 +41,21 @@ pub trait DepsNotReplaced {
 }
 
 pub trait GetPackageIdInformation {
+    fn get_package_id_authors<T: GetNodeForKid>(
+        &self,
+        krates: &T,
+    ) -> Option<Vec<String>>;
+
+    fn get_package_id_description<T: GetNodeForKid>(
+        &self,
+        krates: &T,
+    ) -> Option<String>;
+
+    fn get_package_id_edition<T: GetNodeForKid>(
+        &self,
+        krates: &T,
+    ) -> Option<String>;
+
     fn get_package_id_licence<T: GetNodeForKid>(
         &self,
         krates: &T,
@@ -85,6 +101,8 @@ pub trait MatchesIgnoringSource {
 
 pub trait QueryResolve {
     fn query_resolve(&self, query: &str) -> Option<CargoMetadataPackageId>;
+
+    fn query_resolve_all(&self, query: &str) -> Vec<CargoMetadataPackageId>;
 }
 
 pub trait ToCargoCoreDepKind {
@@ -128,6 +146,13 @@ pub trait ToCargoMetadataPackageId: GetDependencyInformation {
     }
 }
 
+pub trait ToCargoMetadataPackageIdExact {
+    fn to_cargo_metadata_package_id_exact<T: GetMetadataPackages>(
+        &self,
+        metadata: &T,
+    ) -> Option<CargoMetadataPackageId>;
+}
+
 #[cfg(test)]
 mod mapping_tests {
     use super::*;
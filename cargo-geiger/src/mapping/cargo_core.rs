@@ -0,0 +1,137 @@
+use super::metadata::GetMetadataPackages;
+use super::ToCargoMetadataPackageIdExact;
+
+use cargo::core::PackageId as CargoCorePackageId;
+use cargo_metadata::PackageId as CargoMetadataPackageId;
+
+impl ToCargoMetadataPackageIdExact for CargoCorePackageId {
+    fn to_cargo_metadata_package_id_exact<T: GetMetadataPackages>(
+        &self,
+        metadata: &T,
+    ) -> Option<CargoMetadataPackageId> {
+        // The same name and version can come from several sources within a
+        // single resolve, e.g. crates.io and a git repository, so the source
+        // is compared too. `cargo_metadata` has no source for path packages,
+        // and the url form of the `SourceId` for the others.
+        let source = if self.source_id().is_path() {
+            None
+        } else {
+            Some(self.source_id().as_url().to_string())
+        };
+        let candidates = metadata
+            .get_metadata_packages()
+            .filter(|p| {
+                p.name == self.name().as_str() && p.version == *self.version()
+            })
+            .collect::<Vec<_>>();
+        candidates
+            .iter()
+            .find(|p| p.source.as_ref().map(|s| s.repr.clone()) == source)
+            // Replaced sources, e.g. vendored ones, don't match the source of
+            // the package in the metadata, an unambiguous package still does.
+            .or(match candidates.as_slice() {
+                [package] => Some(package),
+                _ => None,
+            })
+            .map(|p| p.id.clone())
+    }
+}
+
+#[cfg(test)]
+mod cargo_core_tests {
+    use super::*;
+
+    use cargo::core::{GitReference, SourceId};
+    use cargo_metadata::{Metadata, MetadataCommand};
+    use rstest::*;
+    use std::path::Path;
+    use url::Url;
+
+    #[rstest]
+    fn to_cargo_metadata_package_id_test() {
+        let metadata = MetadataCommand::new()
+            .manifest_path("./Cargo.toml")
+            .exec()
+            .unwrap();
+        let package = metadata.root_package().unwrap();
+        let source_id = SourceId::for_path(
+            package.manifest_path.parent().unwrap().as_ref(),
+        )
+        .unwrap();
+        let cargo_core_package_id = CargoCorePackageId::new(
+            package.name.as_str(),
+            package.version.to_string().as_str(),
+            source_id,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cargo_core_package_id.to_cargo_metadata_package_id_exact(&metadata),
+            Some(package.id.clone())
+        );
+    }
+
+    #[rstest(
+        input_source_id,
+        expected_package_index,
+        case(
+            SourceId::for_registry(
+                &Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap()
+            )
+            .unwrap(),
+            0
+        ),
+        case(
+            SourceId::for_git(
+                &Url::parse("https://github.com/a/b").unwrap(),
+                GitReference::DefaultBranch
+            )
+            .unwrap()
+            .with_precise(Some(String::from(
+                "0123456789abcdef0123456789abcdef01234567"
+            ))),
+            1
+        ),
+        case(SourceId::for_path(Path::new("/b")).unwrap(), 2)
+    )]
+    fn to_cargo_metadata_package_id_test_same_name_and_version(
+        input_source_id: SourceId,
+        expected_package_index: usize,
+    ) {
+        let package = |id: &str, source: Option<&str>| {
+            serde_json::json!({
+                "name": "b",
+                "version": "1.0.0",
+                "id": id,
+                "source": source,
+                "dependencies": [],
+                "targets": [],
+                "features": {},
+                "manifest_path": "/b/Cargo.toml",
+            })
+        };
+        let registry = "registry+https://github.com/rust-lang/crates.io-index";
+        let git = "git+https://github.com/a/b#0123456789abcdef0123456789abcdef01234567";
+        let metadata: Metadata = serde_json::from_value(serde_json::json!({
+            "packages": [
+                package(&format!("b 1.0.0 ({})", registry), Some(registry)),
+                package(&format!("b 1.0.0 ({})", git), Some(git)),
+                package("b 1.0.0 (path+file:///b)", None),
+            ],
+            "workspace_members": [],
+            "resolve": null,
+            "workspace_root": "/",
+            "target_directory": "/target",
+            "version": 1,
+        }))
+        .unwrap();
+        let cargo_core_package_id =
+            CargoCorePackageId::new("b", "1.0.0", input_source_id).unwrap();
+
+        assert_eq!(
+            cargo_core_package_id.to_cargo_metadata_package_id_exact(&metadata),
+            Some(metadata.packages[expected_package_index].id.clone())
+        );
+    }
+}
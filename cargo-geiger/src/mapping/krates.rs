@@ -23,16 +23,19 @@ impl GetNodeForKid for Krates {
 
 impl QueryResolve for Krates {
     fn query_resolve(&self, query: &str) -> Option<CargoMetadataPackageId> {
+        self.query_resolve_all(query).pop()
+    }
+
+    fn query_resolve_all(&self, query: &str) -> Vec<CargoMetadataPackageId> {
         match PkgSpec::from_str(query) {
             Ok(package_spec) => self
                 .krates_by_name(package_spec.name.as_str())
                 .filter(|(_, node)| package_spec.matches(&node.krate))
                 .map(|(_, node)| node.krate.clone().id)
-                .collect::<Vec<CargoMetadataPackageId>>()
-                .pop(),
+                .collect(),
             _ => {
                 eprintln!("Failed to construct PkgSpec from string: {}", query);
-                None
+                vec![]
             }
         }
     }
@@ -4,6 +4,33 @@ use cargo_metadata::semver::Version;
 use cargo_metadata::{Metadata, Package, PackageId};
 
 impl GetPackageIdInformation for PackageId {
+    fn get_package_id_authors<T: GetNodeForKid>(
+        &self,
+        krates: &T,
+    ) -> Option<Vec<String>> {
+        krates
+            .get_node_for_kid(self)
+            .map(|package| package.krate.authors.clone())
+    }
+
+    fn get_package_id_description<T: GetNodeForKid>(
+        &self,
+        krates: &T,
+    ) -> Option<String> {
+        krates
+            .get_node_for_kid(self)
+            .and_then(|package| package.krate.description.clone())
+    }
+
+    fn get_package_id_edition<T: GetNodeForKid>(
+        &self,
+        krates: &T,
+    ) -> Option<String> {
+        krates
+            .get_node_for_kid(self)
+            .map(|package| package.krate.edition.to_string())
+    }
+
     fn get_package_id_licence<T: GetNodeForKid>(
         &self,
         krates: &T,
@@ -0,0 +1,177 @@
+use crate::format::print_config::OutputFormat;
+
+use cargo::util::CargoResult;
+use console::strip_ansi_codes;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Where the output of a scan is written to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OutputDestination {
+    File(PathBuf),
+    Stdout,
+}
+
+/// An output of a scan, given as `format[=destination]` on the command line.
+/// A missing destination, or `-`, means stdout.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutputSpec {
+    pub destination: OutputDestination,
+    pub format: OutputFormat,
+}
+
+impl OutputSpec {
+    pub fn stdout(format: OutputFormat) -> Self {
+        OutputSpec {
+            destination: OutputDestination::Stdout,
+            format,
+        }
+    }
+}
+
+impl FromStr for OutputSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, destination) = match s.split_once('=') {
+            Some((format, destination)) => (format, Some(destination)),
+            None => (s, None),
+        };
+        let format = OutputFormat::from_str(format).map_err(|_| {
+            format!(
+                "Invalid output `{}`, expected `format[=destination]` with one of the formats: Ascii, Csv, CycloneDx, Dot, GitHubMarkdown, Html, Json, JUnit, Mermaid, Tsv, Utf8, Ratio",
+                s
+            )
+        })?;
+        let destination = match destination {
+            None | Some("-") => OutputDestination::Stdout,
+            Some("") => {
+                return Err(format!(
+                    "Invalid output `{}`, the destination is empty",
+                    s
+                ))
+            }
+            Some(path) => OutputDestination::File(PathBuf::from(path)),
+        };
+
+        Ok(OutputSpec {
+            destination,
+            format,
+        })
+    }
+}
+
+/// Writes the lines of a scan output to `destination`, one per line. Colors
+/// are only kept on stdout, files are written without them.
+pub fn write_scan_output_lines(
+    destination: &OutputDestination,
+    scan_output_lines: &[String],
+) -> CargoResult<()> {
+    match destination {
+        OutputDestination::File(path) => {
+            let mut content = scan_output_lines
+                .iter()
+                .map(|scan_output_line| strip_ansi_codes(scan_output_line))
+                .collect::<Vec<_>>()
+                .join("\n");
+            content.push('\n');
+            fs::write(path, content).map_err(|error| {
+                anyhow::anyhow!(
+                    "Failed to write output to {}: {}",
+                    path.display(),
+                    error
+                )
+            })
+        }
+        OutputDestination::Stdout => {
+            for scan_output_line in scan_output_lines {
+                println!("{}", scan_output_line);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod output_tests {
+    use super::*;
+
+    use rstest::*;
+    use tempfile::tempdir;
+
+    #[rstest(
+        input_output_spec,
+        expected_output_spec_result,
+        case("json", Ok(OutputSpec::stdout(OutputFormat::Json))),
+        case("utf8=-", Ok(OutputSpec::stdout(OutputFormat::Utf8))),
+        case(
+            "json=report.json",
+            Ok(OutputSpec {
+                destination: OutputDestination::File(PathBuf::from(
+                    "report.json"
+                )),
+                format: OutputFormat::Json
+            })
+        ),
+        case(
+            "GitHubMarkdown=out/report.md",
+            Ok(OutputSpec {
+                destination: OutputDestination::File(PathBuf::from(
+                    "out/report.md"
+                )),
+                format: OutputFormat::GitHubMarkdown
+            })
+        ),
+        case(
+            "json=",
+            Err(String::from("Invalid output `json=`, the destination is empty"))
+        ),
+        case(
+            "yaml=report.yaml",
+            Err(String::from("Invalid output `yaml=report.yaml`, expected `format[=destination]` with one of the formats: Ascii, Csv, CycloneDx, Dot, GitHubMarkdown, Html, Json, JUnit, Mermaid, Tsv, Utf8, Ratio"))
+        )
+    )]
+    fn output_spec_from_str_test(
+        input_output_spec: &str,
+        expected_output_spec_result: Result<OutputSpec, String>,
+    ) {
+        assert_eq!(
+            OutputSpec::from_str(input_output_spec),
+            expected_output_spec_result
+        );
+    }
+
+    #[rstest]
+    fn write_scan_output_lines_test_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("report.txt");
+
+        write_scan_output_lines(
+            &OutputDestination::File(path.clone()),
+            &[String::from("first"), String::from("second")],
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(path).unwrap(), "first\nsecond\n");
+    }
+
+    #[rstest]
+    fn write_scan_output_lines_test_file_without_colors() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("report.txt");
+        // A Utf8 table line as rendered for a terminal
+        let scan_output_line =
+            String::from("\u{1b}[1;31m1/1 ☢️ package 0.1.0\u{1b}[0m");
+
+        write_scan_output_lines(
+            &OutputDestination::File(path.clone()),
+            &[scan_output_line],
+        )
+        .unwrap();
+
+        let content = fs::read_to_string(path).unwrap();
+        assert!(!content.contains("\u{1b}["));
+        assert_eq!(content, "1/1 ☢️ package 0.1.0\n");
+    }
+}
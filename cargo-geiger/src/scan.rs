@@ -1,25 +1,34 @@
+mod binary;
 mod default;
 mod find;
 mod forbid;
+mod modules;
 mod rs_file;
+mod stats;
 
 use crate::args::Args;
-use crate::format::print_config::PrintConfig;
-use crate::graph::Graph;
+use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::graph::{DependencyEdge, Graph};
 use crate::mapping::{
     CargoMetadataParameters, ToCargoGeigerDependencyKind,
     ToCargoGeigerPackageId,
 };
 
+pub use binary::scan_binary;
+pub use modules::{module_stats, package_module_stats, parent_module_name};
 pub use rs_file::RsFileMetricsWrapper;
+pub use stats::{construct_stats_lines, ScanStatsCollector};
 
 use default::scan_unsafe;
+use rs_file::CompilationUnit;
+
+pub use default::scan_to_safety_report;
 use forbid::scan_forbid_unsafe;
 
 use cargo::core::Workspace;
 use cargo::{CliError, Config};
 use cargo_geiger_serde::{
-    CounterBlock, DependencyKind, PackageInfo, UnsafeInfo,
+    CounterBlock, DependencyCondition, PackageInfo, ScanError, UnsafeInfo,
 };
 use cargo_metadata::PackageId;
 use krates::NodeId;
@@ -28,6 +37,7 @@ use petgraph::visit::EdgeRef;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
+use std::hash::Hash;
 use std::path::PathBuf;
 
 #[derive(Debug)]
@@ -43,17 +53,50 @@ impl fmt::Display for FoundWarningsError {
     }
 }
 
+#[derive(Debug)]
+pub struct BuildFailedError;
+
+impl Error for BuildFailedError {}
+
+impl fmt::Display for BuildFailedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Build failed, the results are incomplete")
+    }
+}
+
 pub struct ScanResult {
     pub scan_output_lines: Vec<String>,
     pub warning_count: u64,
 }
 
+/// The results of a scan, one for each output format
+pub struct ScanOutcome {
+    pub scan_results: Vec<ScanResult>,
+    /// Whether the build failed, leaving every file counted as unused
+    pub build_failed: bool,
+}
+
+/// The empty result of an output rendered from a root package missing from
+/// the graph, e.g. one excluded by `--exclude`.
+pub fn root_package_not_in_graph(root_package_id: &PackageId) -> ScanResult {
+    eprintln!(
+        "WARNING: Root package is not in the dependency graph: {}",
+        root_package_id
+    );
+    ScanResult {
+        scan_output_lines: vec![],
+        warning_count: 1,
+    }
+}
+
 /// Provides a more terse and searchable name for the wrapped generic
 /// collection.
 #[derive(Default)]
 pub struct GeigerContext {
     pub package_id_to_metrics: HashMap<PackageId, PackageMetrics>,
     pub ignored_paths: HashSet<PathBuf>,
+    /// Files that failed to scan, sorted by path.
+    pub scan_errors: Vec<ScanError>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -76,35 +119,71 @@ pub struct ScanParameters<'a> {
     pub args: &'a Args,
     pub config: &'a Config,
     pub print_config: &'a PrintConfig,
+    pub stats: &'a ScanStatsCollector,
+}
+
+/// Renders `render_fn` once per output format, each with the print config
+/// switched to that format.
+fn render_output_formats<F>(
+    output_formats: &[OutputFormat],
+    scan_parameters: &ScanParameters,
+    mut render_fn: F,
+) -> Vec<ScanResult>
+where
+    F: FnMut(&ScanParameters) -> ScanResult,
+{
+    output_formats
+        .iter()
+        .map(|output_format| {
+            let print_config = PrintConfig {
+                output_format: *output_format,
+                ..scan_parameters.print_config.clone()
+            };
+            render_fn(&ScanParameters {
+                print_config: &print_config,
+                ..*scan_parameters
+            })
+        })
+        .collect()
 }
 
+/// Scans once and renders the results in each of `Args::output_formats`, in
+/// order.
 pub fn scan(
     args: &Args,
     cargo_metadata_parameters: &CargoMetadataParameters,
     config: &Config,
     graph: &Graph,
     root_package_id: PackageId,
+    stats: &ScanStatsCollector,
     workspace: &Workspace,
-) -> Result<ScanResult, CliError> {
+) -> Result<ScanOutcome, CliError> {
+    let output_formats = args.output_formats();
     let print_config = PrintConfig::new(args)?;
 
     let scan_parameters = ScanParameters {
         args,
         config,
         print_config: &print_config,
+        stats,
     };
 
     if args.forbid_only {
-        scan_forbid_unsafe(
-            cargo_metadata_parameters,
-            graph,
-            root_package_id,
-            &scan_parameters,
-        )
+        Ok(ScanOutcome {
+            scan_results: scan_forbid_unsafe(
+                cargo_metadata_parameters,
+                graph,
+                &output_formats,
+                root_package_id,
+                &scan_parameters,
+            )?,
+            build_failed: false,
+        })
     } else {
         scan_unsafe(
             cargo_metadata_parameters,
             graph,
+            &output_formats,
             root_package_id,
             &scan_parameters,
             workspace,
@@ -148,9 +227,30 @@ pub fn unsafe_stats(
     }
 }
 
+/// Like `unsafe_stats`, but only counts the files read by the units of
+/// `package_id` as used. A file shared by several packages, e.g. through
+/// `#[path]`, is then only used by the packages whose build read it.
+pub fn package_unsafe_stats<T: Eq + Hash>(
+    package_metrics: &PackageMetrics,
+    package_id: &T,
+    rs_files_used_by_package: &HashMap<T, HashSet<PathBuf>>,
+) -> UnsafeInfo {
+    match rs_files_used_by_package.get(package_id) {
+        Some(rs_files_used) => unsafe_stats(package_metrics, rs_files_used),
+        // No unit of the package was compiled, so none of its files were used
+        None => unsafe_stats(package_metrics, &HashSet::new()),
+    }
+}
+
 struct ScanDetails {
     rs_files_used: HashSet<PathBuf>,
+    rs_files_used_by_package: HashMap<PackageId, HashSet<PathBuf>>,
+    /// The files read by each compiled unit, grouped by package. Empty when
+    /// nothing was built.
+    rs_files_used_by_unit:
+        HashMap<PackageId, Vec<(CompilationUnit, HashSet<PathBuf>)>>,
     geiger_context: GeigerContext,
+    compiled_package_ids: HashSet<PackageId>,
 }
 
 fn construct_rs_files_used_lines(
@@ -200,8 +300,12 @@ fn package_metrics(
 ) -> Vec<(PackageInfo, Option<PackageMetrics>)> {
     let mut package_metrics =
         Vec::<(PackageInfo, Option<PackageMetrics>)>::new();
-    let root_index = graph.nodes[&root_package_id];
-    let mut indices = vec![root_index];
+    let mut indices = graph
+        .nodes
+        .get(&root_package_id)
+        .into_iter()
+        .copied()
+        .collect::<Vec<_>>();
     let mut visited = HashSet::new();
 
     while let Some(index) = indices.pop() {
@@ -215,13 +319,10 @@ fn package_metrics(
             for edge in graph.graph.edges(index) {
                 let dep_index = edge.target();
 
-                let dependency_kind_option =
-                    edge.weight().to_cargo_geiger_dependency_kind();
-
                 add_dependency_to_package_info(
                     cargo_metadata_parameters,
                     dep_index,
-                    dependency_kind_option,
+                    edge.weight(),
                     graph,
                     &mut indices,
                     &mut package_info,
@@ -250,7 +351,7 @@ fn package_metrics(
 fn add_dependency_to_package_info(
     cargo_metadata_parameters: &CargoMetadataParameters,
     dependency_index: NodeId,
-    dependency_kind_option: Option<DependencyKind>,
+    dependency_edge: &DependencyEdge,
     graph: &Graph,
     indices: &mut Vec<NodeIndex>,
     package_info: &mut PackageInfo,
@@ -263,8 +364,21 @@ fn add_dependency_to_package_info(
     let dependency_package_id_option = graph.graph[dependency_index]
         .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata);
 
-    match (dependency_package_id_option, dependency_kind_option) {
+    match (
+        dependency_package_id_option,
+        dependency_edge.kind.to_cargo_geiger_dependency_kind(),
+    ) {
         (Some(dependency_package_id), Some(dependency_kind)) => {
+            if dependency_edge.is_conditional() {
+                package_info
+                    .dependency_conditions
+                    .push(DependencyCondition {
+                        dependency: dependency_package_id.clone(),
+                        kind: dependency_kind,
+                        target: dependency_edge.target.clone(),
+                        features: dependency_edge.features.clone(),
+                    });
+            }
             package_info.add_dependency(dependency_package_id, dependency_kind);
         }
         (Some(dependency_package_id), None) => {
@@ -291,20 +405,32 @@ mod scan_tests {
 
     use crate::lib_tests::construct_krates_and_metadata;
     use cargo_geiger_serde::{Count, Source, UnsafeInfo};
+    use cargo_metadata::DependencyKind;
     use rstest::*;
     use semver::{BuildMetadata, Prerelease, Version};
     use std::{collections::HashSet, path::PathBuf};
     use url::Url;
 
     #[rstest(
-        input_dependency_kind_option,
+        input_dependency_edge,
         expected_package_info_dependency_length,
-        case(Some(DependencyKind::Normal), 1,),
-        case(None, 0)
+        expected_package_info_dependency_conditions_length,
+        case(DependencyKind::Normal.into(), 1, 0),
+        case(DependencyKind::Unknown.into(), 0, 0),
+        case(
+            DependencyEdge {
+                kind: DependencyKind::Normal,
+                target: Some(String::from("cfg(unix)")),
+                features: vec![String::from("std")],
+            },
+            1,
+            1
+        )
     )]
     fn add_dependency_to_package_info_test(
-        input_dependency_kind_option: Option<DependencyKind>,
+        input_dependency_edge: DependencyEdge,
         expected_package_info_dependency_length: usize,
+        expected_package_info_dependency_conditions_length: usize,
     ) {
         let (krates, metadata) = construct_krates_and_metadata();
         let package_id = metadata.root_package().unwrap().id.clone();
@@ -340,6 +466,7 @@ mod scan_tests {
             dependencies: Default::default(),
             dev_dependencies: Default::default(),
             build_dependencies: Default::default(),
+            dependency_conditions: Default::default(),
         };
 
         let mut indices = vec![];
@@ -350,7 +477,7 @@ mod scan_tests {
         add_dependency_to_package_info(
             &cargo_metadata_parameters,
             NodeIndex::new(0),
-            input_dependency_kind_option,
+            &input_dependency_edge,
             &graph,
             &mut indices,
             &mut package_info,
@@ -361,7 +488,11 @@ mod scan_tests {
         assert_eq!(
             package_info.dependencies.len(),
             expected_package_info_dependency_length
-        )
+        );
+        assert_eq!(
+            package_info.dependency_conditions.len(),
+            expected_package_info_dependency_conditions_length
+        );
     }
 
     #[rstest]
@@ -454,6 +585,7 @@ mod scan_tests {
             .cloned()
             .collect(),
             ignored_paths: HashSet::new(),
+            scan_errors: vec![],
         };
 
         let rs_files_used = input_rs_files_used_vec.iter().cloned().collect();
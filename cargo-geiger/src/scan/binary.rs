@@ -0,0 +1,652 @@
+use crate::args::Args;
+use crate::format::cyclonedx::create_cyclonedx_bom;
+use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::scan::rs_file::{
+    into_is_entry_point_and_path_buf, into_rs_code_file, RsFile,
+    RsFileMetricsWrapper,
+};
+
+use super::find::{find_rs_files_in_dir, into_scan_error};
+use super::modules::find_crate_root;
+use super::{unsafe_stats, PackageMetrics, ScanResult};
+
+use cargo::core::SourceId;
+use cargo::util::CargoResult;
+use cargo::{CliError, Config};
+use cargo_geiger_serde::{
+    DependencyKind, PackageId, PackageInfo, ReportEntry, SafetyReport,
+    ScanError, ScanErrorKind, Source,
+};
+use cargo_metadata::semver::Version;
+use flate2::read::ZlibDecoder;
+use geiger::find::find_unsafe_in_file;
+use geiger::{IncludeTests, ScanFileError};
+use object::{Object, ObjectSection};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Name of the linker section `cargo auditable` embeds the dependency list in.
+const DEPENDENCY_SECTION_NAME: &str = ".dep-v0";
+
+const CRATES_IO_INDEX_URL: &str =
+    "https://github.com/rust-lang/crates.io-index";
+
+/// The dependency list embedded by `cargo auditable`, see
+/// <https://github.com/rust-secure-code/cargo-auditable/blob/master/PARSING.md>
+#[derive(Debug, Deserialize)]
+struct VersionInfo {
+    packages: Vec<AuditablePackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditablePackage {
+    name: String,
+    version: Version,
+    source: String,
+    #[serde(default)]
+    kind: AuditableDependencyKind,
+    /// Whether this is the package of the binary itself
+    #[serde(default)]
+    root: bool,
+    /// Indices into `VersionInfo::packages`
+    #[serde(default)]
+    dependencies: Vec<usize>,
+}
+
+#[derive(Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum AuditableDependencyKind {
+    Build,
+    #[default]
+    Runtime,
+}
+
+/// Builds a `SafetyReport` for the packages linked into a binary built with
+/// `cargo auditable`, without a manifest or a rebuild, and renders it in each
+/// of `args.outputs`, `Json` or `CycloneDx`. The sources of the packages are
+/// looked up in the vendor directory, if provided, and the local registry
+/// cache. The code of the library target of a located package is counted as
+/// used, as is the binary target of the root package. Packages only
+/// used at build time, build dependencies and proc-macros, are left out as
+/// they are not linked into the binary.
+pub fn scan_binary(
+    args: &Args,
+    binary_path: &Path,
+    config: &Config,
+) -> Result<Vec<ScanResult>, CliError> {
+    let print_config = PrintConfig::new(args)?;
+    let version_info = read_version_info(binary_path)
+        .map_err(|error| CliError::new(error, 1))?;
+    // The binary of the root package is named after its target.
+    let bin_name = binary_path
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let registry_source_path =
+        config.registry_source_path().into_path_unlocked();
+
+    let package_roots = version_info
+        .packages
+        .iter()
+        .map(|package| match package.kind {
+            AuditableDependencyKind::Build => None,
+            AuditableDependencyKind::Runtime => find_package_root(
+                package,
+                args.vendor_dir.as_deref(),
+                &registry_source_path,
+                config,
+            ),
+        })
+        .collect::<Vec<Option<PathBuf>>>();
+    let package_infos =
+        construct_package_infos(&version_info, &package_roots, binary_path);
+    let root_package_id = version_info
+        .packages
+        .iter()
+        .zip(&package_infos)
+        .find(|(package, _)| package.root)
+        .map(|(_, package_info)| package_info.id.clone());
+
+    let mut report = SafetyReport::default();
+    for ((package, package_info), package_root) in version_info
+        .packages
+        .iter()
+        .zip(package_infos)
+        .zip(package_roots)
+    {
+        if package.kind == AuditableDependencyKind::Build {
+            continue;
+        }
+        let (package_metrics, rs_files_used) =
+            match package_root.map(|package_root| {
+                find_unsafe_in_package(
+                    &package_root,
+                    config,
+                    &package_info.id,
+                    print_config.include_tests,
+                    package.root.then_some(bin_name.as_str()),
+                )
+            }) {
+                Some(Ok((package_metrics, rs_files_used, scan_errors))) => {
+                    if args.strict && !scan_errors.is_empty() {
+                        return Err(CliError::new(
+                            anyhow::anyhow!(
+                                "Failed to scan package: {} {}, {}",
+                                package_info.id.name,
+                                package_info.id.version,
+                                scan_errors[0].message
+                            ),
+                            1,
+                        ));
+                    }
+                    report.scan_errors.extend(scan_errors);
+                    (package_metrics, rs_files_used)
+                }
+                Some(Err(error)) => {
+                    if args.strict {
+                        return Err(CliError::new(error, 1));
+                    }
+                    eprintln!(
+                        "Failed to scan package: {} {}, {}",
+                        package_info.id.name, package_info.id.version, error
+                    );
+                    report
+                        .scan_errors
+                        .push(into_package_scan_error(error, &package_info.id));
+                    report.packages_without_metrics.insert(package_info.id);
+                    continue;
+                }
+                None => {
+                    report.packages_without_metrics.insert(package_info.id);
+                    continue;
+                }
+            };
+        let entry = ReportEntry {
+            package: package_info,
+            unsafety: unsafe_stats(&package_metrics, &rs_files_used),
+            modules: None,
+            targets: None,
+        };
+        report.packages.insert(entry.package.id.clone(), entry);
+    }
+
+    Ok(
+        args.outputs
+            .iter()
+            .map(|output| match output.format {
+                OutputFormat::CycloneDx => {
+                    create_cyclonedx_bom(&report, root_package_id.as_ref())
+                }
+                _ => ScanResult {
+                    scan_output_lines: vec![
+                        serde_json::to_string(&report).unwrap()
+                    ],
+                    warning_count: 0,
+                },
+            })
+            .collect(),
+    )
+}
+
+fn read_version_info(binary_path: &Path) -> CargoResult<VersionInfo> {
+    let binary = fs::read(binary_path)?;
+    let object_file = object::File::parse(&*binary)?;
+    let section = object_file
+        .section_by_name(DEPENDENCY_SECTION_NAME)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No `{}` section found in {}, was it built with `cargo auditable`?",
+                DEPENDENCY_SECTION_NAME,
+                binary_path.display()
+            )
+        })?;
+    parse_version_info(section.data()?)
+}
+
+fn parse_version_info(compressed: &[u8]) -> CargoResult<VersionInfo> {
+    let mut json = String::new();
+    ZlibDecoder::new(compressed).read_to_string(&mut json)?;
+    let version_info: VersionInfo = serde_json::from_str(&json)?;
+
+    let package_count = version_info.packages.len();
+    if version_info
+        .packages
+        .iter()
+        .flat_map(|package| &package.dependencies)
+        .any(|index| *index >= package_count)
+    {
+        anyhow::bail!("Embedded dependency list refers to a missing package");
+    }
+    Ok(version_info)
+}
+
+/// Looks for the sources of `package`, first in the vendor directory and then
+/// in the extracted registry sources, e.g. `~/.cargo/registry/src/*/`.
+fn find_package_root(
+    package: &AuditablePackage,
+    vendor_dir: Option<&Path>,
+    registry_source_path: &Path,
+    config: &Config,
+) -> Option<PathBuf> {
+    let versioned_dir_name = format!("{}-{}", package.name, package.version);
+
+    let mut candidates = vec![];
+    if let Some(vendor_dir) = vendor_dir {
+        candidates.push(vendor_dir.join(&versioned_dir_name));
+        candidates.push(vendor_dir.join(&package.name));
+    }
+    if package.source == "crates.io" || package.source == "registry" {
+        if let Ok(read_dir) = fs::read_dir(registry_source_path) {
+            let mut registry_dirs = read_dir
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .collect::<Vec<PathBuf>>();
+            registry_dirs.sort();
+            candidates.extend(
+                registry_dirs
+                    .into_iter()
+                    .map(|registry_dir| registry_dir.join(&versioned_dir_name)),
+            );
+        }
+    }
+
+    candidates.into_iter().find(|candidate| {
+        matches!(
+            read_package(candidate, config),
+            Ok(p) if p.name().as_str() == package.name
+                && p.version() == &package.version
+        )
+    })
+}
+
+fn read_package(
+    package_root: &Path,
+    config: &Config,
+) -> CargoResult<cargo::core::Package> {
+    let (package, _) = cargo::ops::read_package(
+        &package_root.join("Cargo.toml"),
+        SourceId::for_path(package_root)?,
+        config,
+    )?;
+    Ok(package)
+}
+
+fn construct_package_infos(
+    version_info: &VersionInfo,
+    package_roots: &[Option<PathBuf>],
+    binary_path: &Path,
+) -> Vec<PackageInfo> {
+    let package_ids = version_info
+        .packages
+        .iter()
+        .zip(package_roots)
+        .map(|(package, package_root)| PackageId {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            source: to_cargo_geiger_source(
+                &package.source,
+                package_root.as_deref().unwrap_or(binary_path),
+            ),
+        })
+        .collect::<Vec<PackageId>>();
+
+    version_info
+        .packages
+        .iter()
+        .zip(&package_ids)
+        .map(|(package, package_id)| {
+            let mut package_info = PackageInfo::new(package_id.clone());
+            for dependency_index in &package.dependencies {
+                // Build time dependencies are not part of the binary.
+                if version_info.packages[*dependency_index].kind
+                    == AuditableDependencyKind::Build
+                {
+                    continue;
+                }
+                package_info.add_dependency(
+                    package_ids[*dependency_index].clone(),
+                    DependencyKind::Normal,
+                );
+            }
+            package_info
+        })
+        .collect()
+}
+
+/// `cargo auditable` only records the kind of source, so apart from crates.io
+/// the location the package was found at is used instead.
+fn to_cargo_geiger_source(source: &str, location: &Path) -> Source {
+    match source {
+        "crates.io" => Source::Registry {
+            name: String::from("crates.io"),
+            url: Url::parse(CRATES_IO_INDEX_URL).unwrap(),
+        },
+        _ => Source::Path(
+            Url::from_file_path(location)
+                .unwrap_or_else(|_| Url::parse("file:///").unwrap()),
+        ),
+    }
+}
+
+fn into_package_scan_error(
+    error: anyhow::Error,
+    package_id: &PackageId,
+) -> ScanError {
+    match error.downcast::<ScanFileError>() {
+        Ok(scan_file_error) => {
+            into_scan_error(scan_file_error, Some(package_id.clone()))
+        }
+        Err(error) => ScanError {
+            path: None,
+            package: Some(package_id.clone()),
+            kind: ScanErrorKind::Package,
+            message: error.to_string(),
+        },
+    }
+}
+
+/// The metrics of the files of the package at `package_root`, the files
+/// linked into the binary and the errors of the files that couldn't be
+/// scanned. Only the files of the library target, and of the binary target
+/// named `bin_name`, are counted as used.
+fn find_unsafe_in_package(
+    package_root: &Path,
+    config: &Config,
+    package_id: &PackageId,
+    include_tests: IncludeTests,
+    bin_name: Option<&str>,
+) -> CargoResult<(PackageMetrics, HashSet<PathBuf>, Vec<ScanError>)> {
+    let package = read_package(package_root, config)?;
+
+    let mut rs_files = vec![];
+    let mut target_paths = HashSet::new();
+    let mut linked_target_paths = HashSet::new();
+    for target in package.targets() {
+        if let Some(path) = target.src_path().path() {
+            if let Ok(canonical_path) = path.canonicalize() {
+                if target.is_lib()
+                    || (target.is_bin() && Some(target.name()) == bin_name)
+                {
+                    linked_target_paths.insert(canonical_path.clone());
+                }
+                target_paths.insert(canonical_path.clone());
+                rs_files.push(into_rs_code_file(target.kind(), canonical_path));
+            }
+        }
+    }
+    rs_files.extend(
+        find_rs_files_in_dir(package_root)
+            .filter(|path_buf| !target_paths.contains(path_buf))
+            .map(RsFile::Other),
+    );
+
+    let mut package_metrics = PackageMetrics::default();
+    let mut scan_errors = vec![];
+    for rs_file in rs_files {
+        // Build scripts are not linked into the binary.
+        if let RsFile::CustomBuildRoot(_) = rs_file {
+            continue;
+        }
+        let (is_crate_entry_point, path_buf) =
+            into_is_entry_point_and_path_buf(rs_file);
+        let metrics = match find_unsafe_in_file(&path_buf, include_tests) {
+            Ok(metrics) => metrics,
+            Err(error) => {
+                eprintln!(
+                    "Failed to parse file: {}, {:?} ",
+                    path_buf.display(),
+                    error
+                );
+                scan_errors
+                    .push(into_scan_error(error, Some(package_id.clone())));
+                continue;
+            }
+        };
+        package_metrics.rs_path_to_metrics.insert(
+            path_buf,
+            RsFileMetricsWrapper {
+                metrics,
+                is_crate_entry_point,
+            },
+        );
+    }
+
+    let crate_roots = target_paths
+        .iter()
+        .map(PathBuf::as_path)
+        .collect::<Vec<&Path>>();
+    let rs_files_used = package_metrics
+        .rs_path_to_metrics
+        .keys()
+        .filter(|path_buf| {
+            matches!(
+                find_crate_root(&crate_roots, path_buf),
+                Some(crate_root) if linked_target_paths.contains(crate_root)
+            )
+        })
+        .cloned()
+        .collect::<HashSet<PathBuf>>();
+    Ok((package_metrics, rs_files_used, scan_errors))
+}
+
+#[cfg(test)]
+mod binary_tests {
+    use super::*;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use rstest::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    const VERSION_INFO_JSON: &str = r#"{"packages":[
+        {"name":"app","version":"0.1.0","source":"local","dependencies":[1,2],"root":true},
+        {"name":"itoa","version":"1.0.6","source":"crates.io"},
+        {"name":"cc","version":"1.0.79","source":"crates.io","kind":"build"}
+    ]}"#;
+
+    #[rstest]
+    fn parse_version_info_test() {
+        let version_info =
+            parse_version_info(&compress(VERSION_INFO_JSON)).unwrap();
+
+        assert_eq!(version_info.packages.len(), 3);
+        assert_eq!(version_info.packages[0].dependencies, vec![1, 2]);
+        assert_eq!(
+            version_info.packages[1].kind,
+            AuditableDependencyKind::Runtime
+        );
+        assert_eq!(
+            version_info.packages[2].kind,
+            AuditableDependencyKind::Build
+        );
+    }
+
+    #[rstest]
+    fn parse_version_info_test_missing_dependency() {
+        let json = r#"{"packages":[
+            {"name":"app","version":"0.1.0","source":"local","dependencies":[1]}
+        ]}"#;
+
+        assert!(parse_version_info(&compress(json)).is_err());
+    }
+
+    #[rstest]
+    fn construct_package_infos_test() {
+        let version_info =
+            parse_version_info(&compress(VERSION_INFO_JSON)).unwrap();
+        let package_roots = vec![None, None, None];
+
+        let package_infos = construct_package_infos(
+            &version_info,
+            &package_roots,
+            Path::new("/path/to/app"),
+        );
+
+        let app = &package_infos[0];
+        assert_eq!(
+            app.id.source,
+            Source::Path(Url::parse("file:///path/to/app").unwrap())
+        );
+        assert_eq!(
+            app.dependencies,
+            vec![package_infos[1].id.clone()].into_iter().collect()
+        );
+        // `cc` is only used at build time, it isn't linked into the binary.
+        assert!(app.build_dependencies.is_empty());
+        assert!(package_infos[1].dependencies.is_empty());
+    }
+
+    #[rstest]
+    fn find_package_root_test() {
+        let config = Config::default().unwrap();
+        let vendor_dir = tempdir().unwrap();
+        let registry_source_path = tempdir().unwrap();
+        create_package_dir(&vendor_dir.path().join("itoa"), "itoa", "1.0.6");
+        // Another crate of the same version is not taken for `cc`.
+        create_package_dir(
+            &vendor_dir.path().join("cc-1.0.79"),
+            "not-cc",
+            "1.0.79",
+        );
+        create_package_dir(
+            &registry_source_path.path().join("index").join("cc-1.0.79"),
+            "cc",
+            "1.0.79",
+        );
+        let version_info =
+            parse_version_info(&compress(VERSION_INFO_JSON)).unwrap();
+
+        let package_roots = version_info
+            .packages
+            .iter()
+            .map(|package| {
+                find_package_root(
+                    package,
+                    Some(vendor_dir.path()),
+                    registry_source_path.path(),
+                    &config,
+                )
+            })
+            .collect::<Vec<Option<PathBuf>>>();
+
+        assert_eq!(
+            package_roots,
+            vec![
+                None,
+                Some(vendor_dir.path().join("itoa")),
+                Some(
+                    registry_source_path.path().join("index").join("cc-1.0.79")
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn find_unsafe_in_package_test() {
+        let config = Config::default().unwrap();
+        let package_dir = tempdir().unwrap();
+        create_package_dir(package_dir.path(), "itoa", "1.0.6");
+        fs::write(
+            package_dir.path().join("src").join("broken.rs"),
+            "fn broken( {",
+        )
+        .unwrap();
+        let package_id = PackageId {
+            name: String::from("itoa"),
+            version: Version::new(1, 0, 6),
+            source: to_cargo_geiger_source("crates.io", package_dir.path()),
+        };
+
+        let (package_metrics, _, scan_errors) = find_unsafe_in_package(
+            package_dir.path(),
+            &config,
+            &package_id,
+            IncludeTests::No,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(package_metrics.rs_path_to_metrics.len(), 1);
+        assert_eq!(scan_errors.len(), 1);
+        assert_eq!(scan_errors[0].kind, ScanErrorKind::Parse);
+        assert_eq!(scan_errors[0].package, Some(package_id));
+    }
+
+    #[rstest(
+        input_bin_name,
+        expected_rs_files_used,
+        case(None, vec!["src/lib.rs", "src/module.rs"]),
+        case(
+            Some("app"),
+            vec!["src/bin/app.rs", "src/lib.rs", "src/module.rs"]
+        )
+    )]
+    fn find_unsafe_in_package_test_rs_files_used(
+        input_bin_name: Option<&str>,
+        expected_rs_files_used: Vec<&str>,
+    ) {
+        let config = Config::default().unwrap();
+        let package_dir = tempdir().unwrap();
+        create_package_dir(package_dir.path(), "app", "0.1.0");
+        let src_dir = package_dir.path().join("src");
+        fs::write(src_dir.join("lib.rs"), "mod module;").unwrap();
+        fs::write(src_dir.join("module.rs"), "").unwrap();
+        fs::create_dir_all(src_dir.join("bin")).unwrap();
+        fs::write(src_dir.join("bin").join("app.rs"), "fn main() {}").unwrap();
+        fs::write(src_dir.join("bin").join("other.rs"), "fn main() {}")
+            .unwrap();
+        fs::create_dir_all(package_dir.path().join("examples")).unwrap();
+        fs::write(
+            package_dir.path().join("examples").join("example.rs"),
+            "fn main() {}",
+        )
+        .unwrap();
+        let package_id = PackageId {
+            name: String::from("app"),
+            version: Version::new(0, 1, 0),
+            source: to_cargo_geiger_source("local", package_dir.path()),
+        };
+
+        let (package_metrics, rs_files_used, _) = find_unsafe_in_package(
+            package_dir.path(),
+            &config,
+            &package_id,
+            IncludeTests::No,
+            input_bin_name,
+        )
+        .unwrap();
+
+        let package_root = package_dir.path().canonicalize().unwrap();
+        assert_eq!(package_metrics.rs_path_to_metrics.len(), 5);
+        assert_eq!(
+            rs_files_used,
+            expected_rs_files_used
+                .into_iter()
+                .map(|path| package_root.join(path))
+                .collect::<HashSet<PathBuf>>()
+        );
+    }
+
+    fn compress(json: &str) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(json.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn create_package_dir(package_dir: &Path, name: &str, version: &str) {
+        fs::create_dir_all(package_dir.join("src")).unwrap();
+        fs::write(package_dir.join("src").join("lib.rs"), "").unwrap();
+        fs::write(
+            package_dir.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{}\"\nversion = \"{}\"\n",
+                name, version
+            ),
+        )
+        .unwrap();
+    }
+}
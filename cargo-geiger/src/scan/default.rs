@@ -1,51 +1,139 @@
 mod table;
 
-use crate::args::FeaturesArgs;
+use crate::args::{Args, Verbosity};
+use crate::format::csv::create_csv_export;
+use crate::format::cyclonedx::create_cyclonedx_bom;
+use crate::format::graph_export::create_graph_export;
+use crate::format::html::create_html_report;
+use crate::format::junit::create_junit_report;
 use crate::format::print_config::OutputFormat;
+use crate::format::table::TableParameters;
 use crate::graph::Graph;
-use crate::mapping::CargoMetadataParameters;
-use crate::scan::rs_file::resolve_rs_file_deps;
+use crate::mapping::{
+    CargoMetadataParameters, ToCargoGeigerPackageId,
+    ToCargoMetadataPackageIdExact,
+};
+use crate::scan::rs_file::{resolve_rs_file_deps, CompilationUnit, RsFileDeps};
 
 use super::find::find_unsafe;
+use super::modules::find_crate_root;
 use super::{
-    list_files_used_but_not_scanned, package_metrics, unsafe_stats,
-    ScanDetails, ScanMode, ScanParameters, ScanResult,
+    list_files_used_but_not_scanned, package_metrics, package_module_stats,
+    package_unsafe_stats, render_output_formats, unsafe_stats, PackageMetrics,
+    ScanDetails, ScanMode, ScanOutcome, ScanParameters, ScanResult,
 };
 
-use table::scan_to_table;
+use table::scan_details_to_table;
 
-use cargo::core::compiler::CompileMode;
+use cargo::core::compiler::{CompileKind, CompileMode};
 use cargo::core::resolver::features::CliFeatures;
 use cargo::core::Workspace;
-use cargo::ops::CompileOptions;
+use cargo::ops::{CompileFilter, CompileOptions, FilterRule, LibRule};
+use cargo::util::interning::InternedString;
+use cargo::util::CargoResult;
 use cargo::{CliError, Config};
-use cargo_geiger_serde::{ReportEntry, SafetyReport};
+use cargo_geiger_serde::{
+    ReportEntry, SafetyReport, ScanError, ScanErrorKind, TargetEntry,
+};
 use cargo_metadata::PackageId;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 pub fn scan_unsafe(
     cargo_metadata_parameters: &CargoMetadataParameters,
     graph: &Graph,
+    output_formats: &[OutputFormat],
     root_package_id: PackageId,
     scan_parameters: &ScanParameters,
     workspace: &Workspace,
-) -> Result<ScanResult, CliError> {
-    match scan_parameters.args.output_format {
-        OutputFormat::Json => scan_to_report(
-            cargo_metadata_parameters,
-            graph,
-            scan_parameters.args.output_format,
-            root_package_id,
-            scan_parameters,
-            workspace,
-        ),
-        _ => scan_to_table(
-            cargo_metadata_parameters,
-            graph,
-            root_package_id,
-            scan_parameters,
-            workspace,
-        ),
+) -> Result<ScanOutcome, CliError> {
+    let scan_details = scan(
+        cargo_metadata_parameters,
+        &root_package_id,
+        scan_parameters,
+        workspace,
+    )?;
+
+    for path in list_files_used_but_not_scanned(
+        &scan_details.geiger_context,
+        &scan_details.rs_files_used,
+    ) {
+        eprintln!(
+            "WARNING: Dependency file was never scanned: {}",
+            path.display()
+        );
     }
+
+    // Without the build every file is counted as unused, the partial results
+    // are still written but the run fails afterwards.
+    let build_failed = scan_details
+        .geiger_context
+        .scan_errors
+        .iter()
+        .any(|scan_error| matches!(scan_error.kind, ScanErrorKind::Build));
+
+    let scan_results = render_output_formats(
+        output_formats,
+        scan_parameters,
+        |scan_parameters| match scan_parameters.print_config.output_format {
+            OutputFormat::Json => {
+                let report = safety_report_from_scan_details(
+                    cargo_metadata_parameters,
+                    graph,
+                    root_package_id.clone(),
+                    scan_parameters,
+                    &scan_details,
+                );
+                ScanResult {
+                    scan_output_lines: vec![
+                        serde_json::to_string(&report).unwrap()
+                    ],
+                    warning_count: 0,
+                }
+            }
+            OutputFormat::CycloneDx => {
+                let report = safety_report_from_scan_details(
+                    cargo_metadata_parameters,
+                    graph,
+                    root_package_id.clone(),
+                    scan_parameters,
+                    &scan_details,
+                );
+                create_cyclonedx_bom(
+                    &report,
+                    root_package_id
+                        .to_cargo_geiger_package_id(
+                            cargo_metadata_parameters.metadata,
+                        )
+                        .as_ref(),
+                )
+            }
+            OutputFormat::Csv
+            | OutputFormat::Dot
+            | OutputFormat::Html
+            | OutputFormat::JUnit
+            | OutputFormat::Mermaid
+            | OutputFormat::Tsv => scan_details_to_export(
+                cargo_metadata_parameters,
+                graph,
+                &root_package_id,
+                scan_parameters,
+                &scan_details,
+            ),
+            _ => scan_details_to_table(
+                cargo_metadata_parameters,
+                graph,
+                root_package_id.clone(),
+                scan_parameters,
+                &scan_details,
+            ),
+        },
+    );
+
+    Ok(ScanOutcome {
+        scan_results,
+        build_failed,
+    })
 }
 
 /// Based on code from cargo-bloat. It seems weird that `CompileOptions` can be
@@ -53,91 +141,438 @@ pub fn scan_unsafe(
 /// in cargo?
 /// Tracker rust-secure-code/cargo-geiger/issues/226
 fn build_compile_options<'a>(
-    args: &'a FeaturesArgs,
+    args: &'a Args,
     config: &'a Config,
-) -> CompileOptions {
+) -> CargoResult<CompileOptions> {
     let mut compile_options =
-        CompileOptions::new(config, CompileMode::Check { test: false })
-            .unwrap();
+        CompileOptions::new(config, CompileMode::Check { test: false })?;
 
-    let uses_default_features = !args.no_default_features;
+    let features_args = &args.features_args;
+    let uses_default_features = !features_args.no_default_features;
 
     compile_options.cli_features = CliFeatures::from_command_line(
-        &args.features,
-        args.all_features,
+        &features_args.features,
+        features_args.all_features,
         uses_default_features,
-    )
-    .unwrap();
-
-    // TODO: Investigate if this is relevant to cargo-geiger.
-    //let mut bins = Vec::new();
-    //let mut examples = Vec::new();
-    // opt.release = args.release;
-    // opt.target = args.target.clone();
-    // if let Some(ref name) = args.bin {
-    //     bins.push(name.clone());
-    // } else if let Some(ref name) = args.example {
-    //     examples.push(name.clone());
-    // }
-    // if args.bin.is_some() || args.example.is_some() {
-    //     opt.filter = ops::CompileFilter::new(
-    //         false,
-    //         bins.clone(), false,
-    //         Vec::new(), false,
-    //         examples.clone(), false,
-    //         Vec::new(), false,
-    //         false,
-    //     );
-    // }
-
-    compile_options
+    )?;
+
+    // With `--all-targets` the graph matches every platform, but the build
+    // can only be run for the host or explicitly requested targets.
+    compile_options.build_config.requested_kinds =
+        CompileKind::from_requested_targets(config, &args.target_args.targets)?;
+
+    if let Some(filter) = build_compile_filter(args) {
+        compile_options.build_config.mode = compile_mode(&filter);
+        compile_options.filter = filter;
+    }
+
+    if args.release {
+        compile_options.build_config.requested_profile =
+            InternedString::new("release");
+    }
+
+    Ok(compile_options)
+}
+
+/// Dev-dependencies are only compiled for test, bench and example targets, so
+/// when they are requested the test targets are built as well. Benches and
+/// examples are only built when selected explicitly.
+fn build_compile_filter(args: &Args) -> Option<CompileFilter> {
+    let target_selection_args = &args.target_selection_args;
+    let includes_dev_deps = args.deps_args.all_deps || args.deps_args.dev_deps;
+
+    if !target_selection_args.is_specified() {
+        return if includes_dev_deps {
+            Some(CompileFilter::new(
+                LibRule::Default,
+                FilterRule::All,
+                FilterRule::All,
+                FilterRule::none(),
+                FilterRule::none(),
+            ))
+        } else {
+            None
+        };
+    }
+
+    Some(CompileFilter::from_raw_arguments(
+        target_selection_args.lib,
+        target_selection_args.bins.clone(),
+        target_selection_args.all_bins,
+        target_selection_args.tests.clone(),
+        target_selection_args.all_tests
+            || (includes_dev_deps && target_selection_args.tests.is_empty()),
+        target_selection_args.examples.clone(),
+        target_selection_args.all_examples,
+        target_selection_args.benches.clone(),
+        target_selection_args.all_benches,
+        false,
+    ))
+}
+
+/// Test and bench targets are checked with `cfg(test)` enabled, as
+/// `cargo check --tests` would do, so their `#[cfg(test)]` code is scanned.
+fn compile_mode(filter: &CompileFilter) -> CompileMode {
+    let selects = |filter_rule: &FilterRule| match filter_rule {
+        FilterRule::All => true,
+        FilterRule::Just(targets) => !targets.is_empty(),
+    };
+    match filter {
+        CompileFilter::Only { tests, benches, .. }
+            if selects(tests) || selects(benches) =>
+        {
+            CompileMode::Check { test: true }
+        }
+        _ => CompileMode::Check { test: false },
+    }
 }
 
 fn scan(
     cargo_metadata_parameters: &CargoMetadataParameters,
+    root_package_id: &PackageId,
     scan_parameters: &ScanParameters,
     workspace: &Workspace,
 ) -> Result<ScanDetails, CliError> {
-    let compile_options = build_compile_options(
-        &scan_parameters.args.features_args,
-        scan_parameters.config,
-    );
+    if scan_parameters.args.no_deps {
+        return scan_root_package_only(
+            cargo_metadata_parameters,
+            root_package_id,
+            scan_parameters,
+        );
+    }
 
-    match resolve_rs_file_deps(&compile_options, workspace) {
-        Ok(rs_files_used) => {
-            let geiger_context = find_unsafe(
-                cargo_metadata_parameters,
-                scan_parameters.config,
-                ScanMode::Full,
-                scan_parameters.print_config,
-            )?;
-            Ok(ScanDetails {
-                rs_files_used,
-                geiger_context,
-            })
+    let compile_options =
+        build_compile_options(scan_parameters.args, scan_parameters.config)
+            .map_err(|error| CliError::new(error, 1))?;
+
+    let (rs_file_deps, build_error) = match resolve_rs_file_deps(
+        &compile_options,
+        scan_parameters.stats,
+        workspace,
+    ) {
+        Ok(rs_file_deps) => (rs_file_deps, None),
+        Err(rs_resolve_error) => {
+            if !scan_parameters.print_config.allow_partial_results {
+                return Err(CliError::new(rs_resolve_error.into(), 1));
+            }
+            // Without the build no file is known to be used, the scan
+            // continues with everything counted as unused.
+            eprintln!(
+                "WARNING: Failed to resolve the files used by the build: {}",
+                rs_resolve_error
+            );
+            (
+                RsFileDeps::default(),
+                Some(ScanError {
+                    path: workspace.root_manifest().parent().map(PathBuf::from),
+                    package: None,
+                    kind: ScanErrorKind::Build,
+                    message: rs_resolve_error.to_string(),
+                }),
+            )
         }
-        Err(rs_resolve_error) => Err(CliError::new(rs_resolve_error.into(), 1)),
+    };
+    let RsFileDeps {
+        rs_files_used,
+        compiled_package_ids,
+        unit_rs_files,
+    } = rs_file_deps;
+
+    let mut geiger_context = find_unsafe(
+        cargo_metadata_parameters,
+        scan_parameters.config,
+        ScanMode::Full,
+        None,
+        scan_parameters.print_config,
+        scan_parameters.stats,
+    )?;
+    if let Some(build_error) = build_error {
+        geiger_context.scan_errors.insert(0, build_error);
     }
+    let compiled_package_ids = compiled_package_ids
+        .iter()
+        .filter_map(|package_id| {
+            package_id.to_cargo_metadata_package_id_exact(
+                cargo_metadata_parameters.metadata,
+            )
+        })
+        .collect();
+    let mut rs_files_used_by_package =
+        HashMap::<PackageId, HashSet<PathBuf>>::new();
+    let mut rs_files_used_by_unit =
+        HashMap::<PackageId, Vec<(CompilationUnit, HashSet<PathBuf>)>>::new();
+    for (compilation_unit, rs_files) in unit_rs_files {
+        if let Some(package_id) = compilation_unit
+            .package_id
+            .to_cargo_metadata_package_id_exact(
+                cargo_metadata_parameters.metadata,
+            )
+        {
+            rs_files_used_by_package
+                .entry(package_id.clone())
+                .or_default()
+                .extend(rs_files.iter().cloned());
+            rs_files_used_by_unit
+                .entry(package_id)
+                .or_default()
+                .push((compilation_unit, rs_files));
+        }
+    }
+    Ok(ScanDetails {
+        rs_files_used,
+        rs_files_used_by_package,
+        rs_files_used_by_unit,
+        geiger_context,
+        compiled_package_ids,
+    })
+}
+
+/// Scans the files of `root_package_id` without building anything. Without
+/// the build, the files counted as used are those of the crates of the
+/// targets selected as `cargo check` would, each file belonging to the crate
+/// entry point whose directory most closely encloses it.
+fn scan_root_package_only(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    root_package_id: &PackageId,
+    scan_parameters: &ScanParameters,
+) -> Result<ScanDetails, CliError> {
+    let root_package_ids = HashSet::from([root_package_id.clone()]);
+    let geiger_context = find_unsafe(
+        cargo_metadata_parameters,
+        scan_parameters.config,
+        ScanMode::Full,
+        Some(&root_package_ids),
+        scan_parameters.print_config,
+        scan_parameters.stats,
+    )?;
+    let selected_crate_roots = cargo_metadata_parameters
+        .metadata
+        .packages
+        .iter()
+        .filter(|package| &package.id == root_package_id)
+        .flat_map(|package| &package.targets)
+        .filter(|target| {
+            is_target_selected(scan_parameters.args, &target.kind, &target.name)
+        })
+        .filter_map(|target| target.src_path.canonicalize().ok())
+        .collect::<HashSet<PathBuf>>();
+    let rs_files_used: HashSet<PathBuf> = geiger_context
+        .package_id_to_metrics
+        .get(root_package_id)
+        .map(|package_metrics| {
+            let crate_roots = package_metrics
+                .rs_path_to_metrics
+                .iter()
+                .filter(|(_, wrapper)| wrapper.is_crate_entry_point)
+                .map(|(path_buf, _)| path_buf.as_path())
+                .collect::<Vec<&Path>>();
+            package_metrics
+                .rs_path_to_metrics
+                .keys()
+                .filter(|path_buf| {
+                    matches!(
+                        find_crate_root(&crate_roots, path_buf),
+                        Some(crate_root)
+                            if selected_crate_roots.contains(crate_root)
+                    )
+                })
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let rs_files_used_by_package =
+        HashMap::from([(root_package_id.clone(), rs_files_used.clone())]);
+    Ok(ScanDetails {
+        rs_files_used,
+        rs_files_used_by_package,
+        rs_files_used_by_unit: HashMap::new(),
+        geiger_context,
+        compiled_package_ids: root_package_ids,
+    })
+}
+
+/// Whether a target of kind `target_kinds` named `target_name` is checked,
+/// following the same selection as `build_compile_filter`. Build scripts are
+/// always compiled.
+fn is_target_selected(
+    args: &Args,
+    target_kinds: &[String],
+    target_name: &str,
+) -> bool {
+    let target_selection_args = &args.target_selection_args;
+    let includes_dev_deps = args.deps_args.all_deps || args.deps_args.dev_deps;
+    let is_named = |names: &[String]| names.iter().any(|n| n == target_name);
+    target_kinds
+        .iter()
+        .any(|target_kind| match target_kind.as_str() {
+            "custom-build" => true,
+            "bin" => {
+                !target_selection_args.is_specified()
+                    || target_selection_args.all_bins
+                    || is_named(&target_selection_args.bins)
+            }
+            "test" => {
+                (includes_dev_deps && target_selection_args.tests.is_empty())
+                    || target_selection_args.all_tests
+                    || is_named(&target_selection_args.tests)
+            }
+            "example" => {
+                target_selection_args.all_examples
+                    || is_named(&target_selection_args.examples)
+            }
+            "bench" => {
+                target_selection_args.all_benches
+                    || is_named(&target_selection_args.benches)
+            }
+            // lib, rlib, proc-macro etc.
+            _ => {
+                !target_selection_args.is_specified()
+                    || target_selection_args.lib
+            }
+        })
 }
 
-fn scan_to_report(
+/// Scans the dependency tree of `root_package_id` and collects the results in
+/// a `SafetyReport`
+pub fn scan_to_safety_report(
     cargo_metadata_parameters: &CargoMetadataParameters,
     graph: &Graph,
-    output_format: OutputFormat,
     root_package_id: PackageId,
     scan_parameters: &ScanParameters,
     workspace: &Workspace,
-) -> Result<ScanResult, CliError> {
+) -> Result<SafetyReport, CliError> {
+    let scan_details = scan(
+        cargo_metadata_parameters,
+        &root_package_id,
+        scan_parameters,
+        workspace,
+    )?;
+
+    Ok(safety_report_from_scan_details(
+        cargo_metadata_parameters,
+        graph,
+        root_package_id,
+        scan_parameters,
+        &scan_details,
+    ))
+}
+
+/// Renders the outputs that are not tables, from the graph restricted to the
+/// compiled packages when targets are selected
+fn scan_details_to_export(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: &PackageId,
+    scan_parameters: &ScanParameters,
+    scan_details: &ScanDetails,
+) -> ScanResult {
+    let restricted_graph;
+    let graph = if scan_parameters.args.target_selection_args.is_specified() {
+        restricted_graph = graph.retain_packages(
+            &scan_details.compiled_package_ids,
+            root_package_id,
+        );
+        &restricted_graph
+    } else {
+        graph
+    };
+    let table_parameters = TableParameters {
+        geiger_context: &scan_details.geiger_context,
+        print_config: scan_parameters.print_config,
+        rs_files_used_by_package: &scan_details.rs_files_used_by_package,
+    };
+
+    match scan_parameters.print_config.output_format {
+        OutputFormat::Csv | OutputFormat::Tsv => create_csv_export(
+            cargo_metadata_parameters,
+            graph,
+            root_package_id,
+            &table_parameters,
+            scan_parameters.args.per_file,
+        ),
+        OutputFormat::Html => create_html_report(
+            cargo_metadata_parameters,
+            graph,
+            root_package_id,
+            &table_parameters,
+            &list_files_used_but_not_scanned(
+                &scan_details.geiger_context,
+                &scan_details.rs_files_used,
+            ),
+        ),
+        OutputFormat::JUnit => create_junit_report(
+            cargo_metadata_parameters,
+            graph,
+            root_package_id,
+            &table_parameters,
+            scan_parameters.args.max_unsafe,
+        ),
+        _ => create_graph_export(
+            cargo_metadata_parameters,
+            graph,
+            root_package_id,
+            &table_parameters,
+            scan_parameters.args.collapse_safe,
+        ),
+    }
+}
+
+fn safety_report_from_scan_details(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
+    scan_details: &ScanDetails,
+) -> SafetyReport {
     let ScanDetails {
         rs_files_used,
+        rs_files_used_by_package,
+        rs_files_used_by_unit,
         geiger_context,
-    } = scan(cargo_metadata_parameters, scan_parameters, workspace)?;
+        compiled_package_ids,
+    } = scan_details;
+    let restricted_graph;
+    let graph = if scan_parameters.args.target_selection_args.is_specified() {
+        restricted_graph =
+            graph.retain_packages(compiled_package_ids, &root_package_id);
+        &restricted_graph
+    } else {
+        graph
+    };
     let mut report = SafetyReport::default();
+    let root_cargo_geiger_package_id = root_package_id
+        .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata);
+    let mut module_entries = if scan_parameters.args.by_module {
+        package_module_stats(
+            cargo_metadata_parameters,
+            geiger_context,
+            &root_package_id,
+            rs_files_used_by_package,
+        )
+    } else {
+        None
+    };
+    let rs_files_used_by_package = rs_files_used_by_package
+        .iter()
+        .filter_map(|(package_id, rs_files)| {
+            package_id
+                .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)
+                .map(|package_id| (package_id, rs_files.clone()))
+        })
+        .collect::<HashMap<_, _>>();
+    let rs_files_used_by_unit = rs_files_used_by_unit
+        .iter()
+        .filter_map(|(package_id, unit_rs_files)| {
+            package_id
+                .to_cargo_geiger_package_id(cargo_metadata_parameters.metadata)
+                .map(|package_id| (package_id, unit_rs_files))
+        })
+        .collect::<HashMap<_, _>>();
     for (package, package_metrics_option) in package_metrics(
         cargo_metadata_parameters,
-        &geiger_context,
+        geiger_context,
         graph,
-        root_package_id,
+        root_package_id.clone(),
     ) {
         let package_metrics = match package_metrics_option {
             Some(m) => m,
@@ -146,32 +581,90 @@ fn scan_to_report(
                 continue;
             }
         };
-        let unsafe_info = unsafe_stats(&package_metrics, &rs_files_used);
+        let unsafe_info = package_unsafe_stats(
+            &package_metrics,
+            &package.id,
+            &rs_files_used_by_package,
+        );
+        let modules =
+            if Some(&package.id) == root_cargo_geiger_package_id.as_ref() {
+                module_entries.take()
+            } else {
+                None
+            };
+        let targets = if scan_parameters.args.by_target {
+            Some(target_unsafe_stats(
+                &package_metrics,
+                rs_files_used_by_unit
+                    .get(&package.id)
+                    .map_or(&[], |unit_rs_files| unit_rs_files.as_slice()),
+            ))
+        } else {
+            None
+        };
         let entry = ReportEntry {
             package,
             unsafety: unsafe_info,
+            modules,
+            targets,
         };
         report.packages.insert(entry.package.id.clone(), entry);
     }
     report.used_but_not_scanned_files =
-        list_files_used_but_not_scanned(&geiger_context, &rs_files_used)
+        list_files_used_but_not_scanned(geiger_context, rs_files_used)
             .into_iter()
             .collect();
-    let json_string = match output_format {
-        OutputFormat::Json => serde_json::to_string(&report).unwrap(),
-        _ => panic!("Only implemented for OutputFormat::Json"),
-    };
+    report.scan_errors = geiger_context.scan_errors.clone();
+    if scan_parameters.args.verbosity != Verbosity::Quiet {
+        report.stats = Some(scan_parameters.stats.stats());
+    }
+    report
+}
 
-    Ok(ScanResult {
-        scan_output_lines: vec![json_string],
-        warning_count: 0,
-    })
+/// The counters of each compiled unit of a package. A file read by several
+/// units, e.g. by a lib and by its unit tests, is used by each of them.
+fn target_unsafe_stats(
+    package_metrics: &PackageMetrics,
+    unit_rs_files: &[(CompilationUnit, HashSet<PathBuf>)],
+) -> Vec<TargetEntry> {
+    let mut target_entries = unit_rs_files
+        .iter()
+        .map(|(compilation_unit, rs_files)| TargetEntry {
+            name: compilation_unit.target.name().to_string(),
+            kind: compilation_unit.target.kind().description().to_string(),
+            test: matches!(
+                compilation_unit.mode,
+                CompileMode::Check { test: true }
+                    | CompileMode::Test
+                    | CompileMode::Bench
+            ),
+            unsafety: unsafe_stats(package_metrics, rs_files),
+        })
+        .collect::<Vec<_>>();
+    target_entries.sort_by(|a, b| {
+        (&a.kind, &a.name, a.test).cmp(&(&b.kind, &b.name, b.test))
+    });
+    target_entries
 }
 
 #[cfg(test)]
 mod default_tests {
     use super::*;
+
+    use crate::args::{
+        DepsArgs, FeaturesArgs, TargetArgs, TargetSelectionArgs,
+    };
+
+    use crate::scan::RsFileMetricsWrapper;
+
+    use cargo::core::compiler::{CompileTarget, CrateType};
+    use cargo::core::{
+        Edition, PackageId as CargoCorePackageId, SourceId, Target,
+    };
+    use cargo_geiger_serde::{Count, CounterBlock};
+    use geiger::RsFileMetrics;
     use rstest::*;
+    use std::path::Path;
 
     #[rstest(
         input_features,
@@ -187,14 +680,18 @@ mod default_tests {
         )
     )]
     fn build_compile_options_test(input_features: Vec<String>) {
-        let args = FeaturesArgs {
-            all_features: rand::random(),
-            features: input_features,
-            no_default_features: rand::random(),
+        let args = Args {
+            features_args: FeaturesArgs {
+                all_features: rand::random(),
+                features: input_features,
+                no_default_features: rand::random(),
+            },
+            ..Default::default()
         };
 
         let config = Config::default().unwrap();
-        let compile_options = build_compile_options(&args, &config);
+        let compile_options = build_compile_options(&args, &config).unwrap();
+        let args = args.features_args;
         let expected_cli_features =
             CliFeatures::from_command_line(&args.features, false, false)
                 .unwrap();
@@ -212,4 +709,296 @@ mod default_tests {
             args.no_default_features
         );
     }
+
+    #[rstest(
+        input_targets,
+        expected_requested_kinds,
+        case(vec![], vec![CompileKind::Host]),
+        case(
+            vec![
+                String::from("x86_64-unknown-linux-gnu"),
+                String::from("wasm32-unknown-unknown")
+            ],
+            vec![
+                CompileKind::Target(
+                    CompileTarget::new("wasm32-unknown-unknown").unwrap()
+                ),
+                CompileKind::Target(
+                    CompileTarget::new("x86_64-unknown-linux-gnu").unwrap()
+                ),
+            ]
+        )
+    )]
+    fn build_compile_options_test_targets(
+        input_targets: Vec<String>,
+        expected_requested_kinds: Vec<CompileKind>,
+    ) {
+        let args = Args {
+            target_args: TargetArgs {
+                all_targets: false,
+                targets: input_targets,
+            },
+            ..Default::default()
+        };
+
+        let config = Config::default().unwrap();
+        let compile_options = build_compile_options(&args, &config).unwrap();
+
+        assert_eq!(
+            compile_options.build_config.requested_kinds,
+            expected_requested_kinds
+        );
+    }
+
+    #[rstest(
+        input_deps_args,
+        input_target_selection_args,
+        expected_filter_is_some,
+        expected_need_dev_deps,
+        case(DepsArgs::default(), TargetSelectionArgs::default(), false, false),
+        case(
+            DepsArgs {
+                dev_deps: true,
+                ..Default::default()
+            },
+            TargetSelectionArgs::default(),
+            true,
+            true
+        ),
+        case(
+            DepsArgs {
+                all_deps: true,
+                ..Default::default()
+            },
+            TargetSelectionArgs {
+                lib: true,
+                ..Default::default()
+            },
+            true,
+            true
+        ),
+        case(
+            DepsArgs::default(),
+            TargetSelectionArgs {
+                lib: true,
+                ..Default::default()
+            },
+            true,
+            false
+        )
+    )]
+    fn build_compile_filter_test(
+        input_deps_args: DepsArgs,
+        input_target_selection_args: TargetSelectionArgs,
+        expected_filter_is_some: bool,
+        expected_need_dev_deps: bool,
+    ) {
+        let args = Args {
+            deps_args: input_deps_args,
+            target_selection_args: input_target_selection_args,
+            ..Default::default()
+        };
+
+        let filter = build_compile_filter(&args);
+
+        assert_eq!(filter.is_some(), expected_filter_is_some);
+        assert_eq!(
+            matches!(
+                filter,
+                Some(filter)
+                    if filter.need_dev_deps(CompileMode::Check { test: false })
+            ),
+            expected_need_dev_deps
+        );
+    }
+
+    #[rstest(
+        input_target_selection_args,
+        expected_compile_mode,
+        case(
+            TargetSelectionArgs {
+                lib: true,
+                ..Default::default()
+            },
+            CompileMode::Check { test: false }
+        ),
+        case(
+            TargetSelectionArgs {
+                tests: vec![String::from("integration")],
+                ..Default::default()
+            },
+            CompileMode::Check { test: true }
+        ),
+        case(
+            TargetSelectionArgs {
+                all_benches: true,
+                ..Default::default()
+            },
+            CompileMode::Check { test: true }
+        )
+    )]
+    fn compile_mode_test(
+        input_target_selection_args: TargetSelectionArgs,
+        expected_compile_mode: CompileMode,
+    ) {
+        let args = Args {
+            target_selection_args: input_target_selection_args,
+            ..Default::default()
+        };
+
+        let filter = build_compile_filter(&args).unwrap();
+
+        assert_eq!(compile_mode(&filter), expected_compile_mode);
+    }
+
+    #[rstest]
+    fn target_unsafe_stats_test() {
+        let rs_file_metrics = |unsafe_exprs: u64| RsFileMetricsWrapper {
+            metrics: RsFileMetrics {
+                counters: CounterBlock {
+                    exprs: Count {
+                        safe: 0,
+                        unsafe_: unsafe_exprs,
+                    },
+                    ..Default::default()
+                },
+                forbids_unsafe: false,
+            },
+            is_crate_entry_point: true,
+        };
+        let package_metrics = PackageMetrics {
+            rs_path_to_metrics: HashMap::from([
+                (PathBuf::from("/a/src/lib.rs"), rs_file_metrics(1)),
+                (PathBuf::from("/a/src/shared.rs"), rs_file_metrics(2)),
+                (PathBuf::from("/a/src/main.rs"), rs_file_metrics(4)),
+            ]),
+        };
+        let package_id = CargoCorePackageId::new(
+            "a",
+            "1.0.0",
+            SourceId::for_path(Path::new("/a")).unwrap(),
+        )
+        .unwrap();
+        let compilation_unit = |target: Target, test: bool| CompilationUnit {
+            package_id,
+            target,
+            mode: CompileMode::Check { test },
+        };
+        let lib_target = Target::lib_target(
+            "a",
+            vec![CrateType::Lib],
+            PathBuf::from("/a/src/lib.rs"),
+            Edition::Edition2018,
+        );
+        let unit_rs_files = vec![
+            (
+                compilation_unit(lib_target.clone(), false),
+                HashSet::from([
+                    PathBuf::from("/a/src/lib.rs"),
+                    PathBuf::from("/a/src/shared.rs"),
+                ]),
+            ),
+            (
+                compilation_unit(lib_target, true),
+                HashSet::from([
+                    PathBuf::from("/a/src/lib.rs"),
+                    PathBuf::from("/a/src/shared.rs"),
+                ]),
+            ),
+            (
+                compilation_unit(
+                    Target::bin_target(
+                        "a",
+                        None,
+                        PathBuf::from("/a/src/main.rs"),
+                        None,
+                        Edition::Edition2018,
+                    ),
+                    false,
+                ),
+                HashSet::from([
+                    PathBuf::from("/a/src/main.rs"),
+                    PathBuf::from("/a/src/shared.rs"),
+                ]),
+            ),
+        ];
+
+        let target_entries =
+            target_unsafe_stats(&package_metrics, &unit_rs_files);
+
+        assert_eq!(
+            target_entries
+                .iter()
+                .map(|target_entry| (
+                    target_entry.kind.as_str(),
+                    target_entry.test,
+                    target_entry.unsafety.used.exprs.unsafe_,
+                    target_entry.unsafety.unused.exprs.unsafe_
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("bin", false, 6, 1),
+                ("lib", false, 3, 4),
+                ("lib", true, 3, 4),
+            ]
+        );
+    }
+
+    #[rstest(
+        input_deps_args,
+        input_target_selection_args,
+        expected_selected_targets,
+        case(
+            DepsArgs::default(),
+            TargetSelectionArgs::default(),
+            vec!["build-script-build", "cli", "lib"]
+        ),
+        case(
+            DepsArgs {
+                dev_deps: true,
+                ..Default::default()
+            },
+            TargetSelectionArgs::default(),
+            vec!["build-script-build", "cli", "integration", "lib"]
+        ),
+        case(
+            DepsArgs::default(),
+            TargetSelectionArgs {
+                lib: true,
+                examples: vec![String::from("demo")],
+                ..Default::default()
+            },
+            vec!["build-script-build", "demo", "lib"]
+        )
+    )]
+    fn is_target_selected_test(
+        input_deps_args: DepsArgs,
+        input_target_selection_args: TargetSelectionArgs,
+        expected_selected_targets: Vec<&str>,
+    ) {
+        let args = Args {
+            deps_args: input_deps_args,
+            target_selection_args: input_target_selection_args,
+            ..Default::default()
+        };
+        let targets = [
+            ("lib", "lib"),
+            ("bin", "cli"),
+            ("test", "integration"),
+            ("example", "demo"),
+            ("bench", "speed"),
+            ("custom-build", "build-script-build"),
+        ];
+
+        let mut selected_targets = targets
+            .iter()
+            .filter(|(kind, name)| {
+                is_target_selected(&args, &[String::from(*kind)], name)
+            })
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+        selected_targets.sort_unstable();
+
+        assert_eq!(selected_targets, expected_selected_targets);
+    }
 }
@@ -2,41 +2,54 @@ use crate::args::Verbosity;
 use crate::format::emoji_symbols::EmojiSymbols;
 use crate::format::print_config::OutputFormat;
 use crate::format::table::{
-    create_table_from_text_tree_lines, TableParameters, UNSAFE_COUNTERS_HEADER,
+    blast_radii, create_table_from_direct_dependency_ranking,
+    create_table_from_duplicates, create_table_from_flat_ranking,
+    create_table_from_module_entries, create_table_from_text_tree_lines,
+    create_table_from_why, TableParameters, UNSAFE_COUNTERS_HEADER,
 };
-use crate::format::SymbolKind;
+use crate::format::{SortBy, SymbolKind};
 use crate::graph::Graph;
 use crate::mapping::CargoMetadataParameters;
+use crate::scan::package_module_stats;
 use crate::tree::traversal::walk_dependency_tree;
 
 use super::super::{
     construct_rs_files_used_lines, list_files_used_but_not_scanned,
     ScanDetails, ScanParameters, ScanResult,
 };
-use super::scan;
 
-use cargo::core::Workspace;
-use cargo::CliError;
 use cargo_metadata::PackageId;
 use colored::Colorize;
 
-pub fn scan_to_table(
+pub fn scan_details_to_table(
     cargo_metadata_parameters: &CargoMetadataParameters,
     graph: &Graph,
     root_package_id: PackageId,
     scan_parameters: &ScanParameters,
-    workspace: &Workspace,
-) -> Result<ScanResult, CliError> {
+    scan_details: &ScanDetails,
+) -> ScanResult {
     let mut combined_scan_output_lines = Vec::<String>::new();
 
     let ScanDetails {
         rs_files_used,
+        rs_files_used_by_package,
         geiger_context,
-    } = scan(cargo_metadata_parameters, scan_parameters, workspace)?;
+        compiled_package_ids,
+        ..
+    } = scan_details;
+    // Only the packages compiled for the selected targets are shown.
+    let restricted_graph;
+    let graph = if scan_parameters.args.target_selection_args.is_specified() {
+        restricted_graph =
+            graph.retain_packages(compiled_package_ids, &root_package_id);
+        &restricted_graph
+    } else {
+        graph
+    };
 
     if scan_parameters.args.verbosity != Verbosity::Quiet {
         let mut rs_files_used_lines =
-            construct_rs_files_used_lines(&rs_files_used);
+            construct_rs_files_used_lines(rs_files_used);
         combined_scan_output_lines.append(&mut rs_files_used_lines);
     }
 
@@ -44,51 +57,99 @@ pub fn scan_to_table(
         EmojiSymbols::new(scan_parameters.print_config.output_format);
     let mut output_key_lines = construct_key_lines(
         &emoji_symbols,
+        scan_parameters.args.by_module,
         scan_parameters.print_config.output_format,
+        scan_parameters.print_config.format.shows_unsafe_usage(),
     );
     combined_scan_output_lines.append(&mut output_key_lines);
 
-    let text_tree_lines = walk_dependency_tree(
-        cargo_metadata_parameters,
-        graph,
-        scan_parameters.print_config,
-        root_package_id,
-    );
     let table_parameters = TableParameters {
-        geiger_context: &geiger_context,
+        geiger_context,
         print_config: scan_parameters.print_config,
-        rs_files_used: &rs_files_used,
+        rs_files_used_by_package,
     };
 
     let ScanResult {
         mut scan_output_lines,
         mut warning_count,
-    } = create_table_from_text_tree_lines(
-        cargo_metadata_parameters,
-        &table_parameters,
-        text_tree_lines,
-    );
+    } = if scan_parameters.args.by_module {
+        let module_entries = package_module_stats(
+            cargo_metadata_parameters,
+            geiger_context,
+            &root_package_id,
+            rs_files_used_by_package,
+        )
+        .unwrap_or_default();
+        create_table_from_module_entries(&module_entries, &table_parameters)
+    } else if let Some(package_spec) = &scan_parameters.args.why {
+        create_table_from_why(
+            cargo_metadata_parameters,
+            graph,
+            package_spec,
+            &root_package_id,
+            &table_parameters,
+        )
+    } else if scan_parameters.args.duplicates {
+        create_table_from_duplicates(
+            cargo_metadata_parameters,
+            graph,
+            &root_package_id,
+            &table_parameters,
+        )
+    } else if scan_parameters.args.rank_direct_deps {
+        create_table_from_direct_dependency_ranking(
+            cargo_metadata_parameters,
+            graph,
+            &root_package_id,
+            &table_parameters,
+        )
+    } else if scan_parameters.args.flat {
+        create_table_from_flat_ranking(
+            cargo_metadata_parameters,
+            graph,
+            &root_package_id,
+            &table_parameters,
+            scan_parameters.args.sort_by.unwrap_or(SortBy::Total),
+            scan_parameters.args.top,
+        )
+    } else {
+        let blast_radii = scan_parameters
+            .args
+            .blast_radius
+            .then(|| blast_radii(graph, &root_package_id, &table_parameters));
+        let text_tree_lines = walk_dependency_tree(
+            cargo_metadata_parameters,
+            graph,
+            scan_parameters.print_config,
+            root_package_id,
+        );
+        create_table_from_text_tree_lines(
+            blast_radii.as_ref(),
+            cargo_metadata_parameters,
+            &table_parameters,
+            text_tree_lines,
+        )
+    };
     combined_scan_output_lines.append(&mut scan_output_lines);
 
-    let used_but_not_scanned =
-        list_files_used_but_not_scanned(&geiger_context, &rs_files_used);
-    warning_count += used_but_not_scanned.len() as u64;
-    for path in &used_but_not_scanned {
-        eprintln!(
-            "WARNING: Dependency file was never scanned: {}",
-            path.display()
-        );
-    }
+    // Printed once per scan by `scan_unsafe`, counted for each table.
+    warning_count +=
+        list_files_used_but_not_scanned(geiger_context, rs_files_used).len()
+            as u64;
 
-    Ok(ScanResult {
+    ScanResult {
         scan_output_lines: combined_scan_output_lines,
         warning_count,
-    })
+    }
 }
 
+/// The legend of the table. The column headers are left out when `--format`
+/// lays out the package lines, `pattern_layout`.
 fn construct_key_lines(
     emoji_symbols: &EmojiSymbols,
+    by_module: bool,
     output_format: OutputFormat,
+    pattern_layout: bool,
 ) -> Vec<String> {
     let mut output_key_lines = vec![String::new()];
 
@@ -148,12 +209,18 @@ fn construct_key_lines(
     }
 
     output_key_lines.push(String::new());
+    if pattern_layout && !by_module {
+        return output_key_lines;
+    }
 
-    let key = UNSAFE_COUNTERS_HEADER
-        .iter()
-        .map(|s| s.to_owned())
-        .collect::<Vec<_>>()
-        .join(" ");
+    let mut key_columns = UNSAFE_COUNTERS_HEADER.to_vec();
+    if by_module {
+        // The last column names the rows, which are modules instead of
+        // dependencies in this mode.
+        key_columns.pop();
+        key_columns.push("Module");
+    }
+    let key = key_columns.join(" ");
 
     match output_format {
         OutputFormat::GitHubMarkdown => output_key_lines.push(key),
@@ -1,14 +1,17 @@
 use crate::format::print_config::PrintConfig;
-use crate::mapping::{CargoMetadataParameters, GetPackageRoot};
+use crate::mapping::{
+    CargoMetadataParameters, GetPackageRoot, ToCargoGeigerPackageId,
+};
 use crate::scan::rs_file::{
     into_is_entry_point_and_path_buf, into_rs_code_file, into_target_kind,
     is_file_with_ext, RsFile, RsFileMetricsWrapper,
 };
 use crate::scan::PackageMetrics;
 
-use super::{GeigerContext, ScanMode};
+use super::{GeigerContext, ScanMode, ScanStatsCollector};
 
 use cargo::{CargoResult, CliError, Config};
+use cargo_geiger_serde::{ScanError, ScanErrorKind};
 use cargo_metadata::PackageId;
 use geiger::find::find_unsafe_in_file;
 use geiger::{IncludeTests, RsFileMetrics, ScanFileError};
@@ -21,23 +24,43 @@ use std::sync::mpsc::sync_channel;
 use std::sync::{Arc, Mutex};
 use walkdir::WalkDir;
 
+/// Scans the packages in `package_ids`, or every package in the metadata if
+/// `None`.
 pub fn find_unsafe(
     cargo_metadata_parameters: &CargoMetadataParameters,
     config: &Config,
     mode: ScanMode,
+    package_ids: Option<&HashSet<PackageId>>,
     print_config: &PrintConfig,
+    stats: &ScanStatsCollector,
 ) -> Result<GeigerContext, CliError> {
     let mut progress = cargo::util::Progress::new("Scanning", config);
-    let geiger_context = find_unsafe_in_packages_with_progress(
-        print_config.allow_partial_results,
-        cargo_metadata_parameters,
-        print_config.include_tests,
-        mode,
-        |progress_count, count| {
-            progress.tick(progress_count, count, "find_unsafe_tick")
-        },
-    );
+    let geiger_context = stats.time_phase("scan", || {
+        find_unsafe_in_packages_with_progress(
+            print_config.allow_partial_results,
+            cargo_metadata_parameters,
+            print_config.include_tests,
+            mode,
+            package_ids,
+            |progress_count, count| {
+                progress.tick(progress_count, count, "find_unsafe_tick")
+            },
+        )
+    });
     progress.clear();
+    stats.record_scanned(&geiger_context);
+    if !print_config.allow_partial_results {
+        if let Some(scan_error) = geiger_context.scan_errors.first() {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "Failed to scan {} file(s), the first failure: {}",
+                    geiger_context.scan_errors.len(),
+                    scan_error.message
+                ),
+                1,
+            ));
+        }
+    }
     config.shell().status("Scanning", "done")?;
     Ok(geiger_context)
 }
@@ -47,6 +70,7 @@ fn find_unsafe_in_packages_with_progress<F>(
     cargo_metadata_parameters: &CargoMetadataParameters,
     include_tests: IncludeTests,
     mode: ScanMode,
+    package_ids: Option<&HashSet<PackageId>>,
     mut progress_fn: F,
 ) -> GeigerContext
 where
@@ -64,6 +88,7 @@ where
                 cargo_metadata_parameters,
                 include_tests,
                 mode,
+                package_ids,
                 Some(on_processed),
             ))
         });
@@ -80,6 +105,7 @@ fn find_unsafe_in_packages<F>(
     cargo_metadata_parameters: &CargoMetadataParameters,
     include_tests: IncludeTests,
     mode: ScanMode,
+    package_ids: Option<&HashSet<PackageId>>,
     on_processed: Option<F>,
 ) -> GeigerContext
 where
@@ -87,14 +113,28 @@ where
 {
     let package_id_to_metrics = Arc::new(Mutex::new(HashMap::new()));
     let ignored = Arc::new(Mutex::new(HashSet::new()));
-    let packages = cargo_metadata_parameters.metadata.packages.to_vec();
+    let scan_errors = Arc::new(Mutex::new(Vec::new()));
+    let packages = cargo_metadata_parameters
+        .metadata
+        .packages
+        .iter()
+        .filter(|package| {
+            !matches!(package_ids, Some(package_ids) if !package_ids.contains(&package.id))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
     let package_code_files: Vec<_> =
         find_rs_files_in_packages(&packages).collect();
     let package_code_file_count = package_code_files.len();
     let processed_count = AtomicUsize::new(0);
     package_code_files.into_par_iter().for_each_with(
-        (package_id_to_metrics.clone(), ignored.clone()),
-        |(package_id_to_metrics, ignored), (package_id, rs_code_file)| {
+        (
+            package_id_to_metrics.clone(),
+            ignored.clone(),
+            scan_errors.clone(),
+        ),
+        |(package_id_to_metrics, ignored, scan_errors),
+         (package_id, rs_code_file)| {
             if let RsFile::CustomBuildRoot(path_buf) = rs_code_file {
                 let mut ignored = ignored.lock().unwrap();
                 ignored.insert(path_buf);
@@ -108,11 +148,20 @@ where
             }
             match find_unsafe_in_file(&path_buf, include_tests) {
                 Err(error) => {
-                    handle_unsafe_in_file_error(
-                        allow_partial_results,
-                        error,
-                        &path_buf,
+                    if allow_partial_results {
+                        eprintln!(
+                            "Failed to parse file: {}, {:?} ",
+                            path_buf.display(),
+                            error
+                        );
+                    }
+                    let package = package_id.to_cargo_geiger_package_id(
+                        cargo_metadata_parameters.metadata,
                     );
+                    scan_errors
+                        .lock()
+                        .unwrap()
+                        .push(into_scan_error(error, package));
                 }
                 Ok(rs_file_metrics) => {
                     let package_id_to_metrics =
@@ -145,13 +194,18 @@ where
         })
         .collect::<HashMap<PackageId, PackageMetrics>>();
 
+    let mut scan_errors =
+        Arc::try_unwrap(scan_errors).unwrap().into_inner().unwrap();
+    scan_errors.sort_by(|a, b| a.path.cmp(&b.path));
+
     GeigerContext {
         package_id_to_metrics: cargo_core_package_metrics,
         ignored_paths: Arc::try_unwrap(ignored).unwrap().into_inner().unwrap(),
+        scan_errors,
     }
 }
 
-fn find_rs_files_in_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
+pub fn find_rs_files_in_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
     let walker = WalkDir::new(dir).into_iter();
     walker.filter_map(|entry| {
         let entry = entry.expect("walkdir error."); // TODO: Return result.
@@ -217,15 +271,21 @@ fn find_rs_files_in_packages(
     })
 }
 
-fn handle_unsafe_in_file_error(
-    allow_partial_results: bool,
+pub fn into_scan_error(
     error: ScanFileError,
-    path_buf: &Path,
-) {
-    if allow_partial_results {
-        eprintln!("Failed to parse file: {}, {:?} ", path_buf.display(), error);
-    } else {
-        panic!("Failed to parse file: {}, {:?} ", path_buf.display(), error);
+    package: Option<cargo_geiger_serde::PackageId>,
+) -> ScanError {
+    let message = error.to_string();
+    let (kind, path) = match error {
+        ScanFileError::Io(_, path) => (ScanErrorKind::Io, path),
+        ScanFileError::Utf8(_, path) => (ScanErrorKind::Utf8, path),
+        ScanFileError::Syn(_, path) => (ScanErrorKind::Parse, path),
+    };
+    ScanError {
+        path: Some(path),
+        package,
+        kind,
+        message,
     }
 }
 
@@ -314,32 +374,20 @@ mod find_tests {
     }
 
     #[rstest]
-    fn handle_unsafe_in_file_error_doesnt_panic_when_allow_partial_results_is_true(
-    ) {
+    fn into_scan_error_test() {
         let path_buf = PathBuf::from("test_path");
-        handle_unsafe_in_file_error(
-            true,
+        let scan_error = into_scan_error(
             ScanFileError::Io(
                 io::Error::new(ErrorKind::Other, "test"),
                 path_buf.clone(),
             ),
-            &path_buf,
+            None,
         );
-    }
 
-    #[rstest]
-    #[should_panic]
-    fn handle_unsafe_in_file_error_panics_when_allow_partial_results_is_false()
-    {
-        let path_buf = PathBuf::from("test_path");
-        handle_unsafe_in_file_error(
-            false,
-            ScanFileError::Io(
-                io::Error::new(ErrorKind::Other, "test"),
-                path_buf.clone(),
-            ),
-            &path_buf,
-        );
+        assert_eq!(scan_error.path, Some(path_buf));
+        assert_eq!(scan_error.package, None);
+        assert_eq!(scan_error.kind, ScanErrorKind::Io);
+        assert!(scan_error.message.contains("test"));
     }
 
     #[rstest(
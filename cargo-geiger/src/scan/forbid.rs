@@ -1,61 +1,75 @@
 mod table;
 
-use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::args::Verbosity;
+use crate::format::print_config::OutputFormat;
 use crate::graph::Graph;
 use crate::mapping::CargoMetadataParameters;
 
 use super::find::find_unsafe;
-use super::{package_metrics, ScanMode, ScanParameters, ScanResult};
+use super::{
+    package_metrics, render_output_formats, GeigerContext, ScanMode,
+    ScanParameters, ScanResult,
+};
 
 use table::scan_forbid_to_table;
 
-use cargo::{CliError, Config};
+use cargo::CliError;
 use cargo_geiger_serde::{QuickReportEntry, QuickSafetyReport};
 use cargo_metadata::PackageId;
+use std::collections::HashSet;
 
 pub fn scan_forbid_unsafe(
     cargo_metadata_parameters: &CargoMetadataParameters,
     graph: &Graph,
+    output_formats: &[OutputFormat],
     root_package_id: PackageId,
     scan_parameters: &ScanParameters,
-) -> Result<ScanResult, CliError> {
-    match scan_parameters.args.output_format {
-        OutputFormat::Json => scan_forbid_to_report(
-            cargo_metadata_parameters,
-            scan_parameters.config,
-            graph,
-            scan_parameters.args.output_format,
-            scan_parameters.print_config,
-            root_package_id,
-        ),
-        _ => scan_forbid_to_table(
-            cargo_metadata_parameters,
-            scan_parameters.config,
-            graph,
-            scan_parameters.print_config,
-            root_package_id,
-        ),
-    }
+) -> Result<Vec<ScanResult>, CliError> {
+    // Entry points are scanned once, every output is rendered from the
+    // results.
+    let root_package_ids = HashSet::from([root_package_id.clone()]);
+    let geiger_context = find_unsafe(
+        cargo_metadata_parameters,
+        scan_parameters.config,
+        ScanMode::EntryPointsOnly,
+        scan_parameters.args.no_deps.then_some(&root_package_ids),
+        scan_parameters.print_config,
+        scan_parameters.stats,
+    )?;
+
+    Ok(render_output_formats(
+        output_formats,
+        scan_parameters,
+        |scan_parameters| match scan_parameters.print_config.output_format {
+            OutputFormat::Json => scan_forbid_to_report(
+                cargo_metadata_parameters,
+                &geiger_context,
+                graph,
+                root_package_id.clone(),
+                scan_parameters,
+            ),
+            _ => scan_forbid_to_table(
+                cargo_metadata_parameters,
+                &geiger_context,
+                graph,
+                scan_parameters.print_config,
+                root_package_id.clone(),
+            ),
+        },
+    ))
 }
 
 fn scan_forbid_to_report(
     cargo_metadata_parameters: &CargoMetadataParameters,
-    config: &Config,
+    geiger_context: &GeigerContext,
     graph: &Graph,
-    output_format: OutputFormat,
-    print_config: &PrintConfig,
     root_package_id: PackageId,
-) -> Result<ScanResult, CliError> {
-    let geiger_context = find_unsafe(
-        cargo_metadata_parameters,
-        config,
-        ScanMode::EntryPointsOnly,
-        print_config,
-    )?;
+    scan_parameters: &ScanParameters,
+) -> ScanResult {
     let mut report = QuickSafetyReport::default();
     for (package, package_metrics) in package_metrics(
         cargo_metadata_parameters,
-        &geiger_context,
+        geiger_context,
         graph,
         root_package_id,
     ) {
@@ -77,13 +91,13 @@ fn scan_forbid_to_report(
         };
         report.packages.insert(entry.package.id.clone(), entry);
     }
-    let json_string = match output_format {
-        OutputFormat::Json => serde_json::to_string(&report).unwrap(),
-        _ => panic!("Only implemented for OutputFormat::Json"),
-    };
+    report.scan_errors = geiger_context.scan_errors.clone();
+    if scan_parameters.args.verbosity != Verbosity::Quiet {
+        report.stats = Some(scan_parameters.stats.stats());
+    }
 
-    Ok(ScanResult {
-        scan_output_lines: vec![json_string],
+    ScanResult {
+        scan_output_lines: vec![serde_json::to_string(&report).unwrap()],
         warning_count: 0,
-    })
+    }
 }
@@ -1,27 +1,25 @@
 use crate::format::emoji_symbols::EmojiSymbols;
 use crate::format::pattern::Pattern;
 use crate::format::print_config::PrintConfig;
-use crate::format::{get_kind_group_name, SymbolKind};
+use crate::format::{format_edge_conditions, get_kind_group_name, SymbolKind};
 use crate::graph::Graph;
 use crate::mapping::CargoMetadataParameters;
 use crate::scan::GeigerContext;
 use crate::tree::traversal::walk_dependency_tree;
 use crate::tree::TextTreeLine;
 
-use super::super::find::find_unsafe;
-use super::super::{ScanMode, ScanResult};
+use super::super::ScanResult;
 
-use cargo::{CliError, Config};
 use cargo_metadata::PackageId;
 use colored::Colorize;
 
 pub fn scan_forbid_to_table(
     cargo_metadata_parameters: &CargoMetadataParameters,
-    config: &Config,
+    geiger_ctx: &GeigerContext,
     graph: &Graph,
     print_config: &PrintConfig,
     root_package_id: PackageId,
-) -> Result<ScanResult, CliError> {
+) -> ScanResult {
     let mut scan_output_lines = Vec::<String>::new();
     let emoji_symbols = EmojiSymbols::new(print_config.output_format);
 
@@ -34,46 +32,64 @@ pub fn scan_forbid_to_table(
         print_config,
         root_package_id,
     );
+    let mut table_lines = construct_table_lines(
+        cargo_metadata_parameters,
+        &emoji_symbols,
+        geiger_ctx,
+        print_config,
+        tree_lines,
+    );
+    scan_output_lines.append(&mut table_lines);
+
+    ScanResult {
+        scan_output_lines,
+        warning_count: 0,
+    }
+}
 
+fn construct_table_lines(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    emoji_symbols: &EmojiSymbols,
+    geiger_ctx: &GeigerContext,
+    print_config: &PrintConfig,
+    tree_lines: Vec<TextTreeLine>,
+) -> Vec<String> {
+    let mut table_lines = Vec::<String>::new();
+    let tree_lines = tree_lines
+        .into_iter()
+        .filter(|tree_line| print_config.is_depth_displayed(tree_line.depth()));
     for tree_line in tree_lines {
         match tree_line {
-            TextTreeLine::ExtraDepsGroup { kind, tree_vines } => {
+            TextTreeLine::ExtraDepsGroup {
+                kind, tree_vines, ..
+            } => {
                 let name = get_kind_group_name(kind);
                 if name.is_none() {
                     continue;
                 }
                 let name = name.unwrap();
                 // TODO: Fix the alignment on macOS (others too?)
-                scan_output_lines.push(format!("  {}{}", tree_vines, name));
+                table_lines.push(format!("  {}{}", tree_vines, name));
             }
             TextTreeLine::Package {
                 id: package_id,
                 tree_vines,
+                edge_conditions,
+                ..
             } => {
-                let geiger_ctx = find_unsafe(
+                table_lines.push(handle_package_text_tree_line(
                     cargo_metadata_parameters,
-                    config,
-                    ScanMode::EntryPointsOnly,
-                    print_config,
-                )?;
-
-                handle_package_text_tree_line(
-                    cargo_metadata_parameters,
-                    &emoji_symbols,
-                    &geiger_ctx,
+                    &edge_conditions,
+                    emoji_symbols,
+                    geiger_ctx,
                     package_id,
                     print_config,
-                    &mut scan_output_lines,
                     tree_vines,
-                );
+                ));
             }
         }
     }
-
-    Ok(ScanResult {
-        scan_output_lines,
-        warning_count: 0,
-    })
+    table_lines
 }
 
 fn construct_key_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
@@ -109,20 +125,24 @@ fn format_package_name(
 
 fn handle_package_text_tree_line(
     cargo_metadata_parameters: &CargoMetadataParameters,
+    edge_conditions: &[String],
     emoji_symbols: &EmojiSymbols,
     geiger_ctx: &GeigerContext,
     package_id: PackageId,
     print_config: &PrintConfig,
-    scan_output_lines: &mut Vec<String>,
     tree_vines: String,
-) {
+) -> String {
     let sym_lock = emoji_symbols.emoji(SymbolKind::Lock);
     let sym_qmark = emoji_symbols.emoji(SymbolKind::QuestionMark);
 
-    let name = format_package_name(
-        cargo_metadata_parameters,
-        &package_id,
-        &print_config.format,
+    let name = format!(
+        "{}{}",
+        format_package_name(
+            cargo_metadata_parameters,
+            &package_id,
+            &print_config.format,
+        ),
+        format_edge_conditions(edge_conditions)
     );
     let package_metrics = geiger_ctx.package_id_to_metrics.get(&package_id);
     let package_forbids_unsafe = match package_metrics {
@@ -138,14 +158,29 @@ fn handle_package_text_tree_line(
     } else {
         (&sym_qmark, name.red())
     };
-    scan_output_lines.push(format!("{} {}{}", symbol, tree_vines, name));
+    format!("{} {}{}", symbol, tree_vines, name)
 }
 
 #[cfg(test)]
 mod forbid_tests {
+    use super::super::scan_forbid_unsafe;
     use super::*;
+    use crate::args::Args;
     use crate::format::print_config::OutputFormat;
+    use crate::scan::{ScanParameters, ScanStatsCollector};
+
+    use cargo::Config;
+
+    use cargo_geiger_serde::QuickSafetyReport;
+    use cargo_metadata::{DependencyKind, Metadata};
+    use krates::Builder as KratesBuilder;
+    use pico_args::Arguments;
     use rstest::*;
+    use std::collections::{HashMap, HashSet};
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
 
     #[rstest]
     fn construct_scan_mode_forbid_only_output_key_lines_test() {
@@ -154,4 +189,312 @@ mod forbid_tests {
 
         assert_eq!(output_key_lines.len(), 5);
     }
+
+    #[rstest]
+    fn scan_forbid_unsafe_test_scans_once_for_a_large_graph() {
+        const PACKAGE_COUNT: usize = 500;
+
+        let temp_dir = tempdir().unwrap();
+        let metadata =
+            create_synthetic_metadata(temp_dir.path(), PACKAGE_COUNT);
+        let krates = KratesBuilder::new()
+            .build_with_metadata(metadata.clone(), |_| ())
+            .unwrap();
+        let cargo_metadata_parameters = CargoMetadataParameters {
+            metadata: &metadata,
+            krates: &krates,
+        };
+        let root_package_id = metadata.packages[0].id.clone();
+        let graph = create_star_graph(&metadata);
+        let print_config = PrintConfig {
+            all: true,
+            ..Default::default()
+        };
+        let stats = ScanStatsCollector::default();
+
+        let args = Args::default();
+        let config = Config::default().unwrap();
+        let scan_parameters = ScanParameters {
+            args: &args,
+            config: &config,
+            print_config: &print_config,
+            stats: &stats,
+        };
+
+        let scan_results = scan_forbid_unsafe(
+            &cargo_metadata_parameters,
+            &graph,
+            &[OutputFormat::Ascii, OutputFormat::Json],
+            root_package_id,
+            &scan_parameters,
+        )
+        .unwrap();
+
+        let stats = stats.stats();
+        assert_eq!(
+            stats
+                .phases
+                .iter()
+                .filter(|phase_duration| phase_duration.phase == "scan")
+                .count(),
+            1
+        );
+        // Each package and its entry point is scanned once for all the
+        // outputs, not once per tree line.
+        assert_eq!(stats.packages_scanned, PACKAGE_COUNT as u64);
+        assert_eq!(stats.files_scanned, PACKAGE_COUNT as u64);
+        assert_eq!(scan_results.len(), 2);
+        assert_eq!(
+            scan_results[0].scan_output_lines.len(),
+            construct_key_lines(&EmojiSymbols::new(OutputFormat::Ascii)).len()
+                + PACKAGE_COUNT
+        );
+        assert_eq!(scan_results[1].scan_output_lines.len(), 1);
+    }
+
+    #[rstest]
+    fn construct_table_lines_test_hides_lines_below_depth() {
+        let temp_dir = tempdir().unwrap();
+        let metadata = create_synthetic_metadata(temp_dir.path(), 3);
+        let krates = KratesBuilder::new()
+            .build_with_metadata(metadata.clone(), |_| ())
+            .unwrap();
+        let cargo_metadata_parameters = CargoMetadataParameters {
+            metadata: &metadata,
+            krates: &krates,
+        };
+        let tree_lines = metadata
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(depth, package)| TextTreeLine::Package {
+                id: package.id.clone(),
+                tree_vines: String::new(),
+                depth,
+                edge_conditions: vec![],
+            })
+            .collect::<Vec<TextTreeLine>>();
+        let print_config = PrintConfig {
+            depth: Some(1),
+            ..Default::default()
+        };
+
+        let table_lines = construct_table_lines(
+            &cargo_metadata_parameters,
+            &EmojiSymbols::new(OutputFormat::Ascii),
+            &GeigerContext::default(),
+            &print_config,
+            tree_lines,
+        );
+
+        assert_eq!(table_lines.len(), 2);
+    }
+
+    #[rstest]
+    fn scan_forbid_unsafe_test_no_deps_scans_root_package_only() {
+        let temp_dir = tempdir().unwrap();
+        let metadata = create_synthetic_metadata(temp_dir.path(), 10);
+        let krates = KratesBuilder::new()
+            .build_with_metadata(metadata.clone(), |_| ())
+            .unwrap();
+        let cargo_metadata_parameters = CargoMetadataParameters {
+            metadata: &metadata,
+            krates: &krates,
+        };
+        let root_package_id = metadata.packages[0].id.clone();
+        let graph = create_star_graph(&metadata)
+            .retain_packages(&HashSet::new(), &root_package_id);
+        let args = Args {
+            no_deps: true,
+            ..Default::default()
+        };
+        let config = Config::default().unwrap();
+        let stats = ScanStatsCollector::default();
+        let scan_parameters = ScanParameters {
+            args: &args,
+            config: &config,
+            print_config: &PrintConfig::default(),
+            stats: &stats,
+        };
+
+        scan_forbid_unsafe(
+            &cargo_metadata_parameters,
+            &graph,
+            &[OutputFormat::Json],
+            root_package_id,
+            &scan_parameters,
+        )
+        .unwrap();
+
+        assert_eq!(stats.stats().packages_scanned, 1);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_has_stats,
+        case(vec![], false),
+        case(vec!["-v"], true),
+        case(vec!["--verbose"], true),
+        case(vec!["-vv"], true)
+    )]
+    fn scan_forbid_unsafe_test_attaches_stats_when_verbose(
+        input_argument_vector: Vec<&str>,
+        expected_has_stats: bool,
+    ) {
+        let temp_dir = tempdir().unwrap();
+        let metadata = create_synthetic_metadata(temp_dir.path(), 2);
+        let krates = KratesBuilder::new()
+            .build_with_metadata(metadata.clone(), |_| ())
+            .unwrap();
+        let cargo_metadata_parameters = CargoMetadataParameters {
+            metadata: &metadata,
+            krates: &krates,
+        };
+        let root_package_id = metadata.packages[0].id.clone();
+        let graph = create_star_graph(&metadata);
+        let args = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ))
+        .unwrap();
+        let config = Config::default().unwrap();
+        let stats = ScanStatsCollector::default();
+        let scan_parameters = ScanParameters {
+            args: &args,
+            config: &config,
+            print_config: &PrintConfig::default(),
+            stats: &stats,
+        };
+
+        let scan_results = scan_forbid_unsafe(
+            &cargo_metadata_parameters,
+            &graph,
+            &[OutputFormat::Json],
+            root_package_id,
+            &scan_parameters,
+        )
+        .unwrap();
+
+        let report: QuickSafetyReport =
+            serde_json::from_str(&scan_results[0].scan_output_lines[0])
+                .unwrap();
+        assert_eq!(report.stats.is_some(), expected_has_stats);
+    }
+
+    /// Metadata for a root package depending on `package_count - 1` other
+    /// packages, each a single `lib.rs` that forbids unsafe code.
+    fn create_synthetic_metadata(
+        root: &Path,
+        package_count: usize,
+    ) -> Metadata {
+        let package_ids = (0..package_count)
+            .map(|index| {
+                format!(
+                    "package-{} 0.1.0 (path+file://{}/package-{})",
+                    index,
+                    root.display(),
+                    index
+                )
+            })
+            .collect::<Vec<String>>();
+
+        let mut packages = vec![];
+        let mut nodes = vec![];
+        for (index, package_id) in package_ids.iter().enumerate() {
+            let package_root = root.join(format!("package-{}", index));
+            fs::create_dir_all(package_root.join("src")).unwrap();
+            fs::write(
+                package_root.join("src").join("lib.rs"),
+                "#![forbid(unsafe_code)]",
+            )
+            .unwrap();
+
+            let dependency_ids = if index == 0 {
+                package_ids[1..].to_vec()
+            } else {
+                vec![]
+            };
+            packages.push(serde_json::json!({
+                "name": format!("package-{}", index),
+                "version": "0.1.0",
+                "id": package_id,
+                "source": null,
+                "dependencies": dependency_ids
+                    .iter()
+                    .enumerate()
+                    .map(|(dependency_index, _)| serde_json::json!({
+                        "name": format!("package-{}", dependency_index + 1),
+                        "source": null,
+                        "req": "^0.1.0",
+                        "kind": null,
+                        "optional": false,
+                        "uses_default_features": true,
+                        "features": [],
+                        "target": null,
+                        "rename": null,
+                        "registry": null,
+                        "path": root.join(format!("package-{}", dependency_index + 1)),
+                    }))
+                    .collect::<Vec<_>>(),
+                "targets": [{
+                    "name": format!("package-{}", index),
+                    "kind": ["lib"],
+                    "crate_types": ["lib"],
+                    "src_path": package_root.join("src").join("lib.rs"),
+                    "edition": "2018",
+                    "doctest": true,
+                    "test": true,
+                }],
+                "features": {},
+                "manifest_path": package_root.join("Cargo.toml"),
+                "edition": "2018",
+            }));
+            nodes.push(serde_json::json!({
+                "id": package_id,
+                "dependencies": dependency_ids,
+                "deps": dependency_ids
+                    .iter()
+                    .enumerate()
+                    .map(|(dependency_index, dependency_id)| serde_json::json!({
+                        "name": format!("package_{}", dependency_index + 1),
+                        "pkg": dependency_id,
+                        "dep_kinds": [{ "kind": null, "target": null }],
+                    }))
+                    .collect::<Vec<_>>(),
+                "features": [],
+            }));
+        }
+
+        serde_json::from_value(serde_json::json!({
+            "packages": packages,
+            "workspace_members": [package_ids[0]],
+            "resolve": { "nodes": nodes, "root": package_ids[0] },
+            "workspace_root": root,
+            "target_directory": root.join("target"),
+            "version": 1,
+        }))
+        .unwrap()
+    }
+
+    fn create_star_graph(metadata: &Metadata) -> Graph {
+        let mut graph = Graph {
+            graph: Default::default(),
+            nodes: HashMap::new(),
+        };
+        for package in &metadata.packages {
+            let node_index = graph.graph.add_node(package.id.clone());
+            graph.nodes.insert(package.id.clone(), node_index);
+        }
+        let root_index = graph.nodes[&metadata.packages[0].id];
+        for package in &metadata.packages[1..] {
+            graph.graph.add_edge(
+                root_index,
+                graph.nodes[&package.id],
+                DependencyKind::Normal.into(),
+            );
+        }
+        graph
+    }
 }
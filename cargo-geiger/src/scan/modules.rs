@@ -0,0 +1,263 @@
+use crate::mapping::{CargoMetadataParameters, GetPackageRoot};
+
+use super::{unsafe_stats, GeigerContext, PackageMetrics};
+
+use cargo_geiger_serde::ModuleEntry;
+use cargo_metadata::PackageId;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Per-module scan results for a single package, or `None` if the package
+/// was not scanned or its root directory could not be determined.
+pub fn package_module_stats(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    geiger_context: &GeigerContext,
+    package_id: &PackageId,
+    rs_files_used_by_package: &HashMap<PackageId, HashSet<PathBuf>>,
+) -> Option<Vec<ModuleEntry>> {
+    let package_metrics =
+        geiger_context.package_id_to_metrics.get(package_id)?;
+    let package_root = cargo_metadata_parameters
+        .krates
+        .node_for_kid(package_id)
+        .and_then(|node| node.krate.clone().get_root())?;
+    // The scanned source file paths are canonicalized, the package root
+    // has to be as well for the relative module paths to resolve.
+    let package_root = package_root.canonicalize().unwrap_or(package_root);
+
+    let no_rs_files_used = HashSet::new();
+    let rs_files_used = rs_files_used_by_package
+        .get(package_id)
+        .unwrap_or(&no_rs_files_used);
+
+    Some(module_stats(&package_root, package_metrics, rs_files_used))
+}
+
+/// Splits the metrics of a package into one entry per module. A module is
+/// attributed to the crate entry point whose directory most closely encloses
+/// its source file, and named after its path relative to that directory.
+pub fn module_stats(
+    package_root: &Path,
+    package_metrics: &PackageMetrics,
+    rs_files_used: &HashSet<PathBuf>,
+) -> Vec<ModuleEntry> {
+    let mut crate_roots = package_metrics
+        .rs_path_to_metrics
+        .iter()
+        .filter(|(_, wrapper)| wrapper.is_crate_entry_point)
+        .map(|(path_buf, _)| path_buf.as_path())
+        .collect::<Vec<&Path>>();
+    crate_roots.sort();
+
+    let mut module_entries = package_metrics
+        .rs_path_to_metrics
+        .iter()
+        .map(|(path_buf, wrapper)| {
+            let crate_root = find_crate_root(&crate_roots, path_buf);
+            let name = match crate_root {
+                Some(crate_root) => module_name(crate_root, path_buf),
+                None => {
+                    relative_path(package_root, path_buf).display().to_string()
+                }
+            };
+
+            let file_metrics = PackageMetrics {
+                rs_path_to_metrics: vec![(path_buf.clone(), wrapper.clone())]
+                    .into_iter()
+                    .collect(),
+            };
+            let mut unsafety = unsafe_stats(&file_metrics, rs_files_used);
+            // A module can only forbid unsafe code by declaring it itself,
+            // whether it is a crate entry point or not.
+            unsafety.forbids_unsafe = wrapper.metrics.forbids_unsafe;
+
+            ModuleEntry {
+                crate_root: crate_root.map(|p| relative_path(package_root, p)),
+                name,
+                path: path_buf.clone(),
+                unsafety,
+            }
+        })
+        .collect::<Vec<ModuleEntry>>();
+
+    module_entries.sort_by(|a, b| {
+        (&a.crate_root, &a.name).cmp(&(&b.crate_root, &b.name))
+    });
+    module_entries
+}
+
+/// The module path of the closest ancestor of `name` present in `names`, or
+/// `None` if `name` is a top level module.
+pub fn parent_module_name<'a>(
+    name: &str,
+    names: &HashSet<&'a str>,
+) -> Option<&'a str> {
+    let mut segments = name.split("::").collect::<Vec<&str>>();
+    while segments.len() > 1 {
+        segments.pop();
+        if let Some(parent) = names.get(segments.join("::").as_str()) {
+            return Some(parent);
+        }
+    }
+    None
+}
+
+pub fn find_crate_root<'a>(
+    crate_roots: &[&'a Path],
+    rs_file_path: &Path,
+) -> Option<&'a Path> {
+    if let Some(crate_root) = crate_roots.iter().find(|p| **p == rs_file_path) {
+        return Some(crate_root);
+    }
+    crate_roots
+        .iter()
+        .filter(|crate_root| {
+            matches!(
+                crate_root.parent(),
+                Some(dir) if rs_file_path.starts_with(dir)
+            )
+        })
+        // Prefer the most deeply nested crate root, then the first in order.
+        .min_by_key(|crate_root| {
+            (
+                std::cmp::Reverse(crate_root.components().count()),
+                **crate_root,
+            )
+        })
+        .copied()
+}
+
+fn module_name(crate_root: &Path, rs_file_path: &Path) -> String {
+    let mut segments = vec![String::from("crate")];
+    if crate_root == rs_file_path {
+        return segments.join("::");
+    }
+    let crate_dir = crate_root.parent().unwrap_or(crate_root);
+    let relative = relative_path(crate_dir, &rs_file_path.with_extension(""));
+    segments.extend(relative.components().filter_map(|c| match c {
+        Component::Normal(s) => Some(s.to_string_lossy().into_owned()),
+        _ => None,
+    }));
+    if segments.len() > 1 && segments.last().map(String::as_str) == Some("mod")
+    {
+        segments.pop();
+    }
+    segments.join("::")
+}
+
+fn relative_path(base: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(base).unwrap_or(path).to_path_buf()
+}
+
+#[cfg(test)]
+mod modules_tests {
+    use super::*;
+
+    use crate::scan::RsFileMetricsWrapper;
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+
+    #[rstest(
+        input_crate_root,
+        input_rs_file_path,
+        expected_module_name,
+        case("/p/src/lib.rs", "/p/src/lib.rs", "crate"),
+        case("/p/src/lib.rs", "/p/src/foo.rs", "crate::foo"),
+        case("/p/src/lib.rs", "/p/src/foo/mod.rs", "crate::foo"),
+        case("/p/src/lib.rs", "/p/src/foo/bar.rs", "crate::foo::bar"),
+        case("/p/src/main.rs", "/p/src/cli.rs", "crate::cli")
+    )]
+    fn module_name_test(
+        input_crate_root: &str,
+        input_rs_file_path: &str,
+        expected_module_name: &str,
+    ) {
+        assert_eq!(
+            module_name(
+                Path::new(input_crate_root),
+                Path::new(input_rs_file_path)
+            ),
+            expected_module_name
+        );
+    }
+
+    #[rstest]
+    fn module_stats_test() {
+        let package_metrics = PackageMetrics {
+            rs_path_to_metrics: vec![
+                ("/p/src/lib.rs", true, true, 0),
+                ("/p/src/foo.rs", false, false, 3),
+                ("/p/src/foo/bar.rs", false, true, 0),
+                ("/p/tests/it.rs", false, false, 1),
+            ]
+            .into_iter()
+            .map(|(path, is_crate_entry_point, forbids_unsafe, unsafe_)| {
+                let mut wrapper = RsFileMetricsWrapper {
+                    is_crate_entry_point,
+                    ..Default::default()
+                };
+                wrapper.metrics.forbids_unsafe = forbids_unsafe;
+                wrapper.metrics.counters.exprs = Count { safe: 1, unsafe_ };
+                (PathBuf::from(path), wrapper)
+            })
+            .collect(),
+        };
+        let rs_files_used = vec![
+            PathBuf::from("/p/src/lib.rs"),
+            PathBuf::from("/p/src/foo.rs"),
+        ]
+        .into_iter()
+        .collect();
+
+        let module_entries =
+            module_stats(Path::new("/p"), &package_metrics, &rs_files_used);
+
+        let summary = module_entries
+            .iter()
+            .map(|m| {
+                (
+                    m.crate_root.clone(),
+                    m.name.as_str(),
+                    m.unsafety.used.exprs.unsafe_,
+                    m.unsafety.unused.exprs.unsafe_,
+                    m.unsafety.forbids_unsafe,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec![
+                (None, "tests/it.rs", 0, 1, false),
+                (Some(PathBuf::from("src/lib.rs")), "crate", 0, 0, true),
+                (Some(PathBuf::from("src/lib.rs")), "crate::foo", 3, 0, false),
+                (
+                    Some(PathBuf::from("src/lib.rs")),
+                    "crate::foo::bar",
+                    0,
+                    0,
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[rstest(
+        input_name,
+        expected_parent,
+        case("crate", None),
+        case("crate::foo", Some("crate")),
+        case("crate::foo::bar", Some("crate::foo")),
+        case("crate::baz::qux", Some("crate"))
+    )]
+    fn parent_module_name_test(
+        input_name: &str,
+        expected_parent: Option<&str>,
+    ) {
+        let names = vec!["crate", "crate::foo", "crate::foo::bar"]
+            .into_iter()
+            .collect::<HashSet<&str>>();
+        assert_eq!(parent_module_name(input_name, &names), expected_parent);
+    }
+}
@@ -1,23 +1,26 @@
 mod custom_executor;
 
+pub use custom_executor::CompilationUnit;
+
 use custom_executor::{CustomExecutor, CustomExecutorInnerContext};
 
-use cargo::core::compiler::Executor;
+use super::ScanStatsCollector;
+
+use cargo::core::compiler::{CompileKind, Executor};
 use cargo::core::manifest::TargetKind;
-use cargo::core::Workspace;
+use cargo::core::{PackageId, Workspace};
 use cargo::ops;
 use cargo::ops::{CleanOptions, CompileOptions};
 use cargo::util::{interning::InternedString, CargoResult};
 use cargo::Config;
 use cargo_util::paths;
 use geiger::RsFileMetrics;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
-use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, PoisonError};
-use walkdir::{DirEntry, WalkDir};
+use walkdir::DirEntry;
 
 /// Provides information needed to scan for crate root
 /// `#![forbid(unsafe_code)]`.
@@ -59,16 +62,8 @@ pub enum RsResolveError {
     /// This is still way better than a panic though.
     Cargo(String),
 
-    /// Failed to parse a .dep file.
-    DepParse(String, PathBuf),
-
     /// Failed to get the inner context out of the mutex.
     InnerContextMutex(String),
-
-    /// Like io::Error but with the related path.
-    Io(io::Error, PathBuf),
-
-    Walkdir(walkdir::Error),
 }
 
 impl Error for RsResolveError {}
@@ -145,12 +140,27 @@ pub fn is_file_with_ext(entry: &DirEntry, file_ext: &str) -> bool {
     ext.to_string_lossy() == file_ext
 }
 
+/// The source files and packages used by a build.
+#[derive(Debug, Default)]
+pub struct RsFileDeps {
+    /// Canonicalized paths of all source files used by the build.
+    pub rs_files_used: HashSet<PathBuf>,
+
+    /// Packages with at least one unit compiled by the build.
+    pub compiled_package_ids: HashSet<PackageId>,
+
+    /// Canonicalized paths of the source files read by each unit compiled
+    /// by the build.
+    pub unit_rs_files: HashMap<CompilationUnit, HashSet<PathBuf>>,
+}
+
 /// Trigger a `cargo clean` + `cargo check` and listen to the cargo/rustc
 /// communication to figure out which source files were used by the build.
 pub fn resolve_rs_file_deps(
     compile_options: &CompileOptions,
+    stats: &ScanStatsCollector,
     workspace: &Workspace,
-) -> Result<HashSet<PathBuf>, RsResolveError> {
+) -> Result<RsFileDeps, RsResolveError> {
     let config = workspace.config();
     let (pkg_set, _) = ops::resolve_ws(workspace)
         .map_err(|e| RsResolveError::Cargo(e.to_string()))?;
@@ -161,82 +171,50 @@ pub fn resolve_rs_file_deps(
     // Need to run a cargo clean to identify all new .d deps files.
     // TODO: Figure out how this can be avoided to improve performance, clean
     // Rust builds are __slow__.
+    let requested_targets = compile_options
+        .build_config
+        .requested_kinds
+        .iter()
+        .filter_map(|compile_kind| match compile_kind {
+            CompileKind::Host => None,
+            CompileKind::Target(compile_target) => {
+                Some(compile_target.rustc_target().to_string())
+            }
+        })
+        .collect();
+    // Clean the profile the build is run with, a clean of the dev profile
+    // leaves the `.d` files of a release build in place.
+    let requested_profile = compile_options.build_config.requested_profile;
     let clean_options = CleanOptions {
         config,
         spec: packages,
-        targets: vec![],
-        profile_specified: false,
-        // A temporary hack to get cargo 0.43 to build, TODO: look closer at the updated cargo API
-        // later.
-        requested_profile: InternedString::new("dev"),
+        targets: requested_targets,
+        profile_specified: requested_profile != InternedString::new("dev"),
+        requested_profile,
         doc: false,
     };
 
-    ops::clean(workspace, &clean_options)
+    stats
+        .time_phase("clean", || ops::clean(workspace, &clean_options))
         .map_err(|e| RsResolveError::Cargo(e.to_string()))?;
 
     let inner_arc = Arc::new(Mutex::new(CustomExecutorInnerContext::default()));
-    {
-        compile_with_exec(
-            compile_options,
-            config,
-            inner_arc.clone(),
-            workspace,
-        )?;
-    }
+    stats.time_phase("build", || {
+        compile_with_exec(compile_options, config, inner_arc.clone(), workspace)
+    })?;
 
-    let workspace_root = workspace.root().to_path_buf();
     let inner_mutex =
         Arc::try_unwrap(inner_arc).map_err(|_| RsResolveError::ArcUnwrap())?;
-    let (rs_files, out_dir_args) = {
-        let ctx = inner_mutex.into_inner()?;
-        (ctx.rs_file_args, ctx.out_dir_args)
-    };
-    let mut path_buf_hash_set = HashSet::<PathBuf>::new();
-    for out_dir in out_dir_args {
-        // TODO: Figure out if the `.d` dep files are used by one or more rustc
-        // calls. It could be useful to know which `.d` dep files belong to
-        // which rustc call. That would allow associating each `.rs` file found
-        // in each dep file with a PackageId.
-        add_dir_entries_to_path_buf_hash_set(
-            out_dir,
-            &mut path_buf_hash_set,
-            workspace_root.clone(),
-        )?;
-    }
-    for path_buf in rs_files {
-        // rs_files must already be canonicalized
-        path_buf_hash_set.insert(path_buf);
-    }
-
-    Ok(path_buf_hash_set)
-}
-
-fn add_dir_entries_to_path_buf_hash_set(
-    out_dir: PathBuf,
-    path_buf_hash_set: &mut HashSet<PathBuf>,
-    workspace_root: PathBuf,
-) -> Result<(), RsResolveError> {
-    for entry in WalkDir::new(&out_dir) {
-        let entry = entry.map_err(RsResolveError::Walkdir)?;
-        if !is_file_with_ext(&entry, "d") {
-            continue;
-        }
-        let dependencies = parse_rustc_dep_info(entry.path()).map_err(|e| {
-            RsResolveError::DepParse(e.to_string(), entry.path().to_path_buf())
-        })?;
-        let canonical_paths = dependencies
-            .into_iter()
-            .flat_map(|(_, dependency_files)| dependency_files)
-            .map(PathBuf::from)
-            .map(|pb| workspace_root.join(pb))
-            .map(|pb| pb.canonicalize().map_err(|e| RsResolveError::Io(e, pb)));
-        for path_buf in canonical_paths {
-            path_buf_hash_set.insert(path_buf?);
-        }
-    }
-
-    Ok(())
+    let unit_rs_files = inner_mutex.into_inner()?.unit_rs_files;
+
+    Ok(RsFileDeps {
+        rs_files_used: unit_rs_files.values().flatten().cloned().collect(),
+        compiled_package_ids: unit_rs_files
+            .keys()
+            .map(|compilation_unit| compilation_unit.package_id)
+            .collect(),
+        unit_rs_files,
+    })
 }
 
 fn compile_with_exec(
@@ -297,6 +275,7 @@ fn parse_rustc_dep_info(
 mod rs_file_tests {
     use super::*;
     use rstest::*;
+    use walkdir::WalkDir;
 
     #[rstest(
         input_rs_file,
@@ -1,13 +1,15 @@
+use super::parse_rustc_dep_info;
+
 use cargo::core::compiler::{CompileMode, Executor, Unit};
 use cargo::core::{PackageId, Target};
 use cargo::util::CargoResult;
 use cargo_util::ProcessBuilder;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::ffi::OsString;
 use std::fmt;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 /// A cargo Executor to intercept all build tasks and store all ".rs" file
@@ -25,8 +27,18 @@ pub struct CustomExecutor {
     pub inner_ctx: Arc<Mutex<CustomExecutorInnerContext>>,
 }
 
+/// A single rustc invocation made by the build.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CompilationUnit {
+    pub package_id: PackageId,
+    pub target: Target,
+    pub mode: CompileMode,
+}
+
 #[derive(Debug)]
 enum CustomExecutorError {
+    CrateNameMissing(String),
+    DepParse(String, PathBuf),
     InnerContextMutex(String),
     Io(io::Error, PathBuf),
     OutDirKeyMissing(String),
@@ -39,25 +51,14 @@ impl Executor for CustomExecutor {
     fn exec(
         &self,
         cmd: &ProcessBuilder,
-        _id: PackageId,
-        _target: &Target,
-        _mode: CompileMode,
+        id: PackageId,
+        target: &Target,
+        mode: CompileMode,
         _on_stdout_line: &mut dyn FnMut(&str) -> CargoResult<()>,
         _on_stderr_line: &mut dyn FnMut(&str) -> CargoResult<()>,
     ) -> CargoResult<()> {
-        let mut args = cmd.get_args();
-        let out_dir_key = OsString::from("--out-dir");
-
-        args.position(|s| *s == out_dir_key).ok_or_else(|| {
-            CustomExecutorError::OutDirKeyMissing(cmd.to_string())
-        })?;
-
-        let out_dir = args
-            .next()
-            .ok_or_else(|| {
-                CustomExecutorError::OutDirValueMissing(cmd.to_string())
-            })
-            .map(PathBuf::from)?;
+        let args = cmd.get_args().collect::<Vec<&OsString>>();
+        let dep_info_path = dep_info_path(&args, cmd)?;
 
         // This can be different from the cwd used to launch the wrapping cargo
         // plugin. Discovered while fixing
@@ -67,24 +68,40 @@ impl Executor for CustomExecutor {
             .map(PathBuf::from)
             .unwrap_or_else(|| self.cwd.to_owned());
 
+        let mut rs_files = HashSet::<PathBuf>::new();
+        for arg_name in args
+            .iter()
+            .filter(|s| s.to_string_lossy().to_lowercase().ends_with(".rs"))
         {
-            // Scope to drop and release the mutex before calling rustc.
-            let mut ctx = self.inner_ctx.lock().map_err(|e| {
-                CustomExecutorError::InnerContextMutex(e.to_string())
-            })?;
-            for (arg_name, _) in args
-                .map(|s| (s, s.to_string_lossy().to_lowercase()))
-                .filter(|(_, arg_value)| arg_value.ends_with(".rs"))
-            {
-                let raw_path = cwd.join(arg_name);
-                let path = raw_path
-                    .canonicalize()
-                    .map_err(|e| CustomExecutorError::Io(e, raw_path))?;
-                ctx.rs_file_args.insert(path);
-            }
-            ctx.out_dir_args.insert(out_dir);
+            rs_files.insert(canonicalize(&cwd, Path::new(arg_name))?);
         }
+
         cmd.exec()?;
+
+        // The dep-info file is written by the rustc call above, so it lists
+        // exactly the files read for this unit.
+        let dependencies =
+            parse_rustc_dep_info(&dep_info_path).map_err(|e| {
+                CustomExecutorError::DepParse(e.to_string(), dep_info_path)
+            })?;
+        for dependency_file in dependencies
+            .into_iter()
+            .flat_map(|(_, dependency_files)| dependency_files)
+        {
+            rs_files.insert(canonicalize(&cwd, Path::new(&dependency_file))?);
+        }
+
+        let mut ctx = self.inner_ctx.lock().map_err(|e| {
+            CustomExecutorError::InnerContextMutex(e.to_string())
+        })?;
+        ctx.unit_rs_files
+            .entry(CompilationUnit {
+                package_id: id,
+                target: target.clone(),
+                mode,
+            })
+            .or_default()
+            .extend(rs_files);
         Ok(())
     }
 
@@ -95,10 +112,22 @@ impl Executor for CustomExecutor {
     }
 }
 
-/// Forward Display to Debug. See the crate root documentation.
+/// Forward Display to Debug, except for the errors naming the rustc call or
+/// the dep-info file they failed on. See the crate root documentation.
 impl fmt::Display for CustomExecutorError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
+        match self {
+            CustomExecutorError::CrateNameMissing(cmd) => {
+                write!(f, "No --crate-name argument in rustc call: {}", cmd)
+            }
+            CustomExecutorError::DepParse(message, dep_info_path) => write!(
+                f,
+                "Failed to parse dep-info file {}: {}",
+                dep_info_path.display(),
+                message
+            ),
+            _ => fmt::Debug::fmt(self, f),
+        }
     }
 }
 
@@ -106,10 +135,109 @@ impl Error for CustomExecutorError {}
 
 #[derive(Debug, Default)]
 pub struct CustomExecutorInnerContext {
-    /// Stores all lib.rs, main.rs etc. passed to rustc during the build.
-    pub rs_file_args: HashSet<PathBuf>,
+    /// Stores the source files read by each unit compiled by the build,
+    /// including the lib.rs, main.rs etc. passed to rustc.
+    pub unit_rs_files: HashMap<CompilationUnit, HashSet<PathBuf>>,
+}
+
+fn canonicalize(
+    cwd: &Path,
+    path: &Path,
+) -> Result<PathBuf, CustomExecutorError> {
+    let raw_path = cwd.join(path);
+    raw_path
+        .canonicalize()
+        .map_err(|e| CustomExecutorError::Io(e, raw_path))
+}
+
+/// rustc writes the dep-info file of a unit to
+/// `<out-dir>/<crate-name><extra-filename>.d`.
+fn dep_info_path(
+    args: &[&OsString],
+    cmd: &ProcessBuilder,
+) -> Result<PathBuf, CustomExecutorError> {
+    let out_dir = match argument_value(args, "--out-dir") {
+        Some(Some(out_dir)) => PathBuf::from(out_dir),
+        Some(None) => {
+            return Err(CustomExecutorError::OutDirValueMissing(
+                cmd.to_string(),
+            ))
+        }
+        None => {
+            return Err(CustomExecutorError::OutDirKeyMissing(cmd.to_string()))
+        }
+    };
+    let crate_name = argument_value(args, "--crate-name")
+        .flatten()
+        .ok_or_else(|| CustomExecutorError::CrateNameMissing(cmd.to_string()))?
+        .to_string_lossy()
+        .into_owned();
+    let extra_filename = args
+        .windows(2)
+        .filter(|window| *window[0] == "-C")
+        .find_map(|window| {
+            window[1]
+                .to_string_lossy()
+                .strip_prefix("extra-filename=")
+                .map(String::from)
+        })
+        .unwrap_or_default();
 
-    /// Investigate if this needs to be intercepted like this or if it can be
-    /// looked up in a nicer way.
-    pub out_dir_args: HashSet<PathBuf>,
+    Ok(out_dir.join(format!("{}{}.d", crate_name, extra_filename)))
+}
+
+/// `None` if the argument is missing, `Some(None)` if it has no value.
+fn argument_value<'a>(
+    args: &[&'a OsString],
+    name: &str,
+) -> Option<Option<&'a OsString>> {
+    args.iter()
+        .position(|arg| *arg == name)
+        .map(|position| args.get(position + 1).copied())
+}
+
+#[cfg(test)]
+mod custom_executor_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_args,
+        expected_dep_info_path,
+        case(
+            vec![
+                "--crate-name",
+                "geiger",
+                "src/lib.rs",
+                "-C",
+                "extra-filename=-0123456789abcdef",
+                "--out-dir",
+                "/target/debug/deps",
+            ],
+            Some(PathBuf::from("/target/debug/deps/geiger-0123456789abcdef.d"))
+        ),
+        case(
+            vec![
+                "--crate-name",
+                "build_script_build",
+                "build.rs",
+                "--out-dir",
+                "/target/debug/build/geiger",
+            ],
+            Some(PathBuf::from("/target/debug/build/geiger/build_script_build.d"))
+        ),
+        case(vec!["--crate-name", "geiger", "--out-dir"], None),
+        case(vec!["--out-dir", "/target/debug/deps"], None)
+    )]
+    fn dep_info_path_test(
+        input_args: Vec<&str>,
+        expected_dep_info_path: Option<PathBuf>,
+    ) {
+        let mut cmd = ProcessBuilder::new("rustc");
+        cmd.args(&input_args);
+        let args = cmd.get_args().collect::<Vec<&OsString>>();
+
+        assert_eq!(dep_info_path(&args, &cmd).ok(), expected_dep_info_path);
+    }
 }
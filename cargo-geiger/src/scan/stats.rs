@@ -0,0 +1,160 @@
+use super::GeigerContext;
+
+use cargo_geiger_serde::{PhaseDuration, ScanStats};
+use std::fs;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Collects the time spent in each phase of a scan and the amount of code
+/// scanned, shared by all phases of a single run.
+#[derive(Debug, Default)]
+pub struct ScanStatsCollector {
+    stats: Mutex<ScanStats>,
+}
+
+impl ScanStatsCollector {
+    /// Runs `phase_fn` and records its wall clock time under `phase`.
+    pub fn time_phase<T, F: FnOnce() -> T>(
+        &self,
+        phase: &str,
+        phase_fn: F,
+    ) -> T {
+        let start = Instant::now();
+        let result = phase_fn();
+        self.stats.lock().unwrap().phases.push(PhaseDuration {
+            phase: String::from(phase),
+            duration_ms: start.elapsed().as_millis() as u64,
+        });
+        result
+    }
+
+    /// Records the packages and files scanned into `geiger_context`.
+    pub fn record_scanned(&self, geiger_context: &GeigerContext) {
+        let mut stats = self.stats.lock().unwrap();
+        for package_metrics in geiger_context.package_id_to_metrics.values() {
+            stats.packages_scanned += 1;
+            for path_buf in package_metrics.rs_path_to_metrics.keys() {
+                stats.files_scanned += 1;
+                stats.bytes_scanned += fs::metadata(path_buf)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or_default();
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ScanStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+/// A summary of `stats` for humans, one line per phase followed by totals.
+pub fn construct_stats_lines(stats: &ScanStats) -> Vec<String> {
+    let mut lines = stats
+        .phases
+        .iter()
+        .map(|phase_duration| {
+            format!(
+                "Phase {}: {} ms",
+                phase_duration.phase, phase_duration.duration_ms
+            )
+        })
+        .collect::<Vec<String>>();
+    lines.push(format!(
+        "Scanned {} files ({} bytes) in {} packages",
+        stats.files_scanned, stats.bytes_scanned, stats.packages_scanned
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use super::*;
+
+    use crate::scan::{PackageMetrics, RsFileMetricsWrapper};
+
+    use cargo_metadata::PackageId;
+    use rstest::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[rstest]
+    fn time_phase_test() {
+        let collector = ScanStatsCollector::default();
+
+        let result = collector.time_phase("metadata", || 42);
+        collector.time_phase("scan", || ());
+
+        assert_eq!(result, 42);
+        assert_eq!(
+            collector
+                .stats()
+                .phases
+                .iter()
+                .map(|phase_duration| phase_duration.phase.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["metadata", "scan"]
+        );
+    }
+
+    #[rstest]
+    fn record_scanned_test() {
+        let temp_dir = tempdir().unwrap();
+        let lib_path = temp_dir.path().join("lib.rs");
+        let mod_path = temp_dir.path().join("mod.rs");
+        fs::write(&lib_path, "mod a;").unwrap();
+        fs::write(&mod_path, "fn a() {}").unwrap();
+
+        let rs_path_to_metrics = vec![lib_path, mod_path]
+            .into_iter()
+            .map(|path_buf| (path_buf, RsFileMetricsWrapper::default()))
+            .collect::<HashMap<PathBuf, RsFileMetricsWrapper>>();
+        let geiger_context = GeigerContext {
+            package_id_to_metrics: vec![(
+                PackageId {
+                    repr: String::from("a 1.0.0"),
+                },
+                PackageMetrics { rs_path_to_metrics },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let collector = ScanStatsCollector::default();
+        collector.record_scanned(&geiger_context);
+        let stats = collector.stats();
+
+        assert_eq!(stats.packages_scanned, 1);
+        assert_eq!(stats.files_scanned, 2);
+        assert_eq!(stats.bytes_scanned, 15);
+    }
+
+    #[rstest]
+    fn construct_stats_lines_test() {
+        let stats = ScanStats {
+            phases: vec![
+                PhaseDuration {
+                    phase: String::from("build"),
+                    duration_ms: 1200,
+                },
+                PhaseDuration {
+                    phase: String::from("scan"),
+                    duration_ms: 300,
+                },
+            ],
+            packages_scanned: 3,
+            files_scanned: 12,
+            bytes_scanned: 4096,
+        };
+
+        assert_eq!(
+            construct_stats_lines(&stats),
+            vec![
+                String::from("Phase build: 1200 ms"),
+                String::from("Phase scan: 300 ms"),
+                String::from("Scanned 12 files (4096 bytes) in 3 packages"),
+            ]
+        );
+    }
+}
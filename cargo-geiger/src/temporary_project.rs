@@ -0,0 +1,259 @@
+use crate::args::FeaturesArgs;
+
+use cargo::util::CargoResult;
+use cargo_metadata::semver::Version;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tempfile::TempDir;
+
+const TEMPORARY_PACKAGE_NAME: &str = "cargo-geiger-temporary-project";
+
+/// A published crate to be scanned, given as `name@version` on the command
+/// line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CrateSpec {
+    pub name: String,
+    pub version: String,
+}
+
+impl CrateSpec {
+    /// The package id specification used to select the crate as the root of
+    /// the scan, once the temporary project has been resolved.
+    pub fn to_package_spec(&self) -> String {
+        format!("{}:{}", self.name, self.version)
+    }
+}
+
+impl fmt::Display for CrateSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+impl FromStr for CrateSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, version) = match s.split_once('@') {
+            Some((name, version))
+                if !name.is_empty() && !version.is_empty() =>
+            {
+                (name, version)
+            }
+            _ => {
+                return Err(format!(
+                    "Invalid crate `{}`, expected the form `name@version`",
+                    s
+                ))
+            }
+        };
+        // The name of a published crate, which is written into the manifest
+        // of the temporary project as is.
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic())
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "Invalid crate name `{}`, expected ASCII letters, digits, `-` and `_`, starting with a letter",
+                name
+            ));
+        }
+        if Version::parse(version).is_err() {
+            return Err(format!(
+                "Invalid crate version `{}`, expected a version like `1.0.0`",
+                version
+            ));
+        }
+
+        Ok(CrateSpec {
+            name: name.to_string(),
+            version: version.to_string(),
+        })
+    }
+}
+
+/// A throwaway cargo project depending on exactly one version of a published
+/// crate. The directory is removed when this is dropped, so it has to be kept
+/// alive for as long as the scan runs.
+pub struct TemporaryProject {
+    pub manifest_path: PathBuf,
+    _temp_dir: TempDir,
+}
+
+/// Creates a project depending on the exact version in `crate_spec`, with
+/// the features in `features_args` applied to that dependency.
+pub fn create_temporary_project(
+    crate_spec: &CrateSpec,
+    features_args: &FeaturesArgs,
+) -> CargoResult<TemporaryProject> {
+    if features_args.all_features {
+        anyhow::bail!("`--all-features` is not supported together with `--crate`, list the features with `--features` instead");
+    }
+
+    let temp_dir =
+        tempfile::Builder::new().prefix("cargo-geiger-").tempdir()?;
+    let manifest_path = temp_dir.path().join("Cargo.toml");
+
+    fs::create_dir(temp_dir.path().join("src"))?;
+    fs::write(temp_dir.path().join("src").join("lib.rs"), "")?;
+    fs::write(
+        &manifest_path,
+        construct_manifest(crate_spec, features_args),
+    )?;
+
+    Ok(TemporaryProject {
+        manifest_path,
+        _temp_dir: temp_dir,
+    })
+}
+
+fn construct_manifest(
+    crate_spec: &CrateSpec,
+    features_args: &FeaturesArgs,
+) -> String {
+    let features = features_args
+        .features
+        .iter()
+        .map(|feature| format!("{:?}", feature))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    format!(
+        "[package]
+name = \"{}\"
+version = \"0.0.0\"
+edition = \"2018\"
+publish = false
+
+[dependencies]
+{} = {{ version = \"={}\", default-features = {}, features = [{}] }}
+
+[workspace]
+",
+        TEMPORARY_PACKAGE_NAME,
+        crate_spec.name,
+        crate_spec.version,
+        !features_args.no_default_features,
+        features
+    )
+}
+
+#[cfg(test)]
+mod temporary_project_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_crate_spec,
+        expected_crate_spec_result,
+        case(
+            "regex@1.7.3",
+            Ok(CrateSpec {
+                name: String::from("regex"),
+                version: String::from("1.7.3")
+            })
+        ),
+        case(
+            "regex",
+            Err(String::from(
+                "Invalid crate `regex`, expected the form `name@version`"
+            ))
+        ),
+        case(
+            "@1.7.3",
+            Err(String::from(
+                "Invalid crate `@1.7.3`, expected the form `name@version`"
+            ))
+        ),
+        case(
+            "regex = \"1\"\n[x]@1.7.3",
+            Err(String::from(
+                "Invalid crate name `regex = \"1\"\n[x]`, expected ASCII letters, digits, `-` and `_`, starting with a letter"
+            ))
+        ),
+        case(
+            "1regex@1.7.3",
+            Err(String::from(
+                "Invalid crate name `1regex`, expected ASCII letters, digits, `-` and `_`, starting with a letter"
+            ))
+        ),
+        case(
+            "regex@1.7.3\"",
+            Err(String::from(
+                "Invalid crate version `1.7.3\"`, expected a version like `1.0.0`"
+            ))
+        )
+    )]
+    fn crate_spec_from_str_test(
+        input_crate_spec: &str,
+        expected_crate_spec_result: Result<CrateSpec, String>,
+    ) {
+        assert_eq!(
+            CrateSpec::from_str(input_crate_spec),
+            expected_crate_spec_result
+        );
+    }
+
+    #[rstest(
+        input_features,
+        input_no_default_features,
+        expected_dependency_line,
+        case(
+            vec![],
+            false,
+            "regex = { version = \"=1.7.3\", default-features = true, features = [] }"
+        ),
+        case(
+            vec![String::from("std"), String::from("unicode")],
+            true,
+            "regex = { version = \"=1.7.3\", default-features = false, features = [\"std\", \"unicode\"] }"
+        )
+    )]
+    fn construct_manifest_test(
+        input_features: Vec<String>,
+        input_no_default_features: bool,
+        expected_dependency_line: &str,
+    ) {
+        let crate_spec = CrateSpec::from_str("regex@1.7.3").unwrap();
+        let features_args = FeaturesArgs {
+            all_features: false,
+            features: input_features,
+            no_default_features: input_no_default_features,
+        };
+
+        let manifest = construct_manifest(&crate_spec, &features_args);
+
+        assert!(manifest.contains(expected_dependency_line));
+        assert!(manifest.contains("[workspace]"));
+    }
+
+    #[rstest]
+    fn create_temporary_project_test() {
+        let crate_spec = CrateSpec::from_str("regex@1.7.3").unwrap();
+        let temporary_project =
+            create_temporary_project(&crate_spec, &FeaturesArgs::default())
+                .unwrap();
+
+        assert!(temporary_project.manifest_path.exists());
+
+        let manifest_path = temporary_project.manifest_path.clone();
+        drop(temporary_project);
+
+        assert!(!manifest_path.exists());
+    }
+
+    #[rstest]
+    fn create_temporary_project_all_features_test() {
+        let crate_spec = CrateSpec::from_str("regex@1.7.3").unwrap();
+        let features_args = FeaturesArgs {
+            all_features: true,
+            ..Default::default()
+        };
+
+        assert!(create_temporary_project(&crate_spec, &features_args).is_err());
+    }
+}
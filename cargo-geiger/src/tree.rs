@@ -9,16 +9,33 @@ use cargo_metadata::{DependencyKind, PackageId};
 /// dependency graph traversal.
 #[derive(Debug, Eq, PartialEq)]
 pub enum TextTreeLine {
-    /// A text line for a package
-    Package { id: PackageId, tree_vines: String },
+    /// A text line for a package, `depth` is 0 for the root package.
+    /// `edge_conditions` are the selected conditions of the dependency
+    /// leading to the package.
+    Package {
+        id: PackageId,
+        tree_vines: String,
+        depth: usize,
+        edge_conditions: Vec<String>,
+    },
     /// There are extra dependencies coming and we should print a group header,
-    /// eg. "[build-dependencies]".
+    /// eg. "[build-dependencies]". `depth` is the depth of those dependencies.
     ExtraDepsGroup {
         kind: DependencyKind,
         tree_vines: String,
+        depth: usize,
     },
 }
 
+impl TextTreeLine {
+    pub fn depth(&self) -> usize {
+        match self {
+            TextTreeLine::Package { depth, .. }
+            | TextTreeLine::ExtraDepsGroup { depth, .. } => *depth,
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct TreeSymbols {
     pub down: &'static str,
@@ -27,7 +44,7 @@ pub struct TreeSymbols {
     pub right: &'static str,
 }
 
-fn construct_tree_vines_string(
+pub fn construct_tree_vines_string(
     levels_continue: &mut [bool],
     print_config: &PrintConfig,
 ) -> String {
@@ -133,6 +150,8 @@ mod tree_tests {
             prefix,
             format: pattern,
             allow_partial_results: false,
+            depth: None,
+            edge_annotations: vec![],
             include_tests: IncludeTests::Yes,
             output_format: OutputFormat::Ascii,
         }
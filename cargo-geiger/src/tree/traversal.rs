@@ -42,10 +42,20 @@ pub fn walk_dependency_tree(
         visited_deps: &mut visited_deps,
     };
 
-    let node = &graph.graph[graph.nodes[&root_package_id]];
+    let node = match graph.nodes.get(&root_package_id) {
+        Some(root_index) => &graph.graph[*root_index],
+        None => {
+            eprintln!(
+                "WARNING: Root package is not in the dependency graph: {}",
+                root_package_id
+            );
+            return vec![];
+        }
+    };
     walk_dependency_node(
         cargo_metadata_parameters,
         node,
+        None,
         &mut walk_dependency_parameters,
     )
 }
@@ -1,4 +1,5 @@
 use crate::format::print_config::Prefix;
+use crate::graph::DependencyEdge;
 use crate::mapping::CargoMetadataParameters;
 use crate::tree::traversal::WalkDependencyParameters;
 use crate::tree::{get_tree_symbols, TextTreeLine, TreeSymbols};
@@ -13,7 +14,7 @@ use std::slice::Iter;
 pub fn walk_dependency_kind(
     cargo_metadata_parameters: &CargoMetadataParameters,
     dep_kind: DependencyKind,
-    deps: &mut [PackageId],
+    deps: &mut [(PackageId, DependencyEdge)],
     walk_dependency_parameters: &mut WalkDependencyParameters,
 ) -> Vec<TextTreeLine> {
     if deps.is_empty() {
@@ -21,7 +22,7 @@ pub fn walk_dependency_kind(
     }
 
     // Resolve uses Hash data types internally but we want consistent output ordering
-    deps.sort_by_key(|n| n.clone());
+    deps.sort_by(|(a, _), (b, _)| a.cmp(b));
 
     let tree_symbols =
         get_tree_symbols(walk_dependency_parameters.print_config.output_format);
@@ -36,10 +37,11 @@ pub fn walk_dependency_kind(
     }
 
     let mut node_iterator = deps.iter().peekable();
-    while let Some(dependency) = node_iterator.next() {
+    while let Some((dependency, dependency_edge)) = node_iterator.next() {
         handle_walk_dependency_node(
             cargo_metadata_parameters,
             dependency,
+            dependency_edge,
             &mut node_iterator,
             &mut text_tree_lines,
             walk_dependency_parameters,
@@ -51,7 +53,8 @@ pub fn walk_dependency_kind(
 fn handle_walk_dependency_node(
     cargo_metadata_parameters: &CargoMetadataParameters,
     dependency: &PackageId,
-    node_iterator: &mut Peekable<Iter<PackageId>>,
+    dependency_edge: &DependencyEdge,
+    node_iterator: &mut Peekable<Iter<(PackageId, DependencyEdge)>>,
     text_tree_lines: &mut Vec<TextTreeLine>,
     walk_dependency_parameters: &mut WalkDependencyParameters,
 ) {
@@ -61,6 +64,7 @@ fn handle_walk_dependency_node(
     text_tree_lines.append(&mut walk_dependency_node(
         cargo_metadata_parameters,
         dependency,
+        Some(dependency_edge),
         walk_dependency_parameters,
     ));
     walk_dependency_parameters.levels_continue.pop();
@@ -83,6 +87,7 @@ fn push_extra_deps_group_text_tree_line_for_non_normal_dependencies(
             text_tree_lines.push(TextTreeLine::ExtraDepsGroup {
                 kind: dep_kind,
                 tree_vines,
+                depth: levels_continue.len() + 1,
             });
         }
     }
@@ -107,7 +112,8 @@ mod traversal_tests {
             vec![
                 ExtraDepsGroup {
                     kind: DependencyKind::Build,
-                    tree_vines: String::from("")
+                    tree_vines: String::from(""),
+                    depth: 1
                 }
             ]
         ),
@@ -120,7 +126,8 @@ mod traversal_tests {
                     tree_vines: format!(
                     "    {}   ",
                     get_tree_symbols(OutputFormat::Utf8).down
-                    )
+                    ),
+                    depth: 3
                 }
             ]
         ),
@@ -133,7 +140,8 @@ mod traversal_tests {
                     tree_vines: format!(
                     "{}   ",
                     get_tree_symbols(OutputFormat::Utf8).down
-                    )
+                    ),
+                    depth: 2
                 }
             ]
         ),
@@ -143,7 +151,8 @@ mod traversal_tests {
             vec![
                 ExtraDepsGroup {
                     kind: DependencyKind::Development,
-                    tree_vines: String::from("    ")
+                    tree_vines: String::from("    "),
+                    depth: 2
                 }
             ]
         ),
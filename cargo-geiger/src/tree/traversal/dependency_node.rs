@@ -1,5 +1,6 @@
+use crate::format::get_edge_conditions;
 use crate::format::print_config::PrintConfig;
-use crate::graph::Graph;
+use crate::graph::{DependencyEdge, Graph};
 use crate::mapping::CargoMetadataParameters;
 use crate::tree::traversal::WalkDependencyParameters;
 use crate::tree::TextTreeLine;
@@ -15,6 +16,7 @@ use std::collections::HashMap;
 pub fn walk_dependency_node(
     cargo_metadata_parameters: &CargoMetadataParameters,
     package: &PackageId,
+    dependency_edge: Option<&DependencyEdge>,
     walk_dependency_parameters: &mut WalkDependencyParameters,
 ) -> Vec<TextTreeLine> {
     let new = walk_dependency_parameters.print_config.all
@@ -29,6 +31,15 @@ pub fn walk_dependency_node(
     let mut all_out_text_tree_lines = vec![TextTreeLine::Package {
         id: package.clone(),
         tree_vines,
+        depth: walk_dependency_parameters.levels_continue.len(),
+        edge_conditions: dependency_edge
+            .map(|dependency_edge| {
+                get_edge_conditions(
+                    dependency_edge,
+                    &walk_dependency_parameters.print_config.edge_annotations,
+                )
+            })
+            .unwrap_or_default(),
     }];
 
     if !new {
@@ -59,8 +70,11 @@ fn construct_dependency_type_nodes_hashmap<'a>(
     graph: &'a Graph,
     package: &PackageId,
     print_config: &PrintConfig,
-) -> HashMap<DependencyKind, Vec<PackageId>> {
-    let mut dependency_type_nodes: HashMap<DependencyKind, Vec<PackageId>> = [
+) -> HashMap<DependencyKind, Vec<(PackageId, DependencyEdge)>> {
+    let mut dependency_type_nodes: HashMap<
+        DependencyKind,
+        Vec<(PackageId, DependencyEdge)>,
+    > = [
         (DependencyKind::Build, vec![]),
         (DependencyKind::Development, vec![]),
         (DependencyKind::Normal, vec![]),
@@ -79,9 +93,9 @@ fn construct_dependency_type_nodes_hashmap<'a>(
         };
 
         dependency_type_nodes
-            .get_mut(edge.weight())
+            .get_mut(&edge.weight().kind)
             .unwrap()
-            .push(dependency.clone());
+            .push((dependency.clone(), edge.weight().clone()));
     }
 
     dependency_type_nodes
@@ -142,7 +156,7 @@ mod dependency_node_tests {
         expected_normal_nodes_length: usize,
     ) {
         let mut inner_graph =
-            petgraph::Graph::<PackageId, cargo_metadata::DependencyKind>::new();
+            petgraph::Graph::<PackageId, DependencyEdge>::new();
         let mut nodes = HashMap::<PackageId, NodeIndex>::new();
 
         let package_ids = create_cargo_metadata_package_id_vec(7);
@@ -190,7 +204,7 @@ mod dependency_node_tests {
 
     fn add_edges_to_graph(
         directed_edges: &[(usize, usize, DependencyKind)],
-        graph: &mut petgraph::Graph<PackageId, DependencyKind>,
+        graph: &mut petgraph::Graph<PackageId, DependencyEdge>,
         nodes: &HashMap<PackageId, NodeIndex>,
         package_ids: &[PackageId],
     ) {
@@ -198,7 +212,7 @@ mod dependency_node_tests {
             graph.add_edge(
                 nodes[&package_ids[*source_index]],
                 nodes[&package_ids[*target_index]],
-                *dep_kind,
+                (*dep_kind).into(),
             );
         }
     }
@@ -215,7 +229,9 @@ mod dependency_node_tests {
         PrintConfig {
             all: false,
             allow_partial_results: false,
+            depth: None,
             direction: edge_direction,
+            edge_annotations: vec![],
             format: Pattern::new(vec![]),
             include_tests: IncludeTests::Yes,
             prefix: Prefix::Depth,
@@ -41,6 +41,8 @@ pub fn ref_slice_safety_report() -> SafetyReport {
             },
             ..Default::default()
         },
+        modules: None,
+        targets: None,
     };
     single_entry_safety_report(entry)
 }
@@ -78,6 +80,8 @@ pub fn either_safety_report() -> SafetyReport {
             },
             ..Default::default()
         },
+        modules: None,
+        targets: None,
     };
     single_entry_safety_report(entry)
 }
@@ -107,6 +111,8 @@ pub fn doc_comment_safety_report() -> SafetyReport {
             },
             ..Default::default()
         },
+        modules: None,
+        targets: None,
     };
     single_entry_safety_report(entry)
 }
@@ -176,6 +182,8 @@ pub fn itertools_safety_report() -> SafetyReport {
             },
             ..Default::default()
         },
+        modules: None,
+        targets: None,
     };
     let mut report = single_entry_safety_report(entry);
     merge_test_reports(&mut report, either_safety_report());
@@ -194,6 +202,8 @@ pub fn cfg_if_safety_report() -> SafetyReport {
     let entry = ReportEntry {
         package: PackageInfo::new(cfg_if_package_id()),
         unsafety: Default::default(),
+        modules: None,
+        targets: None,
     };
     single_entry_safety_report(entry)
 }
@@ -249,6 +259,8 @@ pub fn generational_arena_safety_report() -> SafetyReport {
             },
             forbids_unsafe: true,
         },
+        modules: None,
+        targets: None,
     };
     let mut report = single_entry_safety_report(entry);
     merge_test_reports(&mut report, cfg_if_safety_report());
@@ -298,6 +310,8 @@ pub fn idna_safety_report() -> SafetyReport {
             },
             ..Default::default()
         },
+        modules: None,
+        targets: None,
     };
     let mut report = single_entry_safety_report(entry);
     merge_test_reports(&mut report, matches_safety_report());
@@ -318,6 +332,8 @@ pub fn matches_safety_report() -> SafetyReport {
     let entry = ReportEntry {
         package: PackageInfo::new(matches_package_id()),
         unsafety: Default::default(),
+        modules: None,
+        targets: None,
     };
     single_entry_safety_report(entry)
 }
@@ -380,6 +396,8 @@ pub fn smallvec_safety_report() -> SafetyReport {
             },
             ..Default::default()
         },
+        modules: None,
+        targets: None,
     };
     single_entry_safety_report(entry)
 }
@@ -421,6 +439,8 @@ pub fn unicode_bidi_safety_report() -> SafetyReport {
             forbids_unsafe: true,
             ..Default::default()
         },
+        modules: None,
+        targets: None,
     };
     let mut report = single_entry_safety_report(entry);
     merge_test_reports(&mut report, matches_safety_report());
@@ -477,6 +497,8 @@ pub(super) fn unicode_normalization_safety_report() -> SafetyReport {
             },
             ..Default::default()
         },
+        modules: None,
+        targets: None,
     };
     let mut report = single_entry_safety_report(entry);
     merge_test_reports(&mut report, smallvec_safety_report());
@@ -507,6 +529,8 @@ pub fn num_cpus_safety_report(cx: &Context) -> SafetyReport {
             },
             ..Default::default()
         },
+        modules: None,
+        targets: None,
     };
     let mut report = single_entry_safety_report(entry);
     merge_test_reports(&mut report, super::Test1.expected_report(cx));
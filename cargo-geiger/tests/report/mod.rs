@@ -32,6 +32,7 @@ pub fn merge_test_reports(report: &mut SafetyReport, other: SafetyReport) {
     report
         .used_but_not_scanned_files
         .extend(other.used_but_not_scanned_files);
+    report.scan_errors.extend(other.scan_errors);
 }
 
 pub fn to_quick_report(report: SafetyReport) -> QuickSafetyReport {
@@ -49,6 +50,8 @@ pub fn to_quick_report(report: SafetyReport) -> QuickSafetyReport {
     QuickSafetyReport {
         packages: entries,
         packages_without_metrics: report.packages_without_metrics,
+        scan_errors: report.scan_errors,
+        stats: report.stats,
     }
 }
 
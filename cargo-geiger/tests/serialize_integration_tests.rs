@@ -106,6 +106,8 @@ impl IntegrationTest for Test1 {
                 },
                 ..Default::default()
             },
+            modules: None,
+            targets: None,
         }
     }
 }
@@ -149,6 +151,8 @@ impl IntegrationTest for Test2 {
                 },
                 ..Default::default()
             },
+            modules: None,
+            targets: None,
         }
     }
 }
@@ -197,6 +201,8 @@ impl IntegrationTest for Test3 {
                 },
                 ..Default::default()
             },
+            modules: None,
+            targets: None,
         }
     }
 }
@@ -244,6 +250,8 @@ impl IntegrationTest for Test4 {
                 },
                 ..Default::default()
             },
+            modules: None,
+            targets: None,
         }
     }
 }
@@ -291,6 +299,8 @@ impl IntegrationTest for Test6 {
                 forbids_unsafe: true,
                 ..Default::default()
             },
+            modules: None,
+            targets: None,
         }
     }
 }
@@ -333,6 +343,8 @@ impl IntegrationTest for Test7 {
                 forbids_unsafe: true,
                 ..Default::default()
             },
+            modules: None,
+            targets: None,
         }
     }
 }
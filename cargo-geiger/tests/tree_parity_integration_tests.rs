@@ -0,0 +1,208 @@
+#![forbid(unsafe_code)]
+#![forbid(warnings)]
+
+mod context;
+mod run;
+
+use self::context::Context;
+use self::run::run_geiger_with;
+
+use assert_cmd::prelude::*;
+use cargo_geiger_serde::{QuickSafetyReport, SafetyReport};
+use rstest::rstest;
+use std::collections::BTreeSet;
+use std::process::Command;
+
+/// The packages in the report are those `cargo tree` lists for the same
+/// dependency kinds, platforms and features
+#[rstest(
+    name,
+    case("test1_package_with_no_deps"),
+    case("test2_package_with_shallow_deps"),
+    case("test3_package_with_nested_deps"),
+    case("test4_workspace_with_top_level_package"),
+    case("test7_package_with_patched_dep"),
+    case("test8_package_with_build_rs_no_deps"),
+    case("test10_package_with_target_deps")
+)]
+fn report_packages_match_cargo_tree(name: &str) {
+    let (output, cx) =
+        run_geiger_with(name, &["--forbid-only", "--output-format", "Json"]);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<QuickSafetyReport>(&output.stdout).unwrap();
+    let report_packages = report
+        .packages
+        .keys()
+        .chain(&report.packages_without_metrics)
+        .map(|package_id| format!("{} {}", package_id.name, package_id.version))
+        .collect::<BTreeSet<String>>();
+
+    let cargo_tree_output = Command::new(env!("CARGO"))
+        .args([
+            "tree",
+            "--edges",
+            "normal",
+            "--target",
+            "all",
+            "--all-features",
+            "--prefix",
+            "none",
+            "--format",
+            "{p}",
+        ])
+        .current_dir(cx.crate_dir(name))
+        .output()
+        .expect("failed to run `cargo tree`");
+    assert!(cargo_tree_output.status.success());
+    let cargo_tree_packages = String::from_utf8(cargo_tree_output.stdout)
+        .expect("output should have been valid utf-8")
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(name), Some(version)) => Some(format!(
+                    "{} {}",
+                    name,
+                    version.trim_start_matches('v')
+                )),
+                _ => None,
+            }
+        })
+        .collect::<BTreeSet<String>>();
+
+    assert_eq!(report_packages, cargo_tree_packages);
+}
+
+/// The packages and dependencies in the report are those `cargo tree` lists
+/// for a single target, with dependencies limited to some platforms, a
+/// proc-macro built for the host and build dependencies
+#[rstest(
+    name,
+    target,
+    case("test10_package_with_target_deps", "x86_64-unknown-linux-gnu"),
+    case("test10_package_with_target_deps", "x86_64-pc-windows-msvc")
+)]
+fn report_edges_match_cargo_tree_for_target(name: &str, target: &str) {
+    // `run_geiger_with` passes `--all-targets`, which would match every
+    // platform instead of `target`.
+    let cx = Context::new();
+    let output = Command::cargo_bin("cargo-geiger")
+        .unwrap()
+        .args([
+            "geiger",
+            "--color=never",
+            "--quiet",
+            "--forbid-only",
+            "--build-dependencies",
+            "--output-format",
+            "Json",
+            "--target",
+            target,
+        ])
+        .current_dir(cx.crate_dir(name))
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<QuickSafetyReport>(&output.stdout).unwrap();
+    let report_packages = report
+        .packages
+        .keys()
+        .chain(&report.packages_without_metrics)
+        .map(|package_id| package_id.name.clone())
+        .collect::<BTreeSet<String>>();
+    let report_edges = report
+        .packages
+        .values()
+        .flat_map(|entry| {
+            entry
+                .package
+                .dependencies
+                .iter()
+                .chain(&entry.package.build_dependencies)
+                .map(move |dependency| {
+                    (entry.package.id.name.clone(), dependency.name.clone())
+                })
+        })
+        .collect::<BTreeSet<(String, String)>>();
+
+    let cargo_tree_output = Command::new(env!("CARGO"))
+        .args([
+            "tree",
+            "--edges",
+            "normal,build",
+            "--target",
+            target,
+            "--prefix",
+            "depth",
+            "--format",
+            "{p}",
+        ])
+        .current_dir(cx.crate_dir(name))
+        .output()
+        .expect("failed to run `cargo tree`");
+    assert!(cargo_tree_output.status.success());
+    // Each line is the depth followed by the package, whose parent is the
+    // last package listed one level up.
+    let mut cargo_tree_packages = BTreeSet::new();
+    let mut cargo_tree_edges = BTreeSet::new();
+    let mut ancestors = Vec::<String>::new();
+    for line in String::from_utf8(cargo_tree_output.stdout)
+        .expect("output should have been valid utf-8")
+        .lines()
+    {
+        let name_start = match line.find(|c: char| !c.is_ascii_digit()) {
+            Some(name_start) if name_start > 0 => name_start,
+            _ => continue,
+        };
+        let depth = line[..name_start].parse::<usize>().unwrap();
+        let name = line[name_start..]
+            .split_whitespace()
+            .next()
+            .unwrap()
+            .to_string();
+        ancestors.truncate(depth);
+        if let Some(parent) = ancestors.last() {
+            cargo_tree_edges.insert((parent.clone(), name.clone()));
+        }
+        cargo_tree_packages.insert(name.clone());
+        ancestors.push(name);
+    }
+
+    assert_eq!(report_packages, cargo_tree_packages);
+    assert_eq!(report_edges, cargo_tree_edges);
+}
+
+/// The optional dependency on a package with a custom `[lib]` name is
+/// reported with the feature activating it
+#[rstest]
+fn report_dependency_conditions_of_custom_lib_name() {
+    let (output, _cx) = run_geiger_with(
+        "test10_package_with_target_deps",
+        &["--output-format", "Json"],
+    );
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let root_entry = report
+        .packages
+        .values()
+        .find(|entry| {
+            entry.package.id.name == "test10_package_with_target_deps"
+        })
+        .unwrap();
+    let dependency_condition = root_entry
+        .package
+        .dependency_conditions
+        .iter()
+        .find(|dependency_condition| {
+            dependency_condition.dependency.name == "custom_lib_name_dep"
+        })
+        .unwrap();
+
+    assert_eq!(
+        dependency_condition.features,
+        vec![String::from("with_custom_lib")]
+    );
+}
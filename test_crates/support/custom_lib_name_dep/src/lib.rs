@@ -0,0 +1,4 @@
+pub fn custom_lib() -> u8 {
+    let x = [1u8];
+    unsafe { *x.get_unchecked(0) }
+}
@@ -0,0 +1,4 @@
+pub fn windows_only() -> u8 {
+    let x = [2u8];
+    unsafe { *x.get_unchecked(0) }
+}
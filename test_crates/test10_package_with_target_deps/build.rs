@@ -0,0 +1,3 @@
+fn main() {
+    build_only_dep::build_only();
+}
@@ -0,0 +1,5 @@
+#[derive(derive_dep::Nothing)]
+pub struct S;
+
+#[cfg(feature = "with_custom_lib")]
+pub use custom_lib::custom_lib;